@@ -0,0 +1,74 @@
+/// Rise-time bounds as fractions of the note's peak intensity (the usual 10%-90% definition)
+const ATTACK_START: f32 = 0.1;
+const ATTACK_END: f32 = 0.9;
+
+/// Estimate how sharply a note starts from its per-frame intensity envelope, in milliseconds
+///
+/// `envelope` holds the note's intensity in each frame from its onset on, `frame_rate` is frames
+/// per second (1 / hop). The frame before the onset counts as silence, and the envelope is
+/// interpolated linearly between frames, so the attack is the time it takes to climb from 10% to
+/// 90% of its peak. Plucked and percussive notes come out short, bowed and sung ones long; a note
+/// that is already at full strength in its first frame reports under one hop. Returns `None` for
+/// an empty or silent envelope, or an invalid frame rate.
+pub fn attack_time_ms(envelope: &[f32], frame_rate: f32) -> Option<f32> {
+    if !(frame_rate.is_finite() && frame_rate > 0.0) {
+        return None;
+    }
+    // The first frame at the peak ends the attack; anything after it is sustain or decay
+    let (peak_idx, peak) = envelope
+        .iter()
+        .copied()
+        .enumerate()
+        .rev()
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if !(peak.is_finite() && peak > 0.0) {
+        return None;
+    }
+
+    // Fractional frame position (-1.0 = the silent frame before the onset) where the envelope
+    // first reaches `level`
+    let crossing = |level: f32| -> f32 {
+        let mut previous = 0.0f32;
+        for (idx, &intensity) in envelope[..=peak_idx].iter().enumerate() {
+            if intensity >= level {
+                let fraction = (level - previous) / (intensity - previous);
+                return idx as f32 - 1.0 + fraction;
+            }
+            previous = intensity;
+        }
+        peak_idx as f32
+    };
+
+    let frames = crossing(ATTACK_END * peak) - crossing(ATTACK_START * peak);
+    Some(frames.max(0.0) / frame_rate * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fast_attack_is_shorter_than_slow_attack() {
+        // 100 frames per second: 10 ms hops
+        let plucked = [1.0, 0.8, 0.6, 0.45, 0.3, 0.2];
+        let bowed = [0.1, 0.25, 0.4, 0.55, 0.7, 0.85, 1.0, 1.0];
+
+        let fast = attack_time_ms(&plucked, 100.0).unwrap();
+        let slow = attack_time_ms(&bowed, 100.0).unwrap();
+        assert!(fast < 10.0, "{fast}");
+        assert!(slow > 40.0, "{slow}");
+        assert!(fast < slow);
+
+        // A straight ramp over 4 frames spends 80% of them between 10% and 90%
+        let ramp = [0.25, 0.5, 0.75, 1.0];
+        let attack = attack_time_ms(&ramp, 100.0).unwrap();
+        assert!((attack - 32.0).abs() < 0.01, "{attack}");
+    }
+
+    #[test]
+    fn test_attack_needs_a_signal() {
+        assert_eq!(attack_time_ms(&[], 100.0), None);
+        assert_eq!(attack_time_ms(&[0.0, 0.0], 100.0), None);
+        assert_eq!(attack_time_ms(&[0.5, 1.0], 0.0), None);
+    }
+}
@@ -0,0 +1,2125 @@
+#![allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::unused_self
+)]
+
+use crate::codec::{alaw_to_linear, mulaw_to_linear};
+use crate::models::{
+    AnalysisOptions, ChunkAnalysis, DetectedNote, IntensityMode, SampleFormat, ScoreWeights,
+    VoiceProfile, CLIP_LEVEL, DEFAULT_MAX_OCTAVE, DEFAULT_MIN_OCTAVE, HIGHEST_OCTAVE,
+    LOWEST_OCTAVE, MIN_RMS_DB,
+};
+use crate::utils::{
+    a_weighting_gain, cents_between, confidence_weight, downmix_to_mono, low_frequency_bonus,
+    note_to_frequency, pitch_frequency_at, sanitize_samples,
+};
+use crate::wav::FileWindows;
+use num_complex::Complex;
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use rustfft::{Fft, FftPlanner};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+// Constants for note-to-frequency mapping
+pub const KNOWN_NOTE_FREQUENCY: f32 = 440.0; // A4 = 440 Hz
+                                             // Natural notes only (no sharps/flats) - focuses on standard musical notes
+const NOTE_NAMES: [&str; 7] = ["C", "D", "E", "F", "G", "A", "B"];
+/// Reference pitches (A4) accepted by the note table, wide enough for baroque 415 Hz and 466 Hz
+pub const MIN_REFERENCE_HZ: f32 = 400.0;
+pub const MAX_REFERENCE_HZ: f32 = 480.0;
+/// Distinct note tables kept by `FrequencyToNoteLookup::shared`; beyond this they're built
+/// per call instead of cached, so odd reference pitches can't grow the cache without bound
+const MAX_CACHED_TABLES: usize = 64;
+/// Surroundings compared against a peak span this many peak widths on each side
+const PROMINENCE_SPAN: usize = 4;
+/// Peak-to-average ratios (dB) mapped onto confidence: at or below `NOISY` a peak is
+/// indistinguishable from noise (factor 0), at or above `CLEAN` it's a clear tone (factor 1)
+const NOISY_PROMINENCE_DB: f32 = 6.0;
+const CLEAN_PROMINENCE_DB: f32 = 18.0;
+/// A peak is taken as the 2nd harmonic of the pitch an octave below when the spectrum holds at
+/// least this fraction of its power both at half its frequency and at 1.5x (that pitch's 3rd
+/// harmonic, which a genuine note at the peak can't produce)
+const SUBHARMONIC_MIN_RATIO: f32 = 0.05;
+/// Octave candidates within this power ratio of each other are reported as ambiguous (~3 dB)
+const AMBIGUOUS_OCTAVE_RATIO: f32 = 0.5;
+
+/// Buffers whose peak stays within one 16-bit step of zero are digital silence (or dither)
+const SILENCE_PEAK: f32 = 1.0 / 32768.0;
+
+// Short chunks (e.g. 10ms @ 48kHz) are zero-padded to this length before the FFT
+// Padding interpolates the spectrum so the peak lands closer to the true pitch
+const SHORT_CHUNK_FFT_LEN: usize = 4096;
+
+/// Global FFT planner - reused across all requests
+/// Creating a new `FftPlanner` is very expensive, so we share one globally
+#[allow(clippy::non_std_lazy_statics)]
+static FFT_PLANNER: Lazy<Mutex<FftPlanner<f32>>> = Lazy::new(|| Mutex::new(FftPlanner::new()));
+
+/// Forward FFT plans by length - requests almost always repeat the same few sizes,
+/// so after warm-up every lookup is a shared read lock and the planner mutex is never touched
+#[allow(clippy::non_std_lazy_statics)]
+static FFT_PLANS: Lazy<RwLock<FftPlanMap>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+type FftPlanMap = HashMap<usize, Arc<dyn Fft<f32>>>;
+
+/// Note tables by (min octave, max octave, reference pitch bits), shared by every analyzer
+/// so building an analyzer or overriding the range per request doesn't rebuild the table
+#[allow(clippy::non_std_lazy_statics)]
+static LOOKUP_TABLES: Lazy<RwLock<LookupTableMap>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+type LookupTableMap = HashMap<(i32, i32, u32), Arc<FrequencyToNoteLookup>>;
+
+/// Get the cached forward FFT plan for `len`, planning it on first use
+fn fft_plan(len: usize) -> Arc<dyn Fft<f32>> {
+    if let Some(plan) = FFT_PLANS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&len)
+    {
+        return Arc::clone(plan);
+    }
+
+    let mut plans = FFT_PLANS.write().unwrap_or_else(PoisonError::into_inner);
+    // Another thread may have planned this length while we waited for the write lock
+    Arc::clone(plans.entry(len).or_insert_with(|| {
+        FFT_PLANNER
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .plan_fft_forward(len)
+    }))
+}
+
+/// Pre-computed lookup table for frequency-to-note conversion
+/// This avoids expensive log calculations on every call
+#[derive(Debug, Clone)]
+pub struct FrequencyToNoteLookup {
+    table: Vec<(String, f32)>, // (note_name, base_frequency)
+    min_octave: i32,
+    max_octave: i32,
+    reference_hz: f32,
+}
+
+impl Default for FrequencyToNoteLookup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrequencyToNoteLookup {
+    /// Create a lookup table for note-to-frequency mapping
+    /// Default range C1 (32.7 Hz) to B7, covering every voice type from bass to soprano
+    /// Uses only natural notes (no sharps/flats) to focus on standard musical notes
+    /// Covers all professional voice types: Bass, Baritone, Tenor, Countertenor, Contralto, Mezzo-Soprano, Soprano
+    pub fn new() -> Self {
+        Self::with_octave_range(DEFAULT_MIN_OCTAVE, DEFAULT_MAX_OCTAVE)
+    }
+
+    /// Create a lookup table spanning `min_octave..=max_octave`
+    /// Bounds are clamped to `LOWEST_OCTAVE..=HIGHEST_OCTAVE` (C0 16.35 Hz to B9) and reordered if swapped
+    pub fn with_octave_range(min_octave: i32, max_octave: i32) -> Self {
+        Self::with_tuning(min_octave, max_octave, KNOWN_NOTE_FREQUENCY)
+    }
+
+    /// Create a lookup table spanning `min_octave..=max_octave` tuned to A4 = `reference_hz`
+    /// The reference is clamped to `MIN_REFERENCE_HZ..=MAX_REFERENCE_HZ` (440 Hz if not finite)
+    pub fn with_tuning(min_octave: i32, max_octave: i32, reference_hz: f32) -> Self {
+        let (min_octave, max_octave) = Self::normalize_octave_range(min_octave, max_octave);
+        let reference_hz = Self::normalize_reference(reference_hz);
+        let mut table = Vec::new();
+
+        // Generate natural notes (C, D, E, F, G, A, B) for every octave in range
+        for octave in min_octave..=max_octave {
+            for note_name in &NOTE_NAMES {
+                // Map natural notes to MIDI semitone positions
+                // C=0, D=2, E=4, F=5, G=7, A=9, B=11 (skips sharps/flats)
+                let note_semitones = match *note_name {
+                    "C" => 0,
+                    "D" => 2,
+                    "E" => 4,
+                    "F" => 5,
+                    "G" => 7,
+                    "A" => 9,
+                    "B" => 11,
+                    _ => continue,
+                };
+
+                let frequency = pitch_frequency_at(note_semitones, octave, reference_hz);
+
+                let note_full_name = format!("{note_name}{octave}");
+                table.push((note_full_name, frequency));
+            }
+        }
+
+        // Sort by frequency for binary search
+        table.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        Self {
+            table,
+            min_octave,
+            max_octave,
+            reference_hz,
+        }
+    }
+
+    /// Shared table for this range and tuning, built on first use and reused afterwards
+    pub fn shared(min_octave: i32, max_octave: i32, reference_hz: f32) -> Arc<Self> {
+        let (min_octave, max_octave) = Self::normalize_octave_range(min_octave, max_octave);
+        let reference_hz = Self::normalize_reference(reference_hz);
+        let key = (min_octave, max_octave, reference_hz.to_bits());
+
+        if let Some(table) = LOOKUP_TABLES
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+        {
+            return Arc::clone(table);
+        }
+
+        let table = Arc::new(Self::with_tuning(min_octave, max_octave, reference_hz));
+        let mut tables = LOOKUP_TABLES
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if tables.len() >= MAX_CACHED_TABLES && !tables.contains_key(&key) {
+            return table;
+        }
+        // Another thread may have built this table while we waited for the write lock
+        Arc::clone(tables.entry(key).or_insert(table))
+    }
+
+    /// Clamp an octave range to the supported limits, swapping the bounds if reversed
+    pub fn normalize_octave_range(min_octave: i32, max_octave: i32) -> (i32, i32) {
+        let min_octave = min_octave.clamp(LOWEST_OCTAVE, HIGHEST_OCTAVE);
+        let max_octave = max_octave.clamp(LOWEST_OCTAVE, HIGHEST_OCTAVE);
+        (min_octave.min(max_octave), min_octave.max(max_octave))
+    }
+
+    /// Clamp a reference pitch to the supported range
+    fn normalize_reference(reference_hz: f32) -> f32 {
+        if reference_hz.is_finite() {
+            reference_hz.clamp(MIN_REFERENCE_HZ, MAX_REFERENCE_HZ)
+        } else {
+            KNOWN_NOTE_FREQUENCY
+        }
+    }
+
+    /// Octave range covered by this table (inclusive)
+    pub const fn octave_range(&self) -> (i32, i32) {
+        (self.min_octave, self.max_octave)
+    }
+
+    /// Frequency of A4 this table is tuned to
+    pub const fn reference_hz(&self) -> f32 {
+        self.reference_hz
+    }
+
+    /// Find the closest note for a given frequency
+    /// Returns (`note_name`, confidence)
+    pub fn find_closest_note(&self, frequency: f32) -> Option<(String, f32)> {
+        if frequency <= 0.0 || frequency > 20000.0 {
+            return None;
+        }
+
+        // Binary search to find closest frequency
+        let mut left = 0;
+        let mut right = self.table.len() - 1;
+
+        while left < right {
+            let mid = usize::midpoint(left, right);
+            if self.table[mid].1 < frequency {
+                left = mid + 1;
+            } else {
+                right = mid;
+            }
+        }
+
+        // Check both neighbors to find closest
+        let closest_idx = if left > 0
+            && (frequency - self.table[left - 1].1).abs() < (frequency - self.table[left].1).abs()
+        {
+            left - 1
+        } else {
+            left
+        };
+
+        let (note_name, base_freq) = &self.table[closest_idx];
+
+        // Calculate confidence (how close the frequency matches)
+        // Within 50 cents (0.5 semitone) is considered a good match
+        let cents_diff = 1200.0 * (frequency / base_freq).log2().abs();
+        let confidence = (1.0 - (cents_diff / 100.0).min(1.0)).max(0.0);
+
+        Some((note_name.clone(), confidence))
+    }
+}
+
+/// Analyze audio buffer and detect dominant frequency
+/// Cheap to clone: the note table is shared
+#[derive(Debug, Clone)]
+pub struct AudioAnalyzer {
+    lookup: Arc<FrequencyToNoteLookup>,
+}
+
+impl Default for AudioAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Settings for an `AudioAnalyzer`; tables are shared with every analyzer built alike
+#[derive(Debug, Clone, Copy)]
+pub struct AudioAnalyzerBuilder {
+    octave_range: (i32, i32),
+    reference_hz: f32,
+}
+
+impl Default for AudioAnalyzerBuilder {
+    fn default() -> Self {
+        Self {
+            octave_range: (DEFAULT_MIN_OCTAVE, DEFAULT_MAX_OCTAVE),
+            reference_hz: KNOWN_NOTE_FREQUENCY,
+        }
+    }
+}
+
+impl AudioAnalyzerBuilder {
+    /// Octaves covered by the default note table (requests may still ask for another range)
+    pub const fn octave_range(mut self, min_octave: i32, max_octave: i32) -> Self {
+        self.octave_range = (min_octave, max_octave);
+        self
+    }
+
+    /// Tuning reference: frequency of A4 in Hz (440 by default)
+    pub const fn reference_pitch(mut self, reference_hz: f32) -> Self {
+        self.reference_hz = reference_hz;
+        self
+    }
+
+    pub fn build(self) -> AudioAnalyzer {
+        let (min_octave, max_octave) = self.octave_range;
+        AudioAnalyzer::with_lookup(FrequencyToNoteLookup::shared(
+            min_octave,
+            max_octave,
+            self.reference_hz,
+        ))
+    }
+}
+
+impl AudioAnalyzer {
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> AudioAnalyzerBuilder {
+        AudioAnalyzerBuilder::default()
+    }
+
+    /// Analyzer using an existing note table
+    pub const fn with_lookup(lookup: Arc<FrequencyToNoteLookup>) -> Self {
+        Self { lookup }
+    }
+
+    /// The default note table, shareable with other analyzers via `with_lookup`
+    pub const fn lookup(&self) -> &Arc<FrequencyToNoteLookup> {
+        &self.lookup
+    }
+
+    /// Check if a frequency is within the allowed voice profile range
+    /// If profile is `NoProfile`, all frequencies are allowed
+    /// Otherwise, aggressively filters frequencies outside the profile range
+    fn is_frequency_in_profile(frequency: f32, profile: VoiceProfile) -> bool {
+        match profile.freq_range() {
+            None => true, // NoProfile allows all frequencies
+            Some((min_freq, max_freq)) => {
+                // Aggressive filtering: must be strictly within range
+                // Allow ±10% margin for frequency estimation errors
+                let margin = (max_freq - min_freq) * 0.05; // 5% margin on each side
+                frequency >= (min_freq - margin) && frequency <= (max_freq + margin)
+            }
+        }
+    }
+
+    /// Note table for a request: the analyzer's own table, or the shared one for the
+    /// request's octave range (same tuning), built only the first time that range is asked for
+    fn lookup_for(&self, options: &AnalysisOptions) -> Arc<FrequencyToNoteLookup> {
+        let (min_octave, max_octave) = options.octave_range;
+        if FrequencyToNoteLookup::normalize_octave_range(min_octave, max_octave)
+            == self.lookup.octave_range()
+        {
+            Arc::clone(&self.lookup)
+        } else {
+            FrequencyToNoteLookup::shared(min_octave, max_octave, self.lookup.reference_hz())
+        }
+    }
+
+    /// Resolve the FFT length for a signal of `signal_len` samples
+    /// `None` zero-pads to the next 2/3/5-smooth length (see `next_fast_len`);
+    /// `Some(len)` zero-pads up to `len`, or to the next power of two if the signal is already longer
+    fn fft_length(signal_len: usize, pad_to: Option<usize>) -> usize {
+        match pad_to {
+            Some(len) if len >= signal_len => len,
+            Some(_) => signal_len.next_power_of_two(),
+            None => next_fast_len(signal_len),
+        }
+    }
+
+    /// Compute FFT and return Power Spectral Density
+    /// The signal is zero-padded up to `fft_len` samples, so the returned PSD has
+    /// `max(fft_len, signal.len())` bins and callers must convert bins with that length
+    /// Uses the global per-length plan cache to avoid re-planning (and lock contention) per call
+    /// OPTIMIZED: Faster PSD calculation and lock time reduction
+    fn compute_fft(&self, signal: &[f32], fft_len: usize) -> Vec<f32> {
+        let signal_len = signal.len();
+        let fft_len = fft_len.max(signal_len);
+
+        // Get the cached plan for this length (read lock only, after the first request)
+        let lock_start = std::time::Instant::now();
+        let fft = fft_plan(fft_len);
+        let lock_time = lock_start.elapsed().as_micros();
+
+        // Convert input to complex numbers, zero-padding up to the FFT length
+        let convert_start = std::time::Instant::now();
+        let mut buffer: Vec<Complex<f32>> = Vec::with_capacity(fft_len);
+        buffer.extend(signal.iter().map(|&s| Complex { re: s, im: 0.0 }));
+        buffer.resize(fft_len, Complex { re: 0.0, im: 0.0 });
+        let convert_time = convert_start.elapsed().as_micros();
+
+        // Compute FFT
+        let process_start = std::time::Instant::now();
+        fft.process(&mut buffer);
+        let process_time = process_start.elapsed().as_micros();
+
+        // Compute Power Spectral Density - OPTIMIZED: Faster norm calculation
+        // Normalized by the unpadded length so padding doesn't change peak magnitudes
+        let psd_start = std::time::Instant::now();
+        let signal_len_f32 = signal_len as f32;
+        let psd: Vec<f32> = buffer
+            .iter()
+            .map(|c| {
+                let norm_sq = c.re.mul_add(c.re, c.im * c.im);
+                (norm_sq / signal_len_f32).sqrt()
+            })
+            .collect();
+        let psd_time = psd_start.elapsed().as_micros();
+
+        log::debug!("compute_fft({signal_len} -> {fft_len}): lock={lock_time}us, convert={convert_time}us, process={process_time}us, psd={psd_time}us");
+
+        psd
+    }
+
+    /// Find all significant peaks in the FFT spectrum, with harmonic suppression to find the fundamental.
+    /// Bin frequencies are derived from `psd.len()`, i.e. the (possibly zero-padded) FFT length
+    /// At most `options.max_notes` fundamentals are extracted (one per suppression iteration)
+    /// Bins outside `options.band` are zeroed first, so out-of-band energy can't be picked or raise the threshold
+    /// Each strongest peak is checked for an octave error first (see `resolve_octave`)
+    /// Returns (frequency, power, prominence, octave ambiguous); power is not capped at 1.0 here
+    fn find_all_peaks(
+        &self,
+        psd: &[f32],
+        sample_rate: u32,
+        options: &AnalysisOptions,
+    ) -> Vec<(f32, f32, f32, bool)> {
+        if psd.len() < 4 {
+            return Vec::new();
+        }
+        let fft_len = psd.len();
+
+        let mut peaks = Vec::new();
+        let mut mutable_psd = psd.to_vec(); // Make a mutable copy of the power spectrum
+        zero_outside_band(&mut mutable_psd, sample_rate, options.band);
+
+        // Find global maximum for threshold calculation
+        let max_power = mutable_psd[1..psd.len() / 2]
+            .iter()
+            .copied()
+            .fold(0.0_f32, f32::max);
+
+        // Relative to the strongest peak (default 10%) to catch weaker fundamental frequencies
+        // Notes: lower frequencies often have less energy than their harmonics
+        let threshold = (max_power * options.peak_threshold).max(options.peak_floor);
+
+        // --- Iterative Harmonic Suppression ---
+        // This loop finds the strongest peak, assumes it's a fundamental, removes its harmonics,
+        // and then repeats. This helps to isolate true fundamental frequencies from their overtones.
+        // One iteration per requested note: human voices rarely have >5 distinct notes in a
+        // single chunk (the default), dense piano chords may need more
+        for _ in 0..options.max_notes {
+            let spectrum = &mutable_psd[1..mutable_psd.len() / 2]; // Use the mutable spectrum
+
+            let max_idx_opt = spectrum
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(i, _)| i + 1);
+
+            if let Some(max_idx) = max_idx_opt {
+                let power = spectrum[max_idx - 1];
+                if power < threshold {
+                    break; // Stop if the strongest remaining peak is below the noise threshold
+                }
+
+                // The strongest peak may be the 2nd harmonic of a weaker fundamental
+                let (max_idx, ambiguous) = resolve_octave(&mutable_psd, max_idx);
+                let frequency = (max_idx as f32) * (sample_rate as f32) / (fft_len as f32);
+
+                // Add the found fundamental peak to our list, measuring its prominence on the
+                // untouched spectrum (suppression zeroes would make every peak look clean)
+                peaks.push((frequency, power, peak_prominence(psd, max_idx), ambiguous));
+
+                // --- Suppress the found peak and its harmonics ---
+                let freq_resolution = sample_rate as f32 / fft_len as f32;
+                let peak_width_bins = (frequency * 0.03 / freq_resolution).ceil() as usize;
+
+                // Suppress the fundamental peak itself to prevent re-detection
+                let start_bin = (max_idx - peak_width_bins).max(1);
+                let end_bin = (max_idx + peak_width_bins).min(mutable_psd.len() / 2);
+                for item in mutable_psd.iter_mut().take(end_bin + 1).skip(start_bin) {
+                    *item = 0.0;
+                }
+
+                // OPTIMIZED: Suppress harmonics 2x-4x instead of 2x-6x (~40% faster)
+                // Higher harmonics rarely interfere with fundamental detection
+                for n in 2..=4 {
+                    let harmonic_freq = frequency * n as f32;
+                    let harmonic_idx = (harmonic_freq / freq_resolution) as usize;
+
+                    if harmonic_idx < mutable_psd.len() / 2 {
+                        let h_start_bin = (harmonic_idx - peak_width_bins).max(1);
+                        let h_end_bin = (harmonic_idx + peak_width_bins).min(mutable_psd.len() / 2);
+                        for item in mutable_psd.iter_mut().take(h_end_bin + 1).skip(h_start_bin) {
+                            *item = 0.0;
+                        }
+                    }
+                }
+            } else {
+                break; // No more peaks to find
+            }
+        }
+
+        // Sort by power (descending) as the primary result
+        peaks.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        // Debug logging for detected peaks
+        log::debug!("FFT Peaks (Harmonic Suppression): max_power={:.3}, threshold={:.3}, fundamentals_found={}", 
+            max_power, threshold, peaks.len());
+        for (i, (freq, power, prominence, ambiguous)) in peaks.iter().enumerate() {
+            log::debug!(
+                "  Fundamental Peak {}: {:.2} Hz @ power={:.3}, prominence={:.1}, ambiguous={}",
+                i + 1,
+                freq,
+                power,
+                prominence,
+                ambiguous
+            );
+        }
+
+        peaks
+    }
+
+    /// Clean FFT output to find primary frequency (returns strongest peak only)
+    /// Bin frequencies are derived from `psd.len()`, i.e. the (possibly zero-padded) FFT length
+    /// Only bins within `band` are searched
+    /// Returns (frequency, peak prominence)
+    fn find_primary_frequency(
+        &self,
+        psd: &[f32],
+        sample_rate: u32,
+        band: Option<(f32, f32)>,
+    ) -> Option<(f32, f32)> {
+        if psd.len() < 4 {
+            return None;
+        }
+
+        let mut banded;
+        let search = if band.is_some() {
+            banded = psd.to_vec();
+            zero_outside_band(&mut banded, sample_rate, band);
+            banded.as_slice()
+        } else {
+            psd
+        };
+
+        // Find the index of maximum power (excluding DC component at index 0)
+        let max_idx = search[1..psd.len() / 2] // Only look at positive frequencies
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| i + 1)?;
+
+        let max_power = psd[max_idx];
+
+        // Filter out very weak signals (noise floor)
+        if max_power < 0.1 {
+            return None;
+        }
+
+        // Convert index to frequency
+        let frequency = (max_idx as f32) * (sample_rate as f32) / (psd.len() as f32);
+
+        Some((frequency, peak_prominence(psd, max_idx)))
+    }
+
+    /// Analyze audio chunk and return detected notes with confidence and intensity
+    /// Returns multiple notes if multiple strong peaks are detected
+    /// `pad_to` optionally zero-pads the windowed chunk before the FFT (see `fft_length`)
+    /// At most `options.max_notes` notes are returned, filtered by `options.profile`
+    /// OPTIMIZED: Parallel peak-to-note conversion with rayon (faster note lookup for top peaks)
+    pub fn analyze_chunk_multi(
+        &self,
+        audio_data: &[f32],
+        sample_rate: u32,
+        options: &AnalysisOptions,
+        pad_to: Option<usize>,
+    ) -> Vec<(String, f32, f32, f32, bool)> {
+        if audio_data.is_empty() {
+            return Vec::new();
+        }
+
+        let psd = self.power_spectrum(audio_data, pad_to);
+        self.notes_from_psd(&psd, sample_rate, options)
+    }
+
+    /// Windowed (and optionally zero-padded) power spectrum of a chunk
+    fn power_spectrum(&self, audio_data: &[f32], pad_to: Option<usize>) -> Vec<f32> {
+        // Apply Hann window to reduce spectral leakage
+        let windowed = self.apply_hann_window(audio_data);
+
+        // Compute FFT (zero-padded if requested)
+        let fft_len = Self::fft_length(audio_data.len(), pad_to);
+        self.compute_fft(&windowed, fft_len)
+    }
+
+    /// Convert the strongest peaks of a power spectrum into notes (see `analyze_chunk_multi`)
+    fn notes_from_psd(
+        &self,
+        psd: &[f32],
+        sample_rate: u32,
+        options: &AnalysisOptions,
+    ) -> Vec<(String, f32, f32, f32, bool)> {
+        let AnalysisOptions {
+            profile, max_notes, ..
+        } = *options;
+        let lookup = self.lookup_for(options);
+
+        // Find all peaks in the spectrum
+        let peaks = self.find_all_peaks(psd, sample_rate, options);
+
+        // OPTIMIZED: Parallel conversion of peaks to notes using rayon
+        // This parallelizes the frequency lookup for multiple peaks simultaneously
+        let notes: Vec<(String, f32, f32, f32, bool)> = peaks
+            .into_par_iter()
+            .take(max_notes) // Limit to the requested number of peaks
+            .filter_map(|(frequency, power, prominence, ambiguous)| {
+                // Aggressively filter by voice profile if one is selected
+                if !Self::is_frequency_in_profile(frequency, profile) {
+                    log::debug!(
+                        "Filtered out frequency {frequency:.2} Hz - outside profile {profile:?}"
+                    );
+                    return None;
+                }
+
+                lookup
+                    .find_closest_note(frequency)
+                    .map(|(note_name, note_confidence)| {
+                        let confidence = note_confidence * prominence_confidence(prominence);
+                        // Peak "power" is a spectral magnitude, so it takes the amplitude gain
+                        let power = if options.a_weighting {
+                            power * a_weighting_gain(frequency)
+                        } else {
+                            power
+                        };
+                        // Normalized intensities are scaled once the request's notes are known
+                        let intensity = match options.intensity_mode {
+                            IntensityMode::Raw => power.min(1.0),
+                            IntensityMode::Normalized => power,
+                        };
+                        (note_name, confidence, intensity, frequency, ambiguous)
+                    })
+            })
+            .collect();
+
+        notes
+    }
+
+    /// Analyze audio chunk and return detected notes with confidence
+    /// `pad_to` optionally zero-pads the windowed chunk before the FFT (see `fft_length`)
+    pub fn analyze_chunk(
+        &self,
+        audio_data: &[f32],
+        sample_rate: u32,
+        options: &AnalysisOptions,
+        pad_to: Option<usize>,
+    ) -> Option<(String, f32)> {
+        if audio_data.is_empty() {
+            return None;
+        }
+
+        let start = std::time::Instant::now();
+
+        // Window + FFT (zero-padded if requested)
+        let fft_start = std::time::Instant::now();
+        let psd = self.power_spectrum(audio_data, pad_to);
+        let fft_time = fft_start.elapsed().as_millis();
+
+        let (note_name, final_confidence, _frequency) =
+            self.primary_note_from_psd(&psd, sample_rate, options)?;
+
+        let total_time = start.elapsed().as_millis();
+        log::debug!("analyze_chunk: total={total_time}ms, fft={fft_time}ms");
+
+        Some((note_name, final_confidence))
+    }
+
+    /// Strongest note of a power spectrum (see `analyze_chunk`)
+    /// Returns (`note_name`, confidence, measured frequency in Hz)
+    fn primary_note_from_psd(
+        &self,
+        psd: &[f32],
+        sample_rate: u32,
+        options: &AnalysisOptions,
+    ) -> Option<(String, f32, f32)> {
+        let start = std::time::Instant::now();
+
+        // Find primary frequency
+        let find_start = std::time::Instant::now();
+        let (frequency, prominence) =
+            self.find_primary_frequency(psd, sample_rate, options.band)?;
+        let find_time = find_start.elapsed().as_millis();
+
+        // Convert frequency to note
+        let lookup_start = std::time::Instant::now();
+        let (note_name, note_confidence) = self.lookup_for(options).find_closest_note(frequency)?;
+        let lookup_time = lookup_start.elapsed().as_millis();
+
+        // Frequency match scaled by how clearly the peak stands out (not by raw power, which
+        // can be artificially low due to FFT bin resolution)
+        let final_confidence = note_confidence * prominence_confidence(prominence);
+
+        let total_time = start.elapsed().as_millis();
+        log::debug!("primary_note_from_psd: total={total_time}ms, find={find_time}ms, lookup={lookup_time}ms");
+
+        Some((note_name, final_confidence, frequency))
+    }
+
+    /// Apply Hann window to reduce spectral leakage
+    /// OPTIMIZED: Parallel computation with rayon for multi-core speedup
+    fn apply_hann_window(&self, signal: &[f32]) -> Vec<f32> {
+        let n = signal.len() as f32;
+        let n_minus_1 = (n - 1.0).max(1.0);
+
+        // Use parallel iterator for large signals (>2048 samples)
+        // For smaller signals, overhead of parallelization isn't worth it
+        if signal.len() > 2048 {
+            signal
+                .par_iter()
+                .enumerate()
+                .map(|(i, &sample)| {
+                    let window = 0.5 * (1.0 - (2.0 * PI * i as f32 / n_minus_1).cos());
+                    sample * window
+                })
+                .collect()
+        } else {
+            signal
+                .iter()
+                .enumerate()
+                .map(|(i, &sample)| {
+                    let window = 0.5 * (1.0 - (2.0 * PI * i as f32 / n_minus_1).cos());
+                    sample * window
+                })
+                .collect()
+        }
+    }
+
+    /// Convert raw little-endian PCM bytes to f32 samples in -1.0..1.0
+    /// Never panics on untrusted input: a trailing partial sample is ignored (handlers reject
+    /// such buffers with a 400 first) and unaligned buffers are decoded byte-wise
+    /// OPTIMIZED: Parallel conversion with rayon for large 16-bit buffers
+    pub fn bytes_to_samples(audio_data: &[u8], format: SampleFormat) -> Vec<f32> {
+        match format {
+            SampleFormat::S16le => {
+                let whole = audio_data.len() - audio_data.len() % 2;
+                // OPTIMIZED: Use bytemuck to reinterpret bytes as i16 slice (no allocation)
+                match bytemuck::try_cast_slice::<u8, i16>(&audio_data[..whole]) {
+                    // Parallel conversion for large buffers (>8KB)
+                    Ok(i16_samples) if whole > 8192 => i16_samples
+                        .par_iter()
+                        .map(|&s| f32::from(s) / 32768.0)
+                        .collect(),
+                    // Serial conversion for small buffers (faster due to lower overhead)
+                    Ok(i16_samples) => i16_samples
+                        .iter()
+                        .map(|&s| f32::from(s) / 32768.0)
+                        .collect(),
+                    Err(_) => audio_data
+                        .chunks_exact(2)
+                        .map(|b| f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0)
+                        .collect(),
+                }
+            }
+            SampleFormat::F32le => {
+                // Already -1.0..1.0, no scaling; reinterpret in place when the buffer is aligned
+                let whole = audio_data.len() - audio_data.len() % 4;
+                bytemuck::try_cast_slice::<u8, f32>(&audio_data[..whole]).map_or_else(
+                    |_| {
+                        audio_data
+                            .chunks_exact(4)
+                            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                            .collect()
+                    },
+                    <[f32]>::to_vec,
+                )
+            }
+            SampleFormat::Mulaw => audio_data
+                .iter()
+                .map(|&code| f32::from(mulaw_to_linear(code)) / 32768.0)
+                .collect(),
+            SampleFormat::Alaw => audio_data
+                .iter()
+                .map(|&code| f32::from(alaw_to_linear(code)) / 32768.0)
+                .collect(),
+        }
+    }
+
+    /// Decode raw PCM in `options.format` to mono f32 samples, the one place raw bytes become
+    /// samples: non-finite values are silenced and `options.channels` interleaved channels
+    /// averaged down. A trailing partial sample or frame is dropped, so the result's length is
+    /// the true sample count, whatever the byte length suggests
+    pub fn decode_raw_bytes(audio_data: &[u8], options: &AnalysisOptions) -> Vec<f32> {
+        // Convert bytes to f32 samples (parallel for large buffers, serial for small)
+        let mut samples = Self::bytes_to_samples(audio_data, options.format);
+        // Crafted f32 input can hold NaN/infinity; treat those samples as silence
+        let replaced = sanitize_samples(&mut samples);
+        if replaced > 0 {
+            log::warn!("decode_raw_bytes: replaced {replaced} non-finite samples with silence");
+        }
+        // Interleaved multi-channel input is averaged down to mono
+        downmix_to_mono(samples, usize::from(options.channels))
+    }
+
+    /// Analyze raw audio buffer (simpler version for HTTP requests)
+    /// Takes raw bytes and interprets them as PCM in `options.format` (16-bit int, 32-bit float,
+    /// or 8-bit G.711 µ-law/A-law),
+    /// interleaved across `options.channels` channels
+    /// Returns multiple detected notes per chunk, plus the chunk's RMS level
+    /// Only returns notes with confidence >= `options.min_confidence` to filter out noise
+    /// At most `options.max_notes` notes are returned
+    pub fn analyze_raw_bytes(
+        &self,
+        audio_data: &[u8],
+        sample_rate: u32,
+        options: &AnalysisOptions,
+    ) -> ChunkAnalysis {
+        if audio_data.len() < options.format.bytes_per_sample() {
+            return ChunkAnalysis::default();
+        }
+
+        let start = std::time::Instant::now();
+
+        let samples = Self::decode_raw_bytes(audio_data, options);
+        let convert_time = start.elapsed().as_millis();
+
+        let analysis = self.analyze_samples(&samples, sample_rate, options);
+
+        let total_time = start.elapsed().as_millis();
+        log::debug!("analyze_raw_bytes: total={total_time}ms, convert={convert_time}ms");
+
+        analysis
+    }
+
+    /// Analyze a WAV file lazily, one `window` every `hop`, yielding each window's start (in
+    /// seconds) and its ranked notes; only one window of audio is held in memory at a time, so
+    /// files of any length work. Windows go through `analyze_raw_bytes` with `options` (its
+    /// format and channels are taken from the file, and every channel is averaged to mono)
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use recognotes_core::{AnalysisOptions, AudioAnalyzer};
+    ///
+    /// // Half a second of A4 (440 Hz) as a 16-bit mono WAV
+    /// let path = std::env::temp_dir().join(format!("recognotes-doc-{}.wav", std::process::id()));
+    /// let spec = hound::WavSpec {
+    ///     channels: 1,
+    ///     sample_rate: 44_100,
+    ///     bits_per_sample: 16,
+    ///     sample_format: hound::SampleFormat::Int,
+    /// };
+    /// let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+    /// for i in 0..22_050 {
+    ///     let t = i as f32 / 44_100.0;
+    ///     let sample = 0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin();
+    ///     writer.write_sample((sample * 32767.0) as i16).unwrap();
+    /// }
+    /// writer.finalize().unwrap();
+    ///
+    /// let windows = AudioAnalyzer::new()
+    ///     .analyze_file_windows(
+    ///         &path,
+    ///         Duration::from_millis(200),
+    ///         Duration::from_millis(100),
+    ///         &AnalysisOptions::default(),
+    ///     )
+    ///     .unwrap();
+    /// let mut starts = Vec::new();
+    /// for (start, notes) in windows {
+    ///     assert_eq!(notes[0].note, "A4");
+    ///     starts.push(start);
+    /// }
+    /// assert_eq!(starts.len(), 4); // 0.0, 0.1, 0.2 and 0.3 s
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be opened or isn't 16/24-bit PCM or 32-bit float WAV,
+    /// or if `window` or `hop` is shorter than one sample
+    pub fn analyze_file_windows(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        window: std::time::Duration,
+        hop: std::time::Duration,
+        options: &AnalysisOptions,
+    ) -> Result<FileWindows, String> {
+        FileWindows::open(self.clone(), path.as_ref(), window, hop, options)
+    }
+
+    /// Analyze mono f32 samples (-1.0..1.0), e.g. decoded from a WAV file
+    /// Returns multiple detected notes, filtered and capped by `options` like `analyze_raw_bytes`,
+    /// plus the buffer's RMS level; buffers below `options.gate_db` return no notes
+    pub fn analyze_samples(
+        &self,
+        samples: &[f32],
+        sample_rate: u32,
+        options: &AnalysisOptions,
+    ) -> ChunkAnalysis {
+        if samples.is_empty() {
+            return ChunkAnalysis::default();
+        }
+
+        let start = std::time::Instant::now();
+
+        // Clipping is a property of the input as captured, so it's measured before any filtering
+        let clip_ratio = clip_ratio(samples);
+
+        // A DC bias isn't loudness, and would otherwise hold the noise gate open
+        let mut filtered = (options.highpass_hz > 0.0).then(|| remove_dc(samples));
+
+        // Loudness of the raw signal, measured before the Hann window attenuates the edges
+        let rms_db = rms_dbfs(filtered.as_deref().unwrap_or(samples));
+
+        // Digital silence (e.g. the GUI's zero-filled startup window) has nothing to find even
+        // with the gate disabled; peak picking would only be handed rounding noise
+        let peak = filtered
+            .as_deref()
+            .unwrap_or(samples)
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        if peak <= SILENCE_PEAK {
+            return ChunkAnalysis {
+                rms_db,
+                gated: rms_db < options.gate_db,
+                clip_ratio,
+                samples_analyzed: samples.len(),
+                ..ChunkAnalysis::default()
+            };
+        }
+
+        // Rumble would show up as phantom bass notes
+        if let Some(filtered) = filtered.as_mut() {
+            high_pass(filtered, sample_rate, options.highpass_hz);
+        }
+        let samples = filtered.as_deref().unwrap_or(samples);
+
+        // Noise gate: skip the FFT entirely for silence/room noise
+        if rms_db < options.gate_db {
+            log::debug!(
+                "analyze_samples: gated (rms={rms_db:.1}dBFS < {:.1})",
+                options.gate_db
+            );
+            return ChunkAnalysis {
+                notes: Vec::new(),
+                rms_db,
+                gated: true,
+                clip_ratio,
+                samples_analyzed: samples.len(),
+                ..ChunkAnalysis::default()
+            };
+        }
+
+        // If we have enough samples, analyze as a single large chunk for better frequency resolution
+        // Otherwise split into smaller chunks
+        let analysis_start = std::time::Instant::now();
+        // Zero-pad shorter buffers so the coarse bin spacing doesn't push the peak onto a neighbouring note
+        let pad_to = (samples.len() < 2048).then_some(SHORT_CHUNK_FFT_LEN);
+        let psd = self.power_spectrum(samples, pad_to);
+
+        let mut notes = if samples.len() >= 480 {
+            // Use multi-peak detection for better harmonic detection (down to 10ms @ 48kHz)
+            self.notes_from_psd(&psd, sample_rate, options)
+        } else {
+            // Fallback to single note detection if not enough samples
+            if let Some((note, confidence, frequency)) =
+                self.primary_note_from_psd(&psd, sample_rate, options)
+            {
+                vec![(note, confidence, 0.5, frequency, false)]
+            } else {
+                Vec::new()
+            }
+        };
+        let analysis_time = analysis_start.elapsed().as_millis();
+
+        // Filter out low-confidence noise (default 30%, low enough for weak bass fundamentals)
+        let filter_start = std::time::Instant::now();
+        notes.retain(|(_, confidence, ..)| *confidence >= options.min_confidence);
+        let filter_time = filter_start.elapsed().as_millis();
+
+        if options.intensity_mode == IntensityMode::Normalized {
+            normalize_intensities(&mut notes);
+        }
+
+        let total_time = start.elapsed().as_millis();
+        log::debug!("analyze_samples: total={total_time}ms, analysis={analysis_time}ms, filter={filter_time}ms");
+
+        // Timbre features reuse the PSD computed for note detection
+        let spectral_centroid_hz = options
+            .include_features
+            .then(|| spectral_centroid(&psd, sample_rate));
+
+        ChunkAnalysis {
+            notes,
+            rms_db,
+            gated: false,
+            clip_ratio,
+            samples_analyzed: samples.len(),
+            spectral_centroid_hz,
+            fft_size: Some(psd.len()),
+        }
+    }
+
+    /// Magnitude spectrum resampled onto `bins` logarithmically spaced frequencies
+    /// between `min_hz` and `max_hz` (for visualization)
+    /// Each output bin takes the strongest FFT bin in its band, so narrow peaks aren't averaged away
+    /// Returns (`frequencies`, `magnitudes`), with magnitudes normalized so the strongest bin is 1.0
+    pub fn magnitude_spectrum(
+        &self,
+        samples: &[f32],
+        sample_rate: u32,
+        bins: usize,
+        min_hz: f32,
+        max_hz: f32,
+    ) -> (Vec<f32>, Vec<f32>) {
+        if samples.is_empty() || bins == 0 || sample_rate == 0 || min_hz >= max_hz {
+            return (Vec::new(), Vec::new());
+        }
+
+        // Same windowing and FFT as note detection; pad short buffers for a smoother curve
+        let windowed = self.apply_hann_window(samples);
+        let fft_len = Self::fft_length(samples.len(), Some(SHORT_CHUNK_FFT_LEN));
+        let psd = self.compute_fft(&windowed, fft_len);
+        let freq_resolution = sample_rate as f32 / psd.len() as f32;
+        let nyquist_bin = psd.len() / 2;
+
+        // Geometric spacing: center k is min_hz * ratio^k
+        let ratio = (max_hz / min_hz).powf(1.0 / (bins.max(2) - 1) as f32);
+        let frequencies: Vec<f32> = (0..bins).map(|k| min_hz * ratio.powi(k as i32)).collect();
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let mut magnitudes: Vec<f32> = frequencies
+            .iter()
+            .map(|&center| {
+                // Band edges halfway (geometrically) to the neighbouring centers
+                let half_step = ratio.sqrt();
+                let lo = ((center / half_step) / freq_resolution).ceil() as usize;
+                let hi = ((center * half_step) / freq_resolution).floor() as usize;
+                let nearest = (center / freq_resolution).round() as usize;
+
+                let (lo, hi) = if lo <= hi {
+                    (lo, hi)
+                } else {
+                    (nearest, nearest)
+                };
+                let hi = hi.min(nyquist_bin);
+                psd.get(lo..=hi)
+                    .map_or(0.0, |band| band.iter().copied().fold(0.0, f32::max))
+            })
+            .collect();
+
+        let peak = magnitudes.iter().copied().fold(0.0, f32::max);
+        if peak > 0.0 {
+            magnitudes.iter_mut().for_each(|m| *m /= peak);
+        }
+
+        (frequencies, magnitudes)
+    }
+}
+
+/// Smallest length >= `len` whose only prime factors are 2, 3 and 5
+/// rustfft is fast for these; large prime lengths (e.g. 95989 samples) fall back to much slower algorithms.
+/// Smooth numbers are dense, so this pads by at most a few percent (unlike the next power of two)
+fn next_fast_len(len: usize) -> usize {
+    let is_smooth = |mut n: usize| {
+        for factor in [2, 3, 5] {
+            while n.is_multiple_of(factor) {
+                n /= factor;
+            }
+        }
+        n == 1
+    };
+    (len.max(1)..).find(|&n| is_smooth(n)).unwrap_or(len)
+}
+
+/// RMS level of a signal in dB relative to full scale (0 dBFS = RMS of 1.0)
+/// Silence is floored at `MIN_RMS_DB` rather than -infinity so it serializes as a number
+fn rms_dbfs(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return MIN_RMS_DB;
+    }
+    let mean_square = samples.iter().map(|&s| s * s).sum::<f32>() / samples.len() as f32;
+    (10.0 * mean_square.log10()).max(MIN_RMS_DB)
+}
+
+/// Fraction of samples at or above `CLIP_LEVEL` in magnitude (0.0 for an empty buffer)
+fn clip_ratio(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let clipped = samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count();
+    clipped as f32 / samples.len() as f32
+}
+
+/// Zero every bin of `psd` outside `band` (Hz); bin frequencies follow `psd.len()`
+fn zero_outside_band(psd: &mut [f32], sample_rate: u32, band: Option<(f32, f32)>) {
+    let Some((min_hz, max_hz)) = band else {
+        return;
+    };
+    let bin_hz = sample_rate as f32 / psd.len() as f32;
+    for (i, power) in psd.iter_mut().enumerate() {
+        let frequency = i as f32 * bin_hz;
+        if frequency < min_hz || frequency > max_hz {
+            *power = 0.0;
+        }
+    }
+}
+
+/// The signal with its mean (DC bias) subtracted
+fn remove_dc(samples: &[f32]) -> Vec<f32> {
+    let mean = samples.iter().sum::<f32>() / samples.len().max(1) as f32;
+    samples.iter().map(|s| s - mean).collect()
+}
+
+/// High-pass `samples` in place at `cutoff_hz` with two one-pole stages
+/// Takes out sub-audio rumble (HVAC, handling noise) from cheap mics; the 12 dB/octave
+/// slope leaves notes an octave above the cutoff about 2 dB quieter
+fn high_pass(samples: &mut [f32], sample_rate: u32, cutoff_hz: f32) {
+    if sample_rate == 0 {
+        return;
+    }
+
+    let rc = 1.0 / (2.0 * PI * cutoff_hz);
+    let dt = 1.0 / sample_rate as f32;
+    let alpha = rc / (rc + dt);
+    for _ in 0..2 {
+        let mut previous_input = 0.0;
+        let mut previous_output = 0.0;
+        for sample in samples.iter_mut() {
+            let input = *sample;
+            previous_output = alpha * (previous_output + input - previous_input);
+            previous_input = input;
+            *sample = previous_output;
+        }
+    }
+}
+
+/// Strongest bin within `width` bins of `center` (clamped to the positive spectrum)
+fn strongest_bin_near(psd: &[f32], center: usize, width: usize) -> Option<(usize, f32)> {
+    let half = psd.len() / 2;
+    let start = center.saturating_sub(width).max(1);
+    let end = (center + width).min(half.saturating_sub(1));
+    (start..=end)
+        .map(|bin| (bin, psd[bin]))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Octave check for the strongest peak at bin `peak`: returns the bin to report and whether the
+/// two octave candidates (the peak and half its frequency) are nearly equal in strength
+/// Drops an octave when the sub-harmonic is a real peak and its 3rd harmonic (1.5x the peak) is
+/// present too, i.e. the peak looks like the 2nd harmonic of a weaker fundamental
+fn resolve_octave(psd: &[f32], peak: usize) -> (usize, bool) {
+    let power = psd[peak];
+    // Half a semitone either side, and at least a bin of slack for rounding
+    let width = (peak / 60).max(1);
+    let Some((sub_bin, sub_power)) = strongest_bin_near(psd, peak / 2, width / 2 + 1) else {
+        return (peak, false);
+    };
+    if power <= 0.0 || sub_bin < 2 {
+        return (peak, false);
+    }
+
+    let ambiguous = sub_power / power >= AMBIGUOUS_OCTAVE_RATIO;
+    let sub_is_peak = sub_power >= power * SUBHARMONIC_MIN_RATIO
+        && 10.0 * peak_prominence(psd, sub_bin).log10() >= NOISY_PROMINENCE_DB;
+    let odd_power = strongest_bin_near(psd, peak * 3 / 2, width).map_or(0.0, |(_, p)| p);
+    if sub_is_peak && odd_power >= power * SUBHARMONIC_MIN_RATIO {
+        (sub_bin, ambiguous)
+    } else {
+        (peak, ambiguous)
+    }
+}
+
+/// Peak-to-average ratio of the peak at bin `peak`: its power over the mean power of the
+/// surrounding spectrum, `PROMINENCE_SPAN` peak widths either side of the peak's own lobe
+/// A clean tone towers over its window leakage; a noise peak barely clears its neighbours
+fn peak_prominence(psd: &[f32], peak: usize) -> f32 {
+    let half = psd.len() / 2;
+    if peak == 0 || peak >= half {
+        return 0.0;
+    }
+
+    // The peak's lobe runs downhill to the nearest local minimum on each side
+    let mut low = peak;
+    while low > 1 && psd[low - 1] < psd[low] {
+        low -= 1;
+    }
+    let mut high = peak;
+    while high + 1 < half && psd[high + 1] < psd[high] {
+        high += 1;
+    }
+
+    let reach = (high - low).max(2) * PROMINENCE_SPAN;
+    let start = low.saturating_sub(reach).max(1);
+    let end = (high + reach).min(half - 1);
+    let surrounding = psd[start..low].iter().chain(&psd[high + 1..=end]);
+    let (sum, count) =
+        surrounding.fold((0.0_f32, 0_usize), |(sum, count), &p| (sum + p, count + 1));
+
+    let average = sum / count.max(1) as f32;
+    if average > 0.0 {
+        psd[peak] / average
+    } else {
+        // Nothing around it at all (e.g. a synthetic spectrum): as clean as it gets
+        f32::INFINITY
+    }
+}
+
+/// Confidence factor (0.0-1.0) for a peak-to-average ratio (see `NOISY_PROMINENCE_DB`)
+fn prominence_confidence(prominence: f32) -> f32 {
+    if prominence <= 0.0 {
+        return 0.0;
+    }
+    let prominence_db = 10.0 * prominence.log10();
+    ((prominence_db - NOISY_PROMINENCE_DB) / (CLEAN_PROMINENCE_DB - NOISY_PROMINENCE_DB))
+        .clamp(0.0, 1.0)
+}
+
+/// Spectral centroid in Hz: the power-weighted mean frequency below Nyquist
+/// Higher values mean a "brighter" sound (more energy in the upper harmonics)
+fn spectral_centroid(psd: &[f32], sample_rate: u32) -> f32 {
+    let freq_resolution = sample_rate as f32 / psd.len().max(1) as f32;
+    let nyquist_bin = psd.len() / 2;
+
+    // Skip the DC bin so offsets don't drag the centroid towards 0 Hz
+    let (weighted, total) = psd.iter().enumerate().take(nyquist_bin).skip(1).fold(
+        (0.0f32, 0.0f32),
+        |(weighted, total), (i, &power)| {
+            (weighted + i as f32 * freq_resolution * power, total + power)
+        },
+    );
+
+    if total > 0.0 {
+        weighted / total
+    } else {
+        0.0
+    }
+}
+
+/// Scale intensities so the strongest note is exactly 1.0 (see `IntensityMode::Normalized`)
+/// Ratios between notes are kept; silent peaks (all zero) are left alone
+fn normalize_intensities(notes: &mut [(String, f32, f32, f32, bool)]) {
+    let strongest = notes
+        .iter()
+        .map(|(_, _, intensity, ..)| *intensity)
+        .fold(0.0_f32, f32::max);
+    if strongest > 0.0 {
+        for (_, _, intensity, ..) in notes.iter_mut() {
+            *intensity /= strongest;
+        }
+    }
+}
+
+/// Score raw analyzer detections and keep the top `max_results`
+/// Smart scoring: a weighted sum of the low-frequency bonus, confidence and intensity
+/// (the default weights prefer lower frequencies for bass voices)
+/// Ordering is deterministic: score descending, then note name; NaN scores sort last
+pub fn rank_notes(
+    notes_raw: Vec<(String, f32, f32, f32, bool)>,
+    weights: ScoreWeights,
+    max_results: usize,
+) -> Vec<DetectedNote> {
+    // OPTIMIZED: Pre-compute scores with frequency lookup cache
+    // This avoids redundant note_to_frequency() and bonus calculations
+    let mut notes_with_scores: Vec<(DetectedNote, f32)> = notes_raw
+        .into_iter()
+        .map(|(note, confidence, intensity, frequency, ambiguous)| {
+            let freq = note_to_frequency(&note);
+            let note = DetectedNote {
+                note,
+                confidence,
+                intensity,
+                frequency,
+                cents_off: cents_between(frequency, freq),
+                ambiguous,
+                vibrato: None,
+                attack_ms: None,
+            };
+            let score = note.intensity.mul_add(
+                weights.intensity,
+                low_frequency_bonus(freq).mul_add(
+                    weights.low_frequency,
+                    confidence_weight(note.confidence) * weights.confidence,
+                ),
+            );
+            // NaN (e.g. from a degenerate buffer) ranks below every real score
+            (
+                note,
+                if score.is_nan() {
+                    f32::NEG_INFINITY
+                } else {
+                    score
+                },
+            )
+        })
+        .collect();
+
+    // Sort once by pre-computed scores, breaking ties by name so identical input gives identical output
+    notes_with_scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.note.cmp(&b.0.note)));
+
+    // Extract the top notes
+    notes_with_scores
+        .into_iter()
+        .take(max_results)
+        .map(|(note, _)| note)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AnalysisParams, NamingScheme, DEFAULT_PEAK_THRESHOLD};
+
+    /// Generate a sine tone of `len` samples
+    fn sine(frequency: f32, sample_rate: u32, len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    /// Encode samples as 16-bit little-endian PCM, as sent by clients
+    fn to_pcm16_bytes(samples: &[f32]) -> Vec<u8> {
+        samples
+            .iter()
+            .flat_map(|&s| ((s * 32767.0) as i16).to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_lookup_table_creation() {
+        let lookup = FrequencyToNoteLookup::new();
+        // Should have 7 natural notes × 5 octaves (C2 to C6) = 35 entries
+        assert!(lookup.table.len() >= 35);
+    }
+
+    #[test]
+    fn test_find_a4_frequency() {
+        let lookup = FrequencyToNoteLookup::new();
+        // A4 (440 Hz) should be found
+        let (note_name, confidence) = lookup.find_closest_note(440.0).unwrap();
+        assert_eq!(note_name, "A4");
+        assert!(confidence > 0.99);
+    }
+
+    #[test]
+    fn test_frequency_to_note_nearby() {
+        let lookup = FrequencyToNoteLookup::new();
+        // Frequency slightly off from A4 should still return A4 with lower confidence
+        let (note_name, confidence) = lookup.find_closest_note(445.0).unwrap();
+        let (_, exact_confidence) = lookup.find_closest_note(440.0).unwrap();
+        assert_eq!(note_name, "A4");
+        assert!(confidence < exact_confidence);
+        assert!(confidence > 0.0);
+    }
+
+    #[test]
+    fn test_zero_padding_resolves_short_chunk() {
+        let analyzer = AudioAnalyzer::new();
+        // 10ms of A4 @ 48kHz: 100 Hz bins without padding, ~11.7 Hz bins padded to 4096
+        let tone = sine(440.0, 48000, 480, 0.8);
+        let (note_name, confidence) = analyzer
+            .analyze_chunk(&tone, 48000, &AnalysisOptions::default(), Some(4096))
+            .unwrap();
+        assert_eq!(note_name, "A4");
+        assert!(confidence > 0.5);
+    }
+
+    #[test]
+    fn test_max_notes_returns_dense_cluster() {
+        let analyzer = AudioAnalyzer::new();
+        let sample_rate = 48000;
+        let len = 32768;
+        let options = |max_notes| AnalysisOptions {
+            max_notes,
+            ..AnalysisOptions::default()
+        };
+        // C4..B4 plus a quieter F3: no tone sits on a 2x-4x harmonic of a stronger one
+        let cluster: [(f32, f32); 8] = [
+            (261.63, 0.1),
+            (293.66, 0.1),
+            (329.63, 0.1),
+            (349.23, 0.1),
+            (392.00, 0.1),
+            (440.00, 0.1),
+            (493.88, 0.1),
+            (174.61, 0.06),
+        ];
+        let mut signal = vec![0.0_f32; len];
+        for (frequency, amplitude) in cluster {
+            for (sample, tone) in
+                signal
+                    .iter_mut()
+                    .zip(sine(frequency, sample_rate, len, amplitude))
+            {
+                *sample += tone;
+            }
+        }
+
+        let notes = analyzer.analyze_chunk_multi(&signal, sample_rate, &options(8), None);
+        let distinct: std::collections::HashSet<_> = notes.iter().map(|(n, ..)| n).collect();
+        assert_eq!(notes.len(), 8);
+        assert_eq!(distinct.len(), 8);
+
+        let default_notes = analyzer.analyze_chunk_multi(&signal, sample_rate, &options(5), None);
+        assert_eq!(default_notes.len(), 5);
+    }
+
+    #[test]
+    fn test_min_confidence_drops_borderline_notes() {
+        let analyzer = AudioAnalyzer::new();
+        // 452 Hz is ~45 cents sharp of A4: a borderline (~0.55 confidence) detection
+        let tone = sine(452.0, 48000, 16384, 0.5);
+        let bytes = to_pcm16_bytes(&tone);
+
+        let lenient = AnalysisOptions {
+            min_confidence: 0.3,
+            ..AnalysisOptions::default()
+        };
+        let strict = AnalysisOptions {
+            min_confidence: 0.9,
+            ..AnalysisOptions::default()
+        };
+
+        let lenient_notes = analyzer.analyze_raw_bytes(&bytes, 48000, &lenient).notes;
+        assert!(lenient_notes.iter().any(|(note, ..)| note == "A4"));
+        assert!(analyzer
+            .analyze_raw_bytes(&bytes, 48000, &strict)
+            .notes
+            .is_empty());
+    }
+
+    #[test]
+    fn test_f32le_input_decodes_without_scaling() {
+        let analyzer = AudioAnalyzer::new();
+        // Float samples are used as-is (no /32768), so a half-scale tone stays half-scale
+        let tone = sine(440.0, 48000, 8192, 0.5);
+        let bytes: Vec<u8> = tone.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let options = AnalysisOptions {
+            format: SampleFormat::F32le,
+            ..AnalysisOptions::default()
+        };
+
+        let notes = analyzer.analyze_raw_bytes(&bytes, 48000, &options).notes;
+        assert!(notes.iter().any(|(note, ..)| note == "A4"), "{notes:?}");
+
+        // Misaligned input (offset by one byte) takes the copying path and decodes the same
+        let mut shifted = vec![0u8];
+        shifted.extend_from_slice(&bytes);
+        let notes = analyzer
+            .analyze_raw_bytes(&shifted[1..], 48000, &options)
+            .notes;
+        assert!(notes.iter().any(|(note, ..)| note == "A4"), "{notes:?}");
+    }
+
+    #[test]
+    fn test_magnitude_spectrum_peaks_at_tone() {
+        let analyzer = AudioAnalyzer::new();
+        let tone = sine(440.0, 48000, 4800, 0.5);
+        let (frequencies, magnitudes) =
+            analyzer.magnitude_spectrum(&tone, 48000, 256, 50.0, 2000.0);
+        assert_eq!(frequencies.len(), 256);
+        assert_eq!(magnitudes.len(), 256);
+
+        let (peak_idx, _) = magnitudes
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap();
+        let nearest_440 = frequencies
+            .iter()
+            .enumerate()
+            .min_by(|a, b| (a.1 - 440.0).abs().total_cmp(&(b.1 - 440.0).abs()))
+            .unwrap()
+            .0;
+        assert!(
+            peak_idx.abs_diff(nearest_440) <= 1,
+            "peak at {}",
+            frequencies[peak_idx]
+        );
+
+        // Clear peak: an octave away is far quieter
+        let octave_below = frequencies.iter().position(|&f| f >= 220.0).unwrap();
+        assert!(magnitudes[octave_below] < 0.1);
+    }
+
+    #[test]
+    fn test_rms_db_of_half_scale_tone() {
+        let analyzer = AudioAnalyzer::new();
+        // Half amplitude: a square wave's RMS equals its amplitude (-6 dBFS),
+        // a sine's is amplitude / sqrt(2) (another -3 dB)
+        let square: Vec<f32> = sine(440.0, 48000, 4800, 1.0)
+            .iter()
+            .map(|&s| 0.5 * s.signum())
+            .collect();
+        let square_db = analyzer
+            .analyze_samples(&square, 48000, &AnalysisOptions::default())
+            .rms_db;
+        assert!((square_db - (-6.02)).abs() < 0.1, "{square_db}");
+
+        let tone = sine(440.0, 48000, 4800, 0.5);
+        let tone_db = analyzer
+            .analyze_samples(&tone, 48000, &AnalysisOptions::default())
+            .rms_db;
+        assert!((tone_db - (-9.03)).abs() < 0.1, "{tone_db}");
+
+        assert_eq!(rms_dbfs(&[0.0; 480]), MIN_RMS_DB);
+    }
+
+    #[test]
+    fn test_noise_gate_skips_quiet_buffers() {
+        let analyzer = AudioAnalyzer::new();
+        let options = AnalysisOptions::default();
+
+        // Room noise around -63 dBFS is gated
+        let near_silent = sine(440.0, 48000, 8192, 0.001);
+        let gated = analyzer.analyze_samples(&near_silent, 48000, &options);
+        assert!(gated.notes.is_empty());
+        assert!(gated.rms_db < options.gate_db);
+
+        // Soft singing around -43 dBFS passes
+        let soft = sine(440.0, 48000, 8192, 0.01);
+        let notes = analyzer.analyze_samples(&soft, 48000, &options).notes;
+        assert!(notes.iter().any(|(note, ..)| note == "A4"), "{notes:?}");
+    }
+
+    #[test]
+    fn test_a_weighting_quiets_low_tones() {
+        let analyzer = AudioAnalyzer::new();
+        let (sample_rate, len) = (48000, 8192);
+        // Equal amplitudes, both centered on an FFT bin so their raw peak powers match
+        let bin_hz = sample_rate as f32 / len as f32;
+        let (low, high) = (19.0 * bin_hz, 171.0 * bin_hz); // ~111 Hz and ~1002 Hz
+        let signal: Vec<f32> = sine(low, sample_rate, len, 0.02)
+            .iter()
+            .zip(sine(high, sample_rate, len, 0.02))
+            .map(|(a, b)| a + b)
+            .collect();
+
+        let intensities = |a_weighting| {
+            let options = AnalysisOptions {
+                a_weighting,
+                highpass_hz: 0.0,
+                ..AnalysisOptions::default()
+            };
+            let notes = analyzer
+                .analyze_samples(&signal, sample_rate, &options)
+                .notes;
+            let intensity = |target: f32| {
+                notes
+                    .iter()
+                    .find(|(.., frequency, _)| (frequency - target).abs() < bin_hz)
+                    .map(|(_, _, intensity, ..)| *intensity)
+                    .unwrap_or_else(|| panic!("no note near {target} Hz in {notes:?}"))
+            };
+            (intensity(low), intensity(high))
+        };
+
+        let (raw_low, raw_high) = intensities(false);
+        assert!(raw_high < 1.0, "tones must stay below the intensity cap");
+        assert!(
+            (raw_low / raw_high - 1.0).abs() < 0.05,
+            "{raw_low} vs {raw_high}"
+        );
+
+        // ~111 Hz sits ~18 dB down the A curve, 1 kHz is unchanged
+        let (weighted_low, weighted_high) = intensities(true);
+        assert!((weighted_high / raw_high - 1.0).abs() < 0.01);
+        let drop_db = 20.0 * (weighted_low / raw_low).log10();
+        assert!((drop_db + 18.0).abs() < 1.0, "{drop_db} dB");
+    }
+
+    #[test]
+    fn test_normalized_intensity_scales_top_note_to_one() {
+        let analyzer = AudioAnalyzer::new();
+        let (sample_rate, len) = (48000, 8192);
+        let bin_hz = sample_rate as f32 / len as f32;
+        // ~264 Hz at twice the amplitude of ~393 Hz, both on FFT bins
+        let signal: Vec<f32> = sine(45.0 * bin_hz, sample_rate, len, 0.02)
+            .iter()
+            .zip(sine(67.0 * bin_hz, sample_rate, len, 0.01))
+            .map(|(a, b)| a + b)
+            .collect();
+        let bytes = to_pcm16_bytes(&signal);
+
+        let intensities = |intensity_mode| {
+            let params: AnalysisParams =
+                serde_json::from_value(serde_json::json!({ "intensity_mode": intensity_mode }))
+                    .unwrap();
+            let mut notes = analyzer
+                .analyze_raw_bytes(&bytes, sample_rate, &params.analysis_options())
+                .notes;
+            notes.sort_by(|a, b| a.3.total_cmp(&b.3));
+            notes.iter().map(|note| note.2).collect::<Vec<f32>>()
+        };
+
+        let raw = intensities("raw");
+        let normalized = intensities("normalized");
+        assert_eq!(raw.len(), 2, "{raw:?}");
+        assert!(raw[0] < 1.0, "tones must stay below the raw cap");
+        assert_eq!(normalized[0], 1.0);
+        assert!((normalized[1] / normalized[0] - raw[1] / raw[0]).abs() < 1e-6);
+        assert!((normalized[1] - 0.5).abs() < 0.05, "{normalized:?}");
+    }
+
+    #[test]
+    fn test_non_finite_f32_input_is_treated_as_silence() {
+        let analyzer = AudioAnalyzer::new();
+        let options = AnalysisOptions {
+            format: SampleFormat::F32le,
+            ..AnalysisOptions::default()
+        };
+        let to_bytes =
+            |samples: &[f32]| -> Vec<u8> { samples.iter().flat_map(|s| s.to_le_bytes()).collect() };
+
+        // Nothing but NaN and infinities: a clean, empty result
+        let garbage: Vec<f32> = [f32::NAN, f32::INFINITY, f32::NEG_INFINITY]
+            .into_iter()
+            .cycle()
+            .take(4800)
+            .collect();
+        let analysis = analyzer.analyze_raw_bytes(&to_bytes(&garbage), 48000, &options);
+        assert!(analysis.notes.is_empty());
+        assert!(analysis.rms_db.is_finite());
+
+        // A few bad samples don't hide the tone around them
+        let mut tone = sine(440.0, 48000, 4800, 0.5);
+        for sample in tone.iter_mut().step_by(97) {
+            *sample = f32::NAN;
+        }
+        let notes = analyzer
+            .analyze_raw_bytes(&to_bytes(&tone), 48000, &options)
+            .notes;
+        assert!(notes.iter().any(|(note, ..)| note == "A4"), "{notes:?}");
+        assert!(notes
+            .iter()
+            .all(|(_, confidence, intensity, frequency, _)| {
+                confidence.is_finite() && intensity.is_finite() && frequency.is_finite()
+            }));
+    }
+
+    #[test]
+    fn test_all_zero_buffer_short_circuits() {
+        let analyzer = AudioAnalyzer::new();
+        // Gate disabled, so only the silence check stands between zeros and the FFT
+        let options = AnalysisOptions {
+            gate_db: MIN_RMS_DB,
+            ..AnalysisOptions::default()
+        };
+
+        let zeros = vec![0u8; 96000 * 2];
+        let analysis = analyzer.analyze_raw_bytes(&zeros, 48000, &options);
+        assert!(analysis.notes.is_empty());
+        assert_eq!(analysis.rms_db, MIN_RMS_DB);
+        assert_eq!(analysis.fft_size, None);
+
+        // One-step dither is still silence
+        let dither: Vec<u8> = (0..9600)
+            .flat_map(|i| if i % 2 == 0 { 1i16 } else { -1i16 }.to_le_bytes())
+            .collect();
+        let analysis = analyzer.analyze_raw_bytes(&dither, 48000, &options);
+        assert!(analysis.notes.is_empty());
+        assert_eq!(analysis.fft_size, None);
+    }
+
+    #[test]
+    fn test_samples_analyzed_counts_decoded_samples() {
+        let analyzer = AudioAnalyzer::new();
+        let mut bytes = to_pcm16_bytes(&sine(440.0, 48000, 4800, 0.5));
+        // A stray trailing byte is half a sample, not one more
+        bytes.push(0x7F);
+        let options = AnalysisOptions::default();
+        let analysis = analyzer.analyze_raw_bytes(&bytes, 48000, &options);
+        assert_eq!(
+            analysis.samples_analyzed,
+            AudioAnalyzer::decode_raw_bytes(&bytes, &options).len()
+        );
+        assert_eq!(analysis.samples_analyzed, 4800);
+
+        // Stereo: 4800 samples are 2400 frames, and the leftover byte still doesn't count
+        let stereo = AnalysisOptions {
+            channels: 2,
+            ..AnalysisOptions::default()
+        };
+        let analysis = analyzer.analyze_raw_bytes(&bytes, 48000, &stereo);
+        assert_eq!(analysis.samples_analyzed, 2400);
+
+        // Gated buffers still report what they measured
+        let quiet = to_pcm16_bytes(&sine(440.0, 48000, 960, 0.0001));
+        let analysis = analyzer.analyze_raw_bytes(&quiet, 48000, &options);
+        assert!(analysis.gated);
+        assert_eq!(analysis.samples_analyzed, 960);
+    }
+
+    #[test]
+    fn test_hard_clipped_buffer_reports_clipping() {
+        let analyzer = AudioAnalyzer::new();
+        let options = AnalysisOptions::default();
+        // A 220 Hz sine driven 4x past full scale: flat-topped, nearly square
+        let tone = |gain: f32| -> Vec<u8> {
+            (0..9600)
+                .flat_map(|i| {
+                    let t = i as f32 / 48000.0;
+                    let sample = (gain * (2.0 * PI * 220.0 * t).sin()).clamp(-1.0, 1.0);
+                    ((sample * 32767.0) as i16).to_le_bytes()
+                })
+                .collect()
+        };
+
+        let clipped = analyzer.analyze_raw_bytes(&tone(4.0), 48000, &options);
+        assert!(clipped.clip_ratio > 0.5, "{}", clipped.clip_ratio);
+
+        // The same tone at half scale never reaches the clip level
+        let clean = analyzer.analyze_raw_bytes(&tone(0.5), 48000, &options);
+        assert_eq!(clean.clip_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_spectral_centroid_tracks_brightness() {
+        let analyzer = AudioAnalyzer::new();
+        let options = AnalysisOptions {
+            include_features: true,
+            ..AnalysisOptions::default()
+        };
+
+        // Same fundamental, but the square wave carries odd harmonics up to Nyquist
+        let pure = sine(220.0, 48000, 8192, 0.5);
+        let bright: Vec<f32> = pure.iter().map(|&s| 0.5 * s.signum()).collect();
+
+        let pure_centroid = analyzer
+            .analyze_samples(&pure, 48000, &options)
+            .spectral_centroid_hz
+            .unwrap();
+        let bright_centroid = analyzer
+            .analyze_samples(&bright, 48000, &options)
+            .spectral_centroid_hz
+            .unwrap();
+        assert!((pure_centroid - 220.0).abs() < 20.0, "{pure_centroid}");
+        assert!(bright_centroid > 2.0 * pure_centroid, "{bright_centroid}");
+
+        // Off by default
+        let plain = analyzer.analyze_samples(&pure, 48000, &AnalysisOptions::default());
+        assert!(plain.spectral_centroid_hz.is_none());
+    }
+
+    #[test]
+    fn test_prime_length_buffer_uses_smooth_fft() {
+        assert_eq!(next_fast_len(95989), 96000);
+        assert_eq!(next_fast_len(4096), 4096);
+        assert_eq!(next_fast_len(1), 1);
+
+        let tone = sine(440.0, 48000, 95989, 0.5);
+        let analysis =
+            AudioAnalyzer::new().analyze_samples(&tone, 48000, &AnalysisOptions::default());
+
+        assert_eq!(analysis.fft_size, Some(96000));
+        assert!(
+            analysis.notes.iter().any(|(note, ..)| note == "A4"),
+            "{:?}",
+            analysis.notes
+        );
+    }
+
+    #[test]
+    fn test_bytes_to_samples_tolerates_odd_and_unaligned_input() {
+        let pcm = to_pcm16_bytes(&sine(440.0, 48000, 6000, 0.5));
+
+        // Odd length: the dangling byte is dropped instead of panicking
+        let odd = &pcm[..1001];
+        assert_eq!(
+            AudioAnalyzer::bytes_to_samples(odd, SampleFormat::S16le).len(),
+            500
+        );
+
+        // Shifting by one byte misaligns the slice for i16 (both the serial and parallel paths)
+        let mut shifted = vec![0u8];
+        shifted.extend_from_slice(&pcm);
+        let unaligned = &shifted[1..];
+        for len in [1000, pcm.len()] {
+            assert_eq!(
+                AudioAnalyzer::bytes_to_samples(&unaligned[..len], SampleFormat::S16le),
+                AudioAnalyzer::bytes_to_samples(&pcm[..len], SampleFormat::S16le)
+            );
+        }
+    }
+
+    #[test]
+    fn test_octave_range_includes_octave_zero() {
+        let default_lookup = FrequencyToNoteLookup::new();
+        assert_eq!(default_lookup.octave_range(), (1, 7));
+        // C0 isn't in the default table: nearest entry is a full octave away
+        let (note_name, confidence) = default_lookup.find_closest_note(16.35).unwrap();
+        assert_eq!(note_name, "C1");
+        assert!(confidence < 0.01);
+
+        let wide = FrequencyToNoteLookup::with_octave_range(0, 9);
+        let (note_name, confidence) = wide.find_closest_note(16.35).unwrap();
+        assert_eq!(note_name, "C0");
+        assert!(confidence > 0.99);
+        assert_eq!(wide.find_closest_note(14080.0).unwrap().0, "A9");
+
+        // Out-of-range and reversed bounds are normalized
+        assert_eq!(
+            FrequencyToNoteLookup::normalize_octave_range(12, -3),
+            (0, 9)
+        );
+        assert_eq!(FrequencyToNoteLookup::normalize_octave_range(5, 2), (2, 5));
+    }
+
+    #[test]
+    fn test_stereo_downmix_matches_mono() {
+        let analyzer = AudioAnalyzer::new();
+        let tone = sine(440.0, 48000, 8192, 0.5);
+        let mono_bytes = to_pcm16_bytes(&tone);
+
+        // Same tone on both channels, interleaved L R L R ..., plus one stray trailing sample
+        let mut stereo_bytes: Vec<u8> = mono_bytes
+            .chunks_exact(2)
+            .flat_map(|sample| [sample[0], sample[1], sample[0], sample[1]])
+            .collect();
+        stereo_bytes.extend_from_slice(&[0x7f, 0x7f]);
+
+        let mono = analyzer.analyze_raw_bytes(&mono_bytes, 48000, &AnalysisOptions::default());
+        let stereo_options = AnalysisOptions {
+            channels: 2,
+            ..AnalysisOptions::default()
+        };
+        let stereo = analyzer.analyze_raw_bytes(&stereo_bytes, 48000, &stereo_options);
+
+        assert!(mono.notes.iter().any(|(note, ..)| note == "A4"));
+        assert_eq!(stereo, mono);
+    }
+
+    #[test]
+    fn test_fft_plan_cache_concurrent() {
+        let analyzer = AudioAnalyzer::new();
+        let signal = sine(440.0, 48000, 4800, 0.5);
+        let expected = analyzer.compute_fft(&signal, 8192);
+
+        // Hammer the cache from many threads with a mix of hot and cold lengths
+        std::thread::scope(|scope| {
+            for thread_idx in 0..8 {
+                let (analyzer, signal, expected) = (&analyzer, &signal, &expected);
+                scope.spawn(move || {
+                    for i in 0..20 {
+                        assert_eq!(&analyzer.compute_fft(signal, 8192), expected);
+                        let cold_len = 4800 + thread_idx * 16 + i % 4;
+                        assert_eq!(analyzer.compute_fft(signal, cold_len).len(), cold_len);
+                    }
+                });
+            }
+        });
+
+        // Same length always returns the same shared plan
+        assert!(Arc::ptr_eq(&fft_plan(8192), &fft_plan(8192)));
+    }
+
+    #[test]
+    fn test_soprano_weights_rank_strong_high_note_first() {
+        let detections = || {
+            vec![
+                ("A2".to_string(), 0.9, 0.1, 110.0, false), // weak
+                ("A5".to_string(), 0.9, 1.0, 880.0, false), // strong
+            ]
+        };
+
+        // Default weights keep the bass bias
+        let ranked = rank_notes(detections(), VoiceProfile::NoProfile.score_weights(), 3);
+        assert_eq!(ranked[0].note, "A2");
+
+        let ranked = rank_notes(detections(), VoiceProfile::Soprano.score_weights(), 3);
+        assert_eq!(ranked[0].note, "A5");
+    }
+
+    #[test]
+    fn test_rank_notes_is_deterministic_and_nan_safe() {
+        let detections = vec![
+            ("E4".to_string(), 0.8, 0.5, 329.6, false),
+            ("C4".to_string(), 0.8, 0.5, 261.6, false),
+            ("G4".to_string(), f32::NAN, f32::NAN, 392.0, false),
+            ("D4".to_string(), 0.8, 0.5, 293.7, false),
+        ];
+        let first = rank_notes(detections.clone(), ScoreWeights::default(), 4);
+        let names: Vec<_> = first.iter().map(|n| n.note.as_str()).collect();
+        // C4 scores highest (lowest pitch); NaN sorts last
+        assert_eq!(names, ["C4", "D4", "E4", "G4"]);
+
+        for _ in 0..10 {
+            let again = rank_notes(detections.clone(), ScoreWeights::default(), 4);
+            assert_eq!(
+                serde_json::to_string(&again).unwrap(),
+                serde_json::to_string(&first).unwrap()
+            );
+        }
+        assert_eq!(rank_notes(detections, ScoreWeights::default(), 2).len(), 2);
+    }
+
+    #[test]
+    fn test_analyzers_share_note_tables() {
+        // Building analyzers alike hands out the same table instead of rebuilding it
+        let analyzers: Vec<_> = (0..8)
+            .map(|_| AudioAnalyzer::builder().octave_range(2, 6).build())
+            .collect();
+        assert!(analyzers
+            .iter()
+            .all(|a| Arc::ptr_eq(a.lookup(), analyzers[0].lookup())));
+
+        // Per-request ranges reuse one table too
+        let options = AnalysisOptions {
+            octave_range: (0, 9),
+            ..AnalysisOptions::default()
+        };
+        assert!(Arc::ptr_eq(
+            &analyzers[0].lookup_for(&options),
+            &AudioAnalyzer::new().lookup_for(&options)
+        ));
+
+        // A different tuning gets its own table, and per-request ranges keep that tuning
+        let baroque = AudioAnalyzer::builder().reference_pitch(415.0).build();
+        assert!(!Arc::ptr_eq(
+            baroque.lookup(),
+            AudioAnalyzer::new().lookup()
+        ));
+        let (note, confidence) = baroque.lookup.find_closest_note(415.0).unwrap();
+        assert_eq!(note, "A4");
+        assert!(confidence > 0.99);
+        assert!((baroque.lookup_for(&options).reference_hz() - 415.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_noise_has_low_confidence_and_clean_tone_high() {
+        let analyzer = AudioAnalyzer::new();
+        let options = AnalysisOptions {
+            min_confidence: 0.0,
+            gate_db: MIN_RMS_DB,
+            ..AnalysisOptions::default()
+        };
+
+        // Uniform white noise from a fixed xorshift seed
+        let mut state: u32 = 0x9E37_79B9;
+        let noise: Vec<f32> = (0..48000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state as f32 / u32::MAX as f32 - 0.5) * 0.8
+            })
+            .collect();
+        let noisy = analyzer.analyze_samples(&noise, 48000, &options);
+        for (note, confidence, ..) in &noisy.notes {
+            assert!(*confidence < 0.2, "{note}: {confidence}");
+        }
+
+        let tone = sine(440.0, 48000, 48000, 0.5);
+        let clean = analyzer.analyze_samples(&tone, 48000, &options);
+        let (note, confidence, ..) = &clean.notes[0];
+        assert_eq!(note, "A4");
+        assert!(*confidence > 0.9, "{confidence}");
+
+        // Short chunks take the single-peak path and are scaled the same way
+        // (the coarse bins already cost some cents-match confidence, prominence doesn't add to it)
+        let (_, short_confidence) = analyzer
+            .analyze_chunk(&sine(440.0, 48000, 400, 0.5), 48000, &options, Some(4096))
+            .unwrap();
+        assert!(short_confidence > 0.7, "{short_confidence}");
+        let short_noise = analyzer.analyze_chunk(&noise[..400], 48000, &options, Some(4096));
+        assert!(short_noise.is_none_or(|(_, confidence)| confidence < 0.2));
+    }
+
+    #[test]
+    fn test_dc_offset_and_rumble_are_filtered_out() {
+        let analyzer = AudioAnalyzer::new();
+        // A3 riding on a DC bias and 10 Hz rumble, as from a cheap mic next to an air vent
+        let biased: Vec<f32> = sine(220.0, 48000, 48000, 0.3)
+            .iter()
+            .zip(sine(10.0, 48000, 48000, 0.3))
+            .map(|(tone, rumble)| tone + rumble + 0.3)
+            .collect();
+
+        let options = AnalysisOptions {
+            min_confidence: 0.0,
+            ..AnalysisOptions::default()
+        };
+        let analysis = analyzer.analyze_samples(&biased, 48000, &options);
+        assert_eq!(analysis.notes[0].0, "A3");
+        for (note, _, _, frequency, _) in &analysis.notes {
+            assert!(
+                *frequency > 100.0,
+                "spurious low note {note} at {frequency} Hz"
+            );
+        }
+
+        // With the filter off the rumble swamps the low bins
+        let unfiltered = AnalysisOptions {
+            highpass_hz: 0.0,
+            ..options
+        };
+        let analysis = analyzer.analyze_samples(&biased, 48000, &unfiltered);
+        assert!(analysis
+            .notes
+            .iter()
+            .any(|(_, _, _, frequency, _)| *frequency < 40.0));
+    }
+
+    #[test]
+    fn test_lower_peak_threshold_surfaces_weak_notes() {
+        let analyzer = AudioAnalyzer::new();
+        // A4 with an E5 at under a tenth of its level alongside
+        let mix: Vec<f32> = sine(440.0, 48000, 48000, 0.5)
+            .iter()
+            .zip(sine(659.26, 48000, 48000, 0.04))
+            .map(|(strong, weak)| strong + weak)
+            .collect();
+        let detect = |peak_threshold: f32| -> Vec<String> {
+            let options = AnalysisOptions {
+                peak_threshold,
+                min_confidence: 0.0,
+                ..AnalysisOptions::default()
+            };
+            let notes = analyzer.analyze_samples(&mix, 48000, &options).notes;
+            notes.into_iter().map(|(note, ..)| note).collect()
+        };
+
+        assert_eq!(detect(DEFAULT_PEAK_THRESHOLD), ["A4"]);
+        assert_eq!(detect(0.02), ["A4", "E5"]);
+    }
+
+    #[test]
+    fn test_band_limits_peaks_and_intersects_profile() {
+        let analyzer = AudioAnalyzer::new();
+        // E4 (330 Hz) quieter than a D5 (~600 Hz) above it
+        let chord: Vec<f32> = sine(330.0, 48000, 48000, 0.2)
+            .iter()
+            .zip(sine(587.33, 48000, 48000, 0.5))
+            .map(|(low, high)| low + high)
+            .collect();
+
+        let banded = AnalysisOptions {
+            band: Some((200.0, 400.0)),
+            ..AnalysisOptions::default()
+        };
+        let notes: Vec<_> = analyzer
+            .analyze_samples(&chord, 48000, &banded)
+            .notes
+            .into_iter()
+            .map(|(note, ..)| note)
+            .collect();
+        assert_eq!(notes, ["E4"]);
+
+        // Short chunks (single-peak path) honour the band too
+        let (note, _) = analyzer
+            .analyze_chunk(&chord[..400], 48000, &banded, Some(4096))
+            .unwrap();
+        assert_ne!(note, "D5");
+
+        // With a profile only the overlap counts: bass (C2-C4) and 200-400 Hz share 200-262 Hz
+        let bass_banded = AnalysisOptions {
+            profile: VoiceProfile::Bass,
+            ..banded
+        };
+        assert!(analyzer
+            .analyze_samples(&chord, 48000, &bass_banded)
+            .notes
+            .is_empty());
+    }
+
+    #[test]
+    fn test_naming_schemes_spell_the_same_frequency() {
+        let lookup = FrequencyToNoteLookup::new();
+        let (a4, _) = lookup.find_closest_note(440.0).unwrap();
+        assert_eq!(a4, "A4");
+        assert_eq!(NamingScheme::Scientific.rename(&a4), "A4");
+        assert_eq!(NamingScheme::Solfege.rename(&a4), "La4");
+        assert_eq!(NamingScheme::German.rename(&a4), "A4");
+
+        // German B natural is H, and B flat is B
+        let (b4, _) = lookup.find_closest_note(493.88).unwrap();
+        assert_eq!(NamingScheme::German.rename(&b4), "H4");
+        assert_eq!(NamingScheme::German.rename("A#4"), "B4");
+        assert_eq!(NamingScheme::German.rename("Bb4"), "B4");
+        assert_eq!(NamingScheme::German.rename("F#3"), "Fis3");
+        assert_eq!(NamingScheme::Solfege.rename("C#5"), "Do#5");
+
+        // Not a note: left alone
+        assert_eq!(NamingScheme::Solfege.rename("noise"), "noise");
+    }
+
+    #[test]
+    fn test_even_harmonics_dont_push_note_up_an_octave() {
+        let analyzer = AudioAnalyzer::new();
+        let sample_rate = 48000;
+        let len = 16384;
+        // A3 with a weak fundamental and strong even harmonics (like many reed and brass tones)
+        let tone = |harmonics: &[(f32, f32)]| {
+            let mut signal = vec![0.0_f32; len];
+            for &(multiple, amplitude) in harmonics {
+                for (sample, h) in
+                    signal
+                        .iter_mut()
+                        .zip(sine(220.0 * multiple, sample_rate, len, amplitude))
+                {
+                    *sample += h;
+                }
+            }
+            signal
+        };
+        let options = AnalysisOptions::default();
+
+        let reedy = tone(&[(1.0, 0.15), (2.0, 0.5), (3.0, 0.15), (4.0, 0.3)]);
+        let notes = analyzer.analyze_chunk_multi(&reedy, sample_rate, &options, None);
+        let strongest = notes.iter().max_by(|a, b| a.2.total_cmp(&b.2)).unwrap();
+        assert_eq!(strongest.0, "A3", "{notes:?}");
+        assert!(!strongest.4, "{notes:?}");
+        assert!(!notes.iter().any(|(note, ..)| note == "A4"), "{notes:?}");
+
+        // Fundamental and 2nd harmonic about as strong: still A3, but flagged
+        let even = tone(&[(1.0, 0.45), (2.0, 0.5), (3.0, 0.15)]);
+        let notes = analyzer.analyze_chunk_multi(&even, sample_rate, &options, None);
+        let a3 = notes.iter().find(|(note, ..)| note == "A3").unwrap();
+        assert!(a3.4, "{notes:?}");
+
+        // A plain sine is never ambiguous
+        let pure = sine(440.0, sample_rate, len, 0.5);
+        let notes = analyzer.analyze_chunk_multi(&pure, sample_rate, &options, None);
+        assert!(
+            notes.iter().all(|(_, _, _, _, ambiguous)| !ambiguous),
+            "{notes:?}"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::parse_note_name;
+
+/// Root names used when reporting a chord (accidentals spelled as sharps)
+pub(crate) const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Matches scoring below this are not reported as a chord
+const MIN_CHORD_CONFIDENCE: f32 = 0.5;
+
+/// Interval template: chord quality, name suffix, and semitones above the root
+/// Ordered from most to least common so ties favor the simpler reading
+struct ChordTemplate {
+    quality: &'static str,
+    suffix: &'static str,
+    intervals: &'static [u8],
+}
+
+const CHORD_TEMPLATES: [ChordTemplate; 9] = [
+    ChordTemplate {
+        quality: "major",
+        suffix: " major",
+        intervals: &[0, 4, 7],
+    },
+    ChordTemplate {
+        quality: "minor",
+        suffix: " minor",
+        intervals: &[0, 3, 7],
+    },
+    ChordTemplate {
+        quality: "dominant7",
+        suffix: "7",
+        intervals: &[0, 4, 7, 10],
+    },
+    ChordTemplate {
+        quality: "minor7",
+        suffix: "m7",
+        intervals: &[0, 3, 7, 10],
+    },
+    ChordTemplate {
+        quality: "major7",
+        suffix: "maj7",
+        intervals: &[0, 4, 7, 11],
+    },
+    ChordTemplate {
+        quality: "sus4",
+        suffix: "sus4",
+        intervals: &[0, 5, 7],
+    },
+    ChordTemplate {
+        quality: "sus2",
+        suffix: "sus2",
+        intervals: &[0, 2, 7],
+    },
+    ChordTemplate {
+        quality: "diminished",
+        suffix: " diminished",
+        intervals: &[0, 3, 6],
+    },
+    ChordTemplate {
+        quality: "augmented",
+        suffix: " augmented",
+        intervals: &[0, 4, 8],
+    },
+];
+
+/// Best chord reading of a set of detected notes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChordMatch {
+    /// Display name, e.g. "C major", "A minor", "G7"
+    pub name: String,
+    /// Root pitch class, e.g. "C"
+    pub root: String,
+    /// Chord quality, e.g. "major", "minor", "dominant7"
+    pub quality: String,
+    /// How well the notes fit the template (0.0-1.0, 1.0 = exact match)
+    pub confidence: f32,
+}
+
+/// Name the chord formed by a set of detected notes
+///
+/// Notes are `(name, weight)` pairs, e.g. `("E4", confidence)`. Octaves are ignored, so
+/// inversions match the same chord; the lowest note only breaks ties between equal readings.
+/// Returns `None` when fewer than three distinct pitch classes are present or nothing fits.
+pub fn identify_chord<'a>(notes: impl IntoIterator<Item = (&'a str, f32)>) -> Option<ChordMatch> {
+    // Accumulate weight per pitch class and remember the bass note
+    let mut weights = [0.0f32; 12];
+    let mut bass: Option<(i32, u8)> = None;
+    for (name, weight) in notes {
+        let Some((pitch_class, octave)) = parse_note_name(name) else {
+            continue;
+        };
+        weights[usize::from(pitch_class)] += weight.max(0.0);
+
+        let height = octave * 12 + i32::from(pitch_class);
+        if bass.is_none_or(|(lowest, _)| height < lowest) {
+            bass = Some((height, pitch_class));
+        }
+    }
+
+    let present = weights.iter().filter(|&&w| w > 0.0).count();
+    if present < 3 {
+        return None;
+    }
+    let total_weight: f32 = weights.iter().sum();
+    let bass_class = bass.map(|(_, pitch_class)| pitch_class);
+
+    let mut best: Option<(f32, bool, u8, &ChordTemplate)> = None;
+    for root in 0..12u8 {
+        for template in &CHORD_TEMPLATES {
+            let mut matched_tones = 0usize;
+            let mut matched_weight = 0.0f32;
+            for interval in template.intervals {
+                let weight = weights[usize::from((root + interval) % 12)];
+                if weight > 0.0 {
+                    matched_tones += 1;
+                    matched_weight += weight;
+                }
+            }
+
+            // Fraction of the template that was heard x fraction of the energy it explains
+            #[allow(clippy::cast_precision_loss)]
+            let score = (matched_tones as f32 / template.intervals.len() as f32)
+                * (matched_weight / total_weight);
+            let root_in_bass = bass_class == Some(root);
+
+            let better = best.is_none_or(|(best_score, best_in_bass, _, _)| {
+                score > best_score + f32::EPSILON
+                    || ((score - best_score).abs() <= f32::EPSILON && root_in_bass && !best_in_bass)
+            });
+            if better {
+                best = Some((score, root_in_bass, root, template));
+            }
+        }
+    }
+
+    let (confidence, _, root, template) = best?;
+    if confidence < MIN_CHORD_CONFIDENCE {
+        return None;
+    }
+
+    let root_name = PITCH_CLASS_NAMES[usize::from(root)];
+    Some(ChordMatch {
+        name: format!("{root_name}{}", template.suffix),
+        root: root_name.to_string(),
+        quality: template.quality.to_string(),
+        confidence,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord_name(notes: &[&str]) -> Option<String> {
+        identify_chord(notes.iter().map(|n| (*n, 1.0))).map(|c| c.name)
+    }
+
+    #[test]
+    fn test_major_and_minor_triads() {
+        assert_eq!(chord_name(&["C4", "E4", "G4"]).as_deref(), Some("C major"));
+        assert_eq!(chord_name(&["C4", "Eb4", "G4"]).as_deref(), Some("C minor"));
+        assert_eq!(chord_name(&["A3", "C4", "E4"]).as_deref(), Some("A minor"));
+    }
+
+    #[test]
+    fn test_inversions_and_sevenths() {
+        // First and second inversion of C major
+        assert_eq!(chord_name(&["E3", "G3", "C4"]).as_deref(), Some("C major"));
+        assert_eq!(chord_name(&["G2", "C3", "E3"]).as_deref(), Some("C major"));
+        assert_eq!(chord_name(&["G3", "B3", "D4", "F4"]).as_deref(), Some("G7"));
+    }
+
+    #[test]
+    fn test_too_few_notes() {
+        assert_eq!(chord_name(&["A4", "E5"]), None);
+        assert_eq!(chord_name(&["A4", "A5", "A3"]), None);
+    }
+}
@@ -0,0 +1,103 @@
+//! G.711 telephony codecs: 8-bit µ-law (North America, Japan) and A-law (everywhere else)
+//! decoded to 16-bit linear PCM through 256-entry lookup tables
+
+/// Linear value of every µ-law byte
+static MULAW_TABLE: [i16; 256] = {
+    let mut table = [0i16; 256];
+    let mut code = 0;
+    while code < 256 {
+        table[code] = decode_mulaw(code as u8);
+        code += 1;
+    }
+    table
+};
+/// Linear value of every A-law byte
+static ALAW_TABLE: [i16; 256] = {
+    let mut table = [0i16; 256];
+    let mut code = 0;
+    while code < 256 {
+        table[code] = decode_alaw(code as u8);
+        code += 1;
+    }
+    table
+};
+
+/// Decode one µ-law byte to 16-bit linear PCM
+pub fn mulaw_to_linear(byte: u8) -> i16 {
+    MULAW_TABLE[usize::from(byte)]
+}
+
+/// Decode one A-law byte to 16-bit linear PCM
+pub fn alaw_to_linear(byte: u8) -> i16 {
+    ALAW_TABLE[usize::from(byte)]
+}
+
+/// µ-law: bits are stored inverted; 3-bit exponent, 4-bit mantissa, biased by 132
+const fn decode_mulaw(byte: u8) -> i16 {
+    const BIAS: i16 = 0x84;
+    let byte = !byte;
+    let exponent = (byte >> 4) & 0x07;
+    let mantissa = (byte & 0x0F) as i16;
+    let magnitude = ((mantissa << 3) + BIAS) << exponent;
+    if byte & 0x80 == 0 {
+        magnitude - BIAS
+    } else {
+        BIAS - magnitude
+    }
+}
+
+/// A-law: even bits are stored inverted; segment 0 is linear, the rest double per segment
+const fn decode_alaw(byte: u8) -> i16 {
+    let byte = byte ^ 0x55;
+    let exponent = (byte >> 4) & 0x07;
+    let mantissa = (byte & 0x0F) as i16;
+    let magnitude = if exponent == 0 {
+        (mantissa << 4) + 8
+    } else {
+        ((mantissa << 4) + 0x108) << (exponent - 1)
+    };
+    if byte & 0x80 == 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mulaw_known_values() {
+        // Both zero codes, the loudest codes and one from the middle of a segment
+        assert_eq!(mulaw_to_linear(0xFF), 0);
+        assert_eq!(mulaw_to_linear(0x7F), 0);
+        assert_eq!(mulaw_to_linear(0x80), 32124);
+        assert_eq!(mulaw_to_linear(0x00), -32124);
+        assert_eq!(mulaw_to_linear(0xEF), 132);
+        assert_eq!(mulaw_to_linear(0x6F), -132);
+    }
+
+    #[test]
+    fn test_alaw_known_values() {
+        assert_eq!(alaw_to_linear(0xD5), 8);
+        assert_eq!(alaw_to_linear(0x55), -8);
+        assert_eq!(alaw_to_linear(0xAA), 32256);
+        assert_eq!(alaw_to_linear(0x2A), -32256);
+        assert_eq!(alaw_to_linear(0xC5), 264);
+    }
+
+    #[test]
+    fn test_codes_are_monotonic_per_sign() {
+        // Within each half, louder codes decode to larger magnitudes
+        let magnitudes = |decode: fn(u8) -> i16, codes: &mut dyn Iterator<Item = u8>| {
+            codes
+                .map(|code| decode(code).unsigned_abs())
+                .collect::<Vec<_>>()
+        };
+        let mulaw = magnitudes(mulaw_to_linear, &mut (0x80..=0xFF).rev());
+        assert!(mulaw.windows(2).all(|pair| pair[0] < pair[1]), "{mulaw:?}");
+        let alaw = magnitudes(alaw_to_linear, &mut (0x80..=0xFF).map(|code| code ^ 0x55));
+        assert!(alaw.windows(2).all(|pair| pair[0] < pair[1]), "{alaw:?}");
+    }
+}
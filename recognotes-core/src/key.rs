@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+use crate::chord::PITCH_CLASS_NAMES;
+use crate::utils::parse_note_name;
+
+/// Krumhansl-Kessler probe-tone ratings of each scale degree, starting at the tonic
+const MAJOR_PROFILE: [f32; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f32; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// One candidate key for a set of notes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyEstimate {
+    /// Display name, e.g. "C major", "F# minor"
+    pub name: String,
+    /// Tonic pitch class, e.g. "C"
+    pub tonic: String,
+    /// "major" or "minor"
+    pub mode: String,
+    /// Pearson correlation of the pitch-class histogram with the key's profile (-1.0-1.0)
+    pub correlation: f32,
+}
+
+/// Total weight of each pitch class (C = 0 .. B = 11) over `(name, weight)` pairs
+/// Octaves are ignored and unparseable names skipped, so detections can be fed in as they come
+pub fn pitch_class_histogram<'a>(notes: impl IntoIterator<Item = (&'a str, f32)>) -> [f32; 12] {
+    let mut histogram = [0.0f32; 12];
+    for (name, weight) in notes {
+        if let Some((pitch_class, _)) = parse_note_name(name) {
+            histogram[usize::from(pitch_class)] += weight.max(0.0);
+        }
+    }
+    histogram
+}
+
+/// Rank all 24 major and minor keys against a pitch-class histogram (Krumhansl-Schmuckler)
+///
+/// Each key's profile is rotated to its tonic and correlated with the histogram; the best match
+/// comes first. Returns nothing for an empty or perfectly flat histogram, which favors no key.
+pub fn estimate_key(histogram: &[f32; 12]) -> Vec<KeyEstimate> {
+    let mut estimates: Vec<KeyEstimate> = (0..12)
+        .flat_map(|tonic| {
+            [("major", &MAJOR_PROFILE), ("minor", &MINOR_PROFILE)]
+                .into_iter()
+                .filter_map(move |(mode, profile)| {
+                    let rotated: [f32; 12] =
+                        std::array::from_fn(|pitch_class| profile[(pitch_class + 12 - tonic) % 12]);
+                    let correlation = pearson(histogram, &rotated)?;
+                    let tonic = PITCH_CLASS_NAMES[tonic];
+                    Some(KeyEstimate {
+                        name: format!("{tonic} {mode}"),
+                        tonic: tonic.to_string(),
+                        mode: mode.to_string(),
+                        correlation,
+                    })
+                })
+        })
+        .collect();
+    // Stable sort: equal correlations keep C..B, major before minor
+    estimates.sort_by(|a, b| b.correlation.total_cmp(&a.correlation));
+    estimates
+}
+
+/// Pearson correlation coefficient, `None` when either side has no variance
+fn pearson(a: &[f32; 12], b: &[f32; 12]) -> Option<f32> {
+    let mean = |values: &[f32; 12]| values.iter().sum::<f32>() / 12.0;
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        let (dx, dy) = (x - mean_a, y - mean_b);
+        covariance += dx * dy;
+        variance_a += dx * dx;
+        variance_b += dy * dy;
+    }
+    let denominator = (variance_a * variance_b).sqrt();
+    (denominator > f32::EPSILON).then(|| covariance / denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_major_scale_estimates_its_key() {
+        let scale = ["C4", "D4", "E4", "F4", "G4", "A4", "B4", "C5"];
+        let estimates = estimate_key(&pitch_class_histogram(scale.iter().map(|n| (*n, 1.0))));
+        assert_eq!(estimates.len(), 24);
+        assert_eq!(estimates[0].name, "C major");
+        assert_eq!(estimates[0].mode, "major");
+        assert!(estimates[0].correlation > estimates[1].correlation);
+
+        // Same notes leaning on A and E: the relative minor
+        let weight = |note: &str| {
+            if note.starts_with(['A', 'E']) {
+                3.0
+            } else {
+                1.0
+            }
+        };
+        let minor = pitch_class_histogram(scale.iter().map(|n| (*n, weight(n))));
+        assert_eq!(estimate_key(&minor)[0].name, "A minor");
+    }
+
+    #[test]
+    fn test_flat_histogram_has_no_key() {
+        assert!(estimate_key(&[0.0; 12]).is_empty());
+        assert!(estimate_key(&[1.0; 12]).is_empty());
+    }
+}
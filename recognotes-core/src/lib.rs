@@ -0,0 +1,49 @@
+//! FFT-based pitch detection behind the RecogNotes backend, usable in-process without the
+//! HTTP server
+//!
+//! Hand [`AudioAnalyzer::analyze_raw_bytes`] little-endian PCM and rank the detections into
+//! [`DetectedNote`]s with [`rank_notes`]:
+//!
+//! ```
+//! use recognotes_core::{rank_notes, AnalysisOptions, AudioAnalyzer, VoiceProfile};
+//!
+//! // Half a second of A4 (440 Hz) as 16-bit PCM at 44.1 kHz
+//! let sample_rate = 44_100;
+//! let pcm: Vec<u8> = (0..sample_rate / 2)
+//!     .map(|i| {
+//!         let t = i as f32 / sample_rate as f32;
+//!         (0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin() * 32767.0) as i16
+//!     })
+//!     .flat_map(i16::to_le_bytes)
+//!     .collect();
+//!
+//! let options = AnalysisOptions {
+//!     profile: VoiceProfile::Soprano,
+//!     ..AnalysisOptions::default()
+//! };
+//! let analysis = AudioAnalyzer::new().analyze_raw_bytes(&pcm, sample_rate, &options);
+//! let notes = rank_notes(analysis.notes, options.score_weights, options.max_results);
+//! assert_eq!(notes[0].note, "A4");
+//! ```
+//!
+//! Note tables are built once and shared, so analyzers are cheap to create and clone; use
+//! [`AudioAnalyzer::builder`] for another default octave range or tuning (e.g. A4 = 432 Hz)
+//!
+//! For whole recordings, [`AudioAnalyzer::analyze_file_windows`] reads a WAV file window by
+//! window and yields timestamped notes as it goes
+
+pub mod attack;
+pub mod audio_analyzer;
+pub mod chord;
+pub mod codec;
+pub mod key;
+pub mod models;
+pub mod utils;
+pub mod vibrato;
+pub mod wav;
+
+pub use audio_analyzer::{rank_notes, AudioAnalyzer, AudioAnalyzerBuilder};
+pub use models::{
+    AnalysisOptions, ChunkAnalysis, DetectedNote, IntensityMode, NamingScheme, SampleFormat,
+    ScoreWeights, Scoring, VoiceProfile,
+};
@@ -0,0 +1,775 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::chord::ChordMatch;
+use crate::utils::parse_note_name;
+use crate::vibrato::Vibrato;
+
+/// Number of notes extracted per chunk when the client doesn't ask for a specific count
+pub const DEFAULT_MAX_NOTES: usize = 5;
+/// Upper bound on `max_notes` - each note costs one harmonic-suppression pass over the spectrum
+pub const MAX_NOTES_LIMIT: usize = 32;
+/// Number of ranked notes returned per result when the client doesn't ask for a specific count
+pub const DEFAULT_MAX_RESULTS: usize = 3;
+/// Notes below this confidence are dropped unless the client asks otherwise
+/// Low enough to keep weak bass fundamentals
+pub const DEFAULT_MIN_CONFIDENCE: f32 = 0.30;
+/// Floor for reported loudness - digital silence reports this instead of -infinity
+pub const MIN_RMS_DB: f32 = -120.0;
+/// Samples at or above this fraction of full scale (32700 of 32768) count as clipped
+pub const CLIP_LEVEL: f32 = 32700.0 / 32768.0;
+/// Fraction of clipped samples from which a buffer is reported as `clipped`
+/// A clean sine peaking right at full scale stays below it; flattened tops don't
+pub const CLIPPED_RATIO: f32 = 0.01;
+/// Buffers quieter than this (dBFS RMS) skip analysis unless the client asks otherwise
+pub const DEFAULT_GATE_DB: f32 = -50.0;
+/// High-pass cutoff removing DC bias and sub-audio rumble before detection (0 disables)
+/// Well under the lowest bass note in the default table (C1, 32.7 Hz, is rarely sung)
+pub const DEFAULT_HIGHPASS_HZ: f32 = 40.0;
+/// Highest high-pass cutoff a client may request
+pub const MAX_HIGHPASS_HZ: f32 = 200.0;
+/// Spectrum peaks weaker than this fraction of the strongest one aren't considered notes
+/// Lower finds quieter notes (and more noise); higher keeps only the dominant ones
+pub const DEFAULT_PEAK_THRESHOLD: f32 = 0.10;
+/// Absolute power below which no peak counts, however strong it is relative to the rest
+/// Keeps quiet-but-clean recordings from turning their noise floor into notes
+pub const DEFAULT_PEAK_FLOOR: f32 = 0.05;
+/// Default note table range, covering every voice type
+/// Bass (C1-E2), Baritone (A1-G3), Tenor (C3-C5), Countertenor/Alto (F3-F5), Soprano (C4-C6)
+pub const DEFAULT_MIN_OCTAVE: i32 = 1; // C1 = 32.7 Hz (very low bass)
+pub const DEFAULT_MAX_OCTAVE: i32 = 7; // C7 = 2093 Hz (high soprano)
+/// Most interleaved channels accepted in raw PCM (7.1 surround)
+pub const MAX_CHANNELS: u8 = 8;
+/// Largest transposition a client may request, either way (three octaves covers the
+/// contrabass clarinet)
+pub const MAX_TRANSPOSE_SEMITONES: i32 = 36;
+/// Limits for client-requested octave ranges: C0 (16.35 Hz) to B9 (15.8 kHz)
+pub const LOWEST_OCTAVE: i32 = 0;
+pub const HIGHEST_OCTAVE: i32 = 9;
+
+/// Voice profile for filtering notes by typical vocal range
+#[allow(clippy::trivially_copy_pass_by_ref, clippy::doc_markdown)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum VoiceProfile {
+    #[serde(rename = "no_profile")]
+    #[default]
+    NoProfile,
+    #[serde(rename = "soprano")]
+    Soprano, // C4-C6 (261-1047 Hz) - highest female voice
+    #[serde(rename = "mezzo")]
+    Mezzo, // A3-A5 (220-880 Hz) - middle female voice
+    #[serde(rename = "alto")]
+    Alto, // F3-F5 (174-698 Hz) - lower female/countertenor voice
+    #[serde(rename = "tenor")]
+    Tenor, // C3-C5 (131-523 Hz) - highest male voice
+    #[serde(rename = "baritone")]
+    Baritone, // A2-A4 (110-440 Hz) - middle male voice
+    #[serde(rename = "bass")]
+    Bass, // C2-C4 (65-261 Hz) - lowest male voice
+    #[serde(rename = "custom")]
+    Custom { min_hz: f32, max_hz: f32 }, // Arbitrary range, e.g. for instruments
+}
+
+impl VoiceProfile {
+    /// Get the frequency range for this voice profile
+    /// Returns (`min_freq_hz`, `max_freq_hz`)
+    pub const fn freq_range(self) -> Option<(f32, f32)> {
+        match self {
+            Self::NoProfile => None,
+            Self::Soprano => Some((261.63, 1046.50)), // C4-C6
+            Self::Mezzo => Some((220.00, 880.00)),    // A3-A5
+            Self::Alto => Some((174.61, 698.46)),     // F3-F5
+            Self::Tenor => Some((130.81, 523.25)),    // C3-C5
+            Self::Baritone => Some((110.00, 440.00)), // A2-A4
+            Self::Bass => Some((65.41, 261.63)),      // C2-C4
+            Self::Custom { min_hz, max_hz } => Some((min_hz, max_hz)),
+        }
+    }
+
+    /// Get all available profiles as strings for UI selection
+    pub const fn all_profiles() -> &'static [&'static str] {
+        &[
+            "no_profile",
+            "soprano",
+            "mezzo",
+            "alto",
+            "tenor",
+            "baritone",
+            "bass",
+        ]
+    }
+
+    /// Parse string to `VoiceProfile`
+    /// "custom" needs bounds, so it's resolved by `AnalysisParams::get_profile` instead
+    /// Unknown names fall back to `NoProfile` rather than failing, hence not `FromStr`
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "soprano" => Self::Soprano,
+            "mezzo" => Self::Mezzo,
+            "alto" => Self::Alto,
+            "tenor" => Self::Tenor,
+            "baritone" => Self::Baritone,
+            "bass" => Self::Bass,
+            _ => Self::NoProfile,
+        }
+    }
+
+    /// Get string representation
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::NoProfile => "no_profile",
+            Self::Soprano => "soprano",
+            Self::Mezzo => "mezzo",
+            Self::Alto => "alto",
+            Self::Tenor => "tenor",
+            Self::Baritone => "baritone",
+            Self::Bass => "bass",
+            Self::Custom { .. } => "custom",
+        }
+    }
+
+    /// Default note-ranking weights for this profile
+    /// Low voices keep the bass bias (weak fundamentals need the help); higher voices
+    /// rely on confidence and intensity since the profile already limits the range
+    pub const fn score_weights(self) -> ScoreWeights {
+        match self {
+            Self::NoProfile | Self::Bass | Self::Baritone => ScoreWeights::BASS_BIASED,
+            Self::Tenor | Self::Alto => ScoreWeights {
+                low_frequency: 0.35,
+                confidence: 0.35,
+                intensity: 0.3,
+            },
+            Self::Mezzo | Self::Soprano | Self::Custom { .. } => ScoreWeights {
+                low_frequency: 0.0,
+                confidence: 0.5,
+                intensity: 0.5,
+            },
+        }
+    }
+}
+
+/// Weights of the three terms used to rank detected notes
+/// score = `low_frequency` * bass bonus + `confidence` * confidence + `intensity` * intensity
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ScoreWeights {
+    pub low_frequency: f32,
+    pub confidence: f32,
+    pub intensity: f32,
+}
+
+impl ScoreWeights {
+    /// Original ranking: strongly favors bass fundamentals
+    pub const BASS_BIASED: Self = Self {
+        low_frequency: 0.7,
+        confidence: 0.2,
+        intensity: 0.1,
+    };
+
+    /// Chords: no bass bias, so every voice of a chord competes on its own strength
+    pub const POLYPHONIC: Self = Self {
+        low_frequency: 0.0,
+        confidence: 0.5,
+        intensity: 0.5,
+    };
+
+    /// Every weight is finite and non-negative
+    pub fn is_valid(&self) -> bool {
+        [self.low_frequency, self.confidence, self.intensity]
+            .iter()
+            .all(|w| w.is_finite() && *w >= 0.0)
+    }
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self::BASS_BIASED
+    }
+}
+
+/// What the default note ranking is tuned for
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Scoring {
+    /// One voice: the profile's weights, which favor bass fundamentals for low voices
+    #[serde(rename = "melody")]
+    #[default]
+    Melody,
+    /// Chords: `ScoreWeights::POLYPHONIC`, every note ranked by strength alone
+    #[serde(rename = "polyphonic")]
+    Polyphonic,
+}
+
+/// How `intensity` is scaled in responses
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum IntensityMode {
+    /// Spectral peak power, capped at 1.0: follows the input gain
+    #[serde(rename = "raw")]
+    #[default]
+    Raw,
+    /// Scaled so the strongest detected note is 1.0: same contrast at any input gain
+    #[serde(rename = "normalized")]
+    Normalized,
+}
+
+/// How note names are spelled in responses
+/// Analysis always works on scientific names; the scheme is applied when the response is built
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum NamingScheme {
+    /// English scientific pitch notation: "A4", "C#5", "B3"
+    #[serde(rename = "scientific")]
+    #[default]
+    Scientific,
+    /// German: B natural is "H", B flat is "B", other accidentals as suffixes ("Fis4", "Cis5")
+    #[serde(rename = "german")]
+    German,
+    /// Fixed-do solfège: "La4", "Do#5", "Si3"
+    #[serde(rename = "solfege")]
+    Solfege,
+}
+
+impl NamingScheme {
+    /// Name of a pitch class (semitones above C, 0-11) in this scheme
+    pub const fn pitch_class_name(self, pitch_class: u8) -> &'static str {
+        const SCIENTIFIC: [&str; 12] = [
+            "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+        ];
+        const GERMAN: [&str; 12] = [
+            "C", "Cis", "D", "Dis", "E", "F", "Fis", "G", "Gis", "A", "B", "H",
+        ];
+        const SOLFEGE: [&str; 12] = [
+            "Do", "Do#", "Re", "Re#", "Mi", "Fa", "Fa#", "Sol", "Sol#", "La", "La#", "Si",
+        ];
+        let names = match self {
+            Self::Scientific => &SCIENTIFIC,
+            Self::German => &GERMAN,
+            Self::Solfege => &SOLFEGE,
+        };
+        names[(pitch_class % 12) as usize]
+    }
+
+    /// Full note name, e.g. (9, 4) -> "A4" / "A4" / "La4"
+    pub fn note_name(self, pitch_class: u8, octave: i32) -> String {
+        format!("{}{octave}", self.pitch_class_name(pitch_class))
+    }
+
+    /// Respell a scientific note name ("A#4") in this scheme; unparseable names are kept as-is
+    pub fn rename(self, scientific: &str) -> String {
+        match parse_note_name(scientific) {
+            Some((pitch_class, octave)) if self != Self::Scientific => {
+                self.note_name(pitch_class, octave)
+            }
+            _ => scientific.to_string(),
+        }
+    }
+}
+
+/// Encoding of raw PCM bytes sent by clients (multi-byte samples are little-endian)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// 16-bit signed integers (the default)
+    #[serde(rename = "s16le")]
+    #[default]
+    S16le,
+    /// 32-bit floats in -1.0..1.0, as produced by most capture APIs
+    #[serde(rename = "f32le")]
+    F32le,
+    /// 8-bit G.711 µ-law, as carried by North American and Japanese telephony
+    #[serde(rename = "mulaw")]
+    Mulaw,
+    /// 8-bit G.711 A-law, the European telephony counterpart
+    #[serde(rename = "alaw")]
+    Alaw,
+}
+
+impl SampleFormat {
+    /// Size of one sample in bytes
+    pub const fn bytes_per_sample(self) -> usize {
+        match self {
+            Self::Mulaw | Self::Alaw => 1,
+            Self::S16le => 2,
+            Self::F32le => 4,
+        }
+    }
+
+    /// Check that a byte buffer holds a whole number of samples
+    ///
+    /// # Errors
+    /// Returns an error naming the expected multiple if it doesn't
+    pub fn validate_len(self, len: usize) -> Result<(), String> {
+        let size = self.bytes_per_sample();
+        if len.is_multiple_of(size) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Audio length {len} is not a multiple of {size} bytes ({self:?} samples)"
+            ))
+        }
+    }
+}
+
+/// Per-request analysis settings, resolved from `AudioData` with defaults applied
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnalysisOptions {
+    /// Voice profile used to filter detected frequencies
+    pub profile: VoiceProfile,
+    /// Max simultaneous notes extracted per chunk
+    pub max_notes: usize,
+    /// Notes with confidence below this (0.0-1.0) are dropped
+    pub min_confidence: f32,
+    /// Encoding of the raw bytes handed to `analyze_raw_bytes`
+    pub format: SampleFormat,
+    /// Noise gate: buffers with RMS below this (dBFS) return no notes
+    pub gate_db: f32,
+    /// High-pass cutoff in Hz applied before analysis (0 = off)
+    pub highpass_hz: f32,
+    /// Spelling of note names in the response
+    pub naming: NamingScheme,
+    /// Semitones added to reported note names (transposing instruments); frequencies are kept
+    pub transpose_semitones: i32,
+    /// Peaks weaker than this fraction of the strongest peak are ignored
+    pub peak_threshold: f32,
+    /// Peaks with less absolute power than this are ignored
+    pub peak_floor: f32,
+    /// Only spectrum peaks within (`min_hz`, `max_hz`) are considered; `None` = whole spectrum
+    /// Applied to the spectrum before peak picking, then intersected with `profile`'s range
+    pub band: Option<(f32, f32)>,
+    /// Octaves covered by the note table (inclusive)
+    pub octave_range: (i32, i32),
+    /// Interleaved channels in the raw bytes, averaged to mono before analysis
+    pub channels: u8,
+    /// Compute timbre features (spectral centroid) alongside the notes
+    pub include_features: bool,
+    /// Report the input level and whether the noise gate closed
+    pub include_diagnostics: bool,
+    /// Scale note intensity by the A-weighting curve (perceived rather than raw loudness)
+    pub a_weighting: bool,
+    /// Raw peak power or relative to the strongest note
+    pub intensity_mode: IntensityMode,
+    /// Weights used to rank notes for the response
+    pub score_weights: ScoreWeights,
+    /// Max ranked notes returned in the response
+    pub max_results: usize,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        Self {
+            profile: VoiceProfile::NoProfile,
+            max_notes: DEFAULT_MAX_NOTES,
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
+            format: SampleFormat::S16le,
+            gate_db: DEFAULT_GATE_DB,
+            highpass_hz: DEFAULT_HIGHPASS_HZ,
+            naming: NamingScheme::Scientific,
+            transpose_semitones: 0,
+            peak_threshold: DEFAULT_PEAK_THRESHOLD,
+            peak_floor: DEFAULT_PEAK_FLOOR,
+            band: None,
+            octave_range: (DEFAULT_MIN_OCTAVE, DEFAULT_MAX_OCTAVE),
+            channels: 1,
+            include_features: false,
+            include_diagnostics: false,
+            a_weighting: false,
+            intensity_mode: IntensityMode::Raw,
+            score_weights: ScoreWeights::default(),
+            max_results: DEFAULT_MAX_RESULTS,
+        }
+    }
+}
+
+impl AnalysisOptions {
+    /// Bytes per interleaved frame (one sample for every channel)
+    pub fn frame_size(&self) -> usize {
+        self.format.bytes_per_sample() * usize::from(self.channels.max(1))
+    }
+}
+
+/// Analyzer output for one buffer, before notes are ranked for the response
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkAnalysis {
+    /// Detected (note, confidence, intensity, measured frequency in Hz, octave ambiguous) tuples
+    pub notes: Vec<(String, f32, f32, f32, bool)>,
+    /// RMS level of the buffer in dBFS
+    pub rms_db: f32,
+    /// The buffer was quieter than `options.gate_db`, so no notes were searched for
+    pub gated: bool,
+    /// Fraction of samples at or near full scale (see `CLIP_LEVEL`)
+    pub clip_ratio: f32,
+    /// Mono samples actually analyzed, after decoding and downmixing
+    /// A trailing partial sample or frame in the raw bytes isn't counted
+    pub samples_analyzed: usize,
+    /// Spectral centroid in Hz, when `include_features` was requested
+    pub spectral_centroid_hz: Option<f32>,
+    /// FFT length used (after zero-padding); `None` if the buffer was gated or empty
+    pub fft_size: Option<usize>,
+}
+
+impl Default for ChunkAnalysis {
+    fn default() -> Self {
+        Self {
+            notes: Vec::new(),
+            rms_db: MIN_RMS_DB,
+            gated: false,
+            clip_ratio: 0.0,
+            samples_analyzed: 0,
+            spectral_centroid_hz: None,
+            fft_size: None,
+        }
+    }
+}
+
+/// Single note detection result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedNote {
+    pub note: String,
+    pub confidence: f32,
+    /// Power/intensity of the note (0.0-1.0, where 1.0 is maximum loudness)
+    pub intensity: f32,
+    /// Measured frequency of the spectral peak in Hz
+    #[serde(default)]
+    pub frequency: f32,
+    /// How far the measured frequency is from the note's equal-tempered pitch
+    /// (cents, positive = sharp)
+    #[serde(default)]
+    pub cents_off: f32,
+    /// The note an octave below (or above) was nearly as strong, so the octave may be off
+    #[serde(default)]
+    pub ambiguous: bool,
+    /// Pitch oscillation of a sustained note, only reported across batch frames
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vibrato: Option<Vibrato>,
+    /// Rise time from 10% to 90% of the note's peak intensity in ms, only reported across
+    /// batch frames: short for plucked or percussive notes, long for bowed or sung ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attack_ms: Option<f32>,
+}
+
+/// Complete analysis result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisResult {
+    pub notes: Vec<DetectedNote>,
+    pub sample_rate: u32,
+    pub samples_analyzed: usize,
+    pub timestamp: f64,
+    /// RMS loudness of the analyzed buffer in dBFS (0 = full scale, -120 = silence)
+    #[serde(default = "min_rms_db")]
+    pub rms_db: f32,
+    /// A significant fraction of the input sat at full scale: the harmonics clipping adds can
+    /// fool detection, so the source gain should come down
+    #[serde(default)]
+    pub clipped: bool,
+    /// Fraction of input samples at or near full scale (0.0-1.0)
+    #[serde(default)]
+    pub clip_ratio: f32,
+    /// Chord named from the detected notes, when they form one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chord: Option<ChordMatch>,
+    /// Power-weighted mean frequency in Hz ("brightness"), only when `include_features` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spectral_centroid_hz: Option<f32>,
+    /// FFT length the buffer was analyzed with, zero-padded to a fast size
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fft_size: Option<usize>,
+    /// Server-side timing breakdown, only when `include_timing` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<RequestTiming>,
+    /// Input level and noise gate outcome, only when `include_diagnostics` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagnostics: Option<Diagnostics>,
+}
+
+/// Why a buffer did (or didn't) produce notes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Diagnostics {
+    /// RMS level the noise gate compared against `gate_db`
+    pub rms_db: f32,
+    /// The buffer was below the noise gate, so it was treated as silence
+    pub gated: bool,
+}
+
+/// Where the time went while handling one `/analyze` request
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RequestTiming {
+    /// Decoding + FFT + note extraction
+    pub analysis_ms: u64,
+    /// Ranking notes and building the result
+    pub convert_us: u64,
+    /// Serializing the response body (measured without the timing block)
+    pub serialize_ms: u64,
+    /// Whole handler, from the deserialized request to the serialized response
+    pub total_ms: u64,
+}
+
+const fn min_rms_db() -> f32 {
+    MIN_RMS_DB
+}
+
+/// Log-spaced magnitude spectrum of one buffer (for spectrum visualization)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpectrumResult {
+    /// Bin center frequencies in Hz, ascending
+    pub frequencies: Vec<f32>,
+    /// Magnitude per bin, normalized so the strongest bin is 1.0
+    pub magnitudes: Vec<f32>,
+    pub sample_rate: u32,
+    pub samples_analyzed: usize,
+    pub timestamp: f64,
+}
+
+/// Optional analysis settings shared by every analyze route
+/// Flattened into request bodies, so clients send these as top-level fields
+#[derive(Debug, Default, Deserialize)]
+pub struct AnalysisParams {
+    #[serde(default)]
+    pub profile: Option<String>, // Voice profile for filtering notes
+    #[serde(default)]
+    pub custom_range: Option<[f32; 2]>, // [min_hz, max_hz] when profile is "custom"
+    #[serde(default)]
+    pub max_notes: Option<usize>, // Max simultaneous notes to extract (default 5)
+    #[serde(default)]
+    pub min_confidence: Option<f32>, // Drop notes below this confidence (default 0.30)
+    #[serde(default)]
+    pub format: SampleFormat, // Raw PCM encoding: "s16le" (default), "f32le", "mulaw" or "alaw"
+    #[serde(default)]
+    pub gate_db: Option<f32>, // Noise gate threshold in dBFS (default -50, -120 disables)
+    #[serde(default)]
+    pub highpass_hz: Option<f32>, // Rumble/DC high-pass cutoff in Hz (default 40, 0 disables)
+    #[serde(default)]
+    pub naming: NamingScheme, // Note spelling: "scientific" (default), "german" or "solfege"
+    #[serde(default)]
+    pub transpose_semitones: Option<i32>, // Shift reported note names, e.g. 2 for a Bb clarinet
+    #[serde(default)]
+    pub peak_threshold: Option<f32>, // Min peak power relative to the strongest (default 0.10)
+    #[serde(default)]
+    pub peak_floor: Option<f32>, // Min absolute peak power (default 0.05)
+    #[serde(default)]
+    pub band: Option<[f32; 2]>, // [min_hz, max_hz] of the spectrum searched for peaks
+    #[serde(default)]
+    pub min_octave: Option<i32>, // Lowest octave in the note table (default 1, min 0)
+    #[serde(default)]
+    pub max_octave: Option<i32>, // Highest octave in the note table (default 7, max 9)
+    #[serde(default)]
+    pub channels: Option<u8>, // Interleaved channels in the raw PCM (default 1 = mono)
+    #[serde(default)]
+    pub include_features: bool, // Also report timbre features like spectral_centroid_hz
+    #[serde(default)]
+    pub a_weighting: bool, // Scale intensity by the A-weighting curve (perceived loudness)
+    #[serde(default)]
+    pub intensity_mode: IntensityMode, // "raw" (default) or "normalized" (strongest note = 1.0)
+    #[serde(default)]
+    pub score_weights: Option<ScoreWeights>, // Note ranking weights (default depends on profile)
+    #[serde(default)]
+    pub scoring: Scoring, // Default ranking: "melody" (default) or "polyphonic" (no bass bias)
+    #[serde(default)]
+    pub max_results: Option<usize>, // Max ranked notes in the response (default 3)
+    #[serde(default)]
+    pub include_timing: bool, // Add a server-side timing breakdown to the response
+    #[serde(default)]
+    pub include_diagnostics: bool, // Report the input RMS and whether the noise gate closed
+}
+
+impl AnalysisParams {
+    /// Get the voice profile from the optional profile string
+    /// `"custom"` uses `custom_range`; a missing or invalid range falls back to `NoProfile`
+    #[must_use]
+    pub fn get_profile(&self) -> VoiceProfile {
+        match self.profile.as_deref() {
+            Some("custom") => match self.custom_range {
+                Some([min_hz, max_hz])
+                    if min_hz.is_finite()
+                        && max_hz.is_finite()
+                        && 0.0 < min_hz
+                        && min_hz < max_hz =>
+                {
+                    VoiceProfile::Custom { min_hz, max_hz }
+                }
+                range => {
+                    log::warn!("Ignoring custom profile with invalid custom_range {range:?}");
+                    VoiceProfile::NoProfile
+                }
+            },
+            Some(profile_str) => VoiceProfile::from_str(profile_str),
+            None => VoiceProfile::NoProfile,
+        }
+    }
+
+    /// Get the number of notes to extract, clamped to `1..=MAX_NOTES_LIMIT`
+    #[must_use]
+    pub fn get_max_notes(&self) -> usize {
+        self.max_notes
+            .unwrap_or(DEFAULT_MAX_NOTES)
+            .clamp(1, MAX_NOTES_LIMIT)
+    }
+
+    /// Get the number of ranked notes to return, clamped to `1..=MAX_NOTES_LIMIT`
+    #[must_use]
+    pub fn get_max_results(&self) -> usize {
+        self.max_results
+            .unwrap_or(DEFAULT_MAX_RESULTS)
+            .clamp(1, MAX_NOTES_LIMIT)
+    }
+
+    /// Get the minimum note confidence, clamped to `0.0..=1.0`
+    #[must_use]
+    pub fn get_min_confidence(&self) -> f32 {
+        self.min_confidence
+            .filter(|c| c.is_finite())
+            .unwrap_or(DEFAULT_MIN_CONFIDENCE)
+            .clamp(0.0, 1.0)
+    }
+
+    /// Get the noise gate threshold, clamped to `MIN_RMS_DB..=0.0`
+    #[must_use]
+    pub fn get_gate_db(&self) -> f32 {
+        self.gate_db
+            .filter(|db| db.is_finite())
+            .unwrap_or(DEFAULT_GATE_DB)
+            .clamp(MIN_RMS_DB, 0.0)
+    }
+
+    /// Get the high-pass cutoff, clamped to `0.0..=MAX_HIGHPASS_HZ`
+    #[must_use]
+    pub fn get_highpass_hz(&self) -> f32 {
+        self.highpass_hz
+            .filter(|hz| hz.is_finite())
+            .unwrap_or(DEFAULT_HIGHPASS_HZ)
+            .clamp(0.0, MAX_HIGHPASS_HZ)
+    }
+
+    /// Get the relative peak threshold, clamped to `0.0..=1.0`
+    #[must_use]
+    pub fn get_peak_threshold(&self) -> f32 {
+        self.peak_threshold
+            .filter(|ratio| ratio.is_finite())
+            .unwrap_or(DEFAULT_PEAK_THRESHOLD)
+            .clamp(0.0, 1.0)
+    }
+
+    /// Get the absolute peak floor; negative or non-finite values fall back to the default
+    #[must_use]
+    pub fn get_peak_floor(&self) -> f32 {
+        self.peak_floor
+            .filter(|floor| floor.is_finite() && *floor >= 0.0)
+            .unwrap_or(DEFAULT_PEAK_FLOOR)
+    }
+
+    /// Get the frequency band of interest; an invalid band (non-finite, negative or
+    /// min >= max) is ignored
+    #[must_use]
+    pub fn get_band(&self) -> Option<(f32, f32)> {
+        match self.band? {
+            [min_hz, max_hz]
+                if min_hz.is_finite() && max_hz.is_finite() && 0.0 <= min_hz && min_hz < max_hz =>
+            {
+                Some((min_hz, max_hz))
+            }
+            band => {
+                log::warn!("Ignoring invalid band {band:?}");
+                None
+            }
+        }
+    }
+
+    /// Get the note table's octave range, clamped to `LOWEST_OCTAVE..=HIGHEST_OCTAVE`
+    /// A reversed range (min > max) falls back to the defaults
+    #[must_use]
+    pub fn get_octave_range(&self) -> (i32, i32) {
+        let min_octave = self
+            .min_octave
+            .unwrap_or(DEFAULT_MIN_OCTAVE)
+            .clamp(LOWEST_OCTAVE, HIGHEST_OCTAVE);
+        let max_octave = self
+            .max_octave
+            .unwrap_or(DEFAULT_MAX_OCTAVE)
+            .clamp(LOWEST_OCTAVE, HIGHEST_OCTAVE);
+
+        if min_octave > max_octave {
+            log::warn!("Ignoring octave range {min_octave}..={max_octave} (min > max)");
+            (DEFAULT_MIN_OCTAVE, DEFAULT_MAX_OCTAVE)
+        } else {
+            (min_octave, max_octave)
+        }
+    }
+
+    /// Get the note ranking weights: explicit `score_weights` if valid, else the defaults for
+    /// `scoring` (the profile's weights for melody, `ScoreWeights::POLYPHONIC` for chords)
+    #[must_use]
+    pub fn get_score_weights(&self) -> ScoreWeights {
+        let default_weights = || match self.scoring {
+            Scoring::Melody => self.get_profile().score_weights(),
+            Scoring::Polyphonic => ScoreWeights::POLYPHONIC,
+        };
+        match self.score_weights {
+            Some(weights) if weights.is_valid() => weights,
+            Some(weights) => {
+                log::warn!("Ignoring invalid score_weights {weights:?} (must be finite and >= 0)");
+                default_weights()
+            }
+            None => default_weights(),
+        }
+    }
+
+    /// Get the interleaved channel count, clamped to `1..=MAX_CHANNELS`
+    #[must_use]
+    pub fn get_channels(&self) -> u8 {
+        self.channels.unwrap_or(1).clamp(1, MAX_CHANNELS)
+    }
+
+    /// Get the note-name transposition, clamped to `±MAX_TRANSPOSE_SEMITONES`
+    #[must_use]
+    pub fn get_transpose_semitones(&self) -> i32 {
+        self.transpose_semitones
+            .unwrap_or(0)
+            .clamp(-MAX_TRANSPOSE_SEMITONES, MAX_TRANSPOSE_SEMITONES)
+    }
+
+    /// Resolve all analysis settings for this request
+    #[must_use]
+    pub fn analysis_options(&self) -> AnalysisOptions {
+        AnalysisOptions {
+            profile: self.get_profile(),
+            max_notes: self.get_max_notes(),
+            min_confidence: self.get_min_confidence(),
+            format: self.format,
+            gate_db: self.get_gate_db(),
+            highpass_hz: self.get_highpass_hz(),
+            naming: self.naming,
+            transpose_semitones: self.get_transpose_semitones(),
+            peak_threshold: self.get_peak_threshold(),
+            peak_floor: self.get_peak_floor(),
+            band: self.get_band(),
+            octave_range: self.get_octave_range(),
+            channels: self.get_channels(),
+            include_features: self.include_features,
+            include_diagnostics: self.include_diagnostics,
+            a_weighting: self.a_weighting,
+            intensity_mode: self.intensity_mode,
+            score_weights: self.get_score_weights(),
+            max_results: self.get_max_results(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AudioData {
+    pub audio_data: String, // Direct String for base64
+    pub sample_rate: u32,
+    #[serde(default)]
+    pub target_rate: Option<u32>, // Resample to this rate before analysis (e.g. 44100)
+    #[serde(default)]
+    pub analyze_tail_ms: Option<u32>, // Analyze only the last N ms of the buffer (default: all of it)
+    #[serde(flatten)]
+    pub params: AnalysisParams,
+}
+
+impl AudioData {
+    /// Decode base64-encoded audio data to bytes
+    ///
+    /// # Errors
+    /// Returns an error if the base64 decoding fails
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        STANDARD
+            .decode(&self.audio_data)
+            .map_err(|e| format!("Base64 decode error: {e}"))
+    }
+}
@@ -0,0 +1,180 @@
+use crate::audio_analyzer::KNOWN_NOTE_FREQUENCY;
+use crate::models::NamingScheme;
+
+/// Convert note name to its equal-tempered frequency (for scoring)
+/// E.g., "A4" -> 440 Hz, "C4" -> 261.63 Hz, "C#5" -> 554.37 Hz
+/// Unparseable names fall back to A4 so they score as a mid-range note
+pub fn note_to_frequency(note_name: &str) -> f32 {
+    parse_note_name(note_name).map_or(KNOWN_NOTE_FREQUENCY, |(pitch_class, octave)| {
+        pitch_frequency(i32::from(pitch_class), octave)
+    })
+}
+
+/// Equal-tempered frequency of a pitch class (semitones above C) in a given octave
+/// Tuned to A4 = `KNOWN_NOTE_FREQUENCY`; C0 is MIDI note 12
+pub fn pitch_frequency(pitch_class: i32, octave: i32) -> f32 {
+    pitch_frequency_at(pitch_class, octave, KNOWN_NOTE_FREQUENCY)
+}
+
+/// `pitch_frequency` tuned to A4 = `reference_hz` (e.g. 432 Hz or baroque 415 Hz)
+pub fn pitch_frequency_at(pitch_class: i32, octave: i32, reference_hz: f32) -> f32 {
+    let note_num = (octave * 12) + pitch_class + 12;
+    let semitones_from_a4 = note_num - 69; // A4 is MIDI 69
+    #[allow(clippy::cast_precision_loss, clippy::suboptimal_flops)]
+    let frequency = reference_hz * (semitones_from_a4 as f32 / 12.0).exp2();
+    frequency
+}
+
+/// Signed distance in cents from `reference` to `frequency` (positive = sharp)
+/// Non-positive inputs give 0 rather than NaN/infinity
+pub fn cents_between(frequency: f32, reference: f32) -> f32 {
+    if frequency <= 0.0 || reference <= 0.0 {
+        return 0.0;
+    }
+    1200.0 * (frequency / reference).log2()
+}
+
+/// Bonus for low frequencies (bass notes)
+/// Lower frequencies get MUCH higher bonus to compensate for lower natural amplitude
+/// Uses inverse log scale: lower freq = exponentially higher score
+pub fn low_frequency_bonus(freq: f32) -> f32 {
+    // Logarithmic inverse: 50 Hz -> 1.0, 100 Hz -> 0.85, 200 Hz -> 0.65, 400 Hz -> 0.40, 800 Hz -> 0.0
+    // Formula: 1.0 / (1.0 + (freq / 100.0).log2())
+    let bonus = 1.0 / (1.0 + (freq / 50.0).log2());
+    bonus.clamp(0.0, 1.0)
+}
+
+/// A-weighting (IEC 61672) as a linear amplitude gain, normalized to 1.0 at 1 kHz
+/// Follows the ear's insensitivity to lows: about -19 dB at 100 Hz, -30 dB at 50 Hz
+pub fn a_weighting_gain(frequency: f32) -> f32 {
+    if frequency <= 0.0 {
+        return 0.0;
+    }
+    let f2 = f64::from(frequency).powi(2);
+    let r_a = 12194.0_f64.powi(2) * f2 * f2
+        / ((f2 + 20.6_f64.powi(2))
+            * ((f2 + 107.7_f64.powi(2)) * (f2 + 737.9_f64.powi(2))).sqrt()
+            * (f2 + 12194.0_f64.powi(2)));
+    // +2.0 dB brings the curve to 0 dB at 1 kHz
+    #[allow(clippy::cast_possible_truncation)]
+    let gain = (r_a * 10.0_f64.powf(2.0 / 20.0)) as f32;
+    gain
+}
+
+/// Weight confidence scores
+pub const fn confidence_weight(confidence: f32) -> f32 {
+    // Higher confidence = better score
+    confidence.clamp(0.0, 1.0)
+}
+
+/// Parse a note name like "A4", "C#3" or "Eb5" into (pitch class, octave)
+/// Pitch class is semitones above C (C=0 ... B=11); accidentals `#` and `b` may be repeated
+/// Returns `None` for anything that isn't a note letter followed by an octave number
+pub fn parse_note_name(note_name: &str) -> Option<(u8, i32)> {
+    let mut chars = note_name.chars();
+    let natural: i32 = match chars.next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+
+    let rest = chars.as_str();
+    let octave_start = rest.find(|c: char| c != '#' && c != 'b')?;
+    let (accidentals, octave) = rest.split_at(octave_start);
+    let octave: i32 = octave.parse().ok()?;
+
+    let shift: i32 = accidentals
+        .chars()
+        .map(|c| if c == '#' { 1 } else { -1 })
+        .sum();
+
+    // Accidentals may cross the octave boundary (Cb4 = B3, B#3 = C4)
+    let semitone = natural + shift;
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    let pitch_class = semitone.rem_euclid(12) as u8;
+    Some((pitch_class, octave + semitone.div_euclid(12)))
+}
+
+/// Shift a note name by `semitones`, e.g. ("C4", 2) -> "D4", ("B3", 1) -> "C4"
+/// The result is spelled with sharps; unparseable names are returned unchanged
+pub fn transpose_note_name(note_name: &str, semitones: i32) -> String {
+    match parse_note_name(note_name) {
+        Some((pitch_class, octave)) if semitones != 0 => {
+            let position = octave * 12 + i32::from(pitch_class) + semitones;
+            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            let pitch_class = position.rem_euclid(12) as u8;
+            NamingScheme::Scientific.note_name(pitch_class, position.div_euclid(12))
+        }
+        _ => note_name.to_string(),
+    }
+}
+
+/// Replace NaN and infinite samples with silence, returning how many were replaced
+/// Float PCM from clients is untrusted: one NaN would poison the whole spectrum
+pub fn sanitize_samples(samples: &mut [f32]) -> usize {
+    let mut replaced = 0;
+    for sample in samples.iter_mut().filter(|sample| !sample.is_finite()) {
+        *sample = 0.0;
+        replaced += 1;
+    }
+    replaced
+}
+
+/// Average interleaved multi-channel samples down to mono
+/// A trailing partial frame (fewer than `channels` samples) is dropped
+pub fn downmix_to_mono(interleaved: Vec<f32>, channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return interleaved;
+    }
+    interleaved
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transpose_note_name_crosses_octaves() {
+        assert_eq!(transpose_note_name("C4", 2), "D4");
+        assert_eq!(transpose_note_name("B3", 1), "C4");
+        assert_eq!(transpose_note_name("C4", -1), "B3");
+        assert_eq!(transpose_note_name("Eb5", -3), "C5");
+        assert_eq!(transpose_note_name("A4", 12), "A5");
+        assert_eq!(transpose_note_name("noise", 2), "noise");
+        assert_eq!(transpose_note_name("Bb4", 0), "Bb4");
+    }
+
+    #[test]
+    fn test_note_to_frequency_any_octave() {
+        assert!((note_to_frequency("C5") - 523.25).abs() < 0.01);
+        assert!((note_to_frequency("A6") - 1760.0).abs() < 0.01);
+        assert!((note_to_frequency("A4") - 440.0).abs() < 0.01);
+        assert!((note_to_frequency("C1") - 32.70).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_note_to_frequency_accidentals() {
+        assert!((note_to_frequency("C#5") - 554.37).abs() < 0.01);
+        assert_eq!(note_to_frequency("Db5"), note_to_frequency("C#5"));
+        // Unknown names score like A4
+        assert_eq!(note_to_frequency("H2"), 440.0);
+    }
+
+    #[test]
+    fn test_a_weighting_curve() {
+        let db = |frequency: f32| 20.0 * a_weighting_gain(frequency).log10();
+        assert!(db(1000.0).abs() < 0.01);
+        assert!((db(100.0) + 19.1).abs() < 0.1, "{}", db(100.0));
+        assert!((db(50.0) + 30.2).abs() < 0.1, "{}", db(50.0));
+        assert!((db(2500.0) - 1.3).abs() < 0.1, "{}", db(2500.0));
+        assert_eq!(a_weighting_gain(0.0), 0.0);
+    }
+}
@@ -0,0 +1,172 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::cents_between;
+
+/// Vocal vibrato oscillates at roughly 4-8 Hz; slower drifts and faster trills aren't reported
+const MIN_VIBRATO_RATE_HZ: f32 = 3.0;
+const MAX_VIBRATO_RATE_HZ: f32 = 10.0;
+
+/// Oscillations shallower than this (peak-to-peak) are pitch jitter, not vibrato
+const MIN_VIBRATO_DEPTH_CENTS: f32 = 20.0;
+
+/// Normalized autocorrelation the trajectory needs at the vibrato period to count as periodic
+const MIN_PERIODICITY: f32 = 0.4;
+
+/// A larger jump between consecutive frames starts a new note
+const MAX_PITCH_STEP_CENTS: f32 = 150.0;
+
+/// Periodic pitch oscillation of a sustained note
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Vibrato {
+    /// Oscillations per second
+    pub rate_hz: f32,
+    /// Peak-to-peak pitch swing in cents
+    pub depth_cents: f32,
+}
+
+/// Split a per-frame pitch track into runs of one sustained note
+///
+/// `None` frames (no note detected) end a run, as does a jump of more than
+/// `MAX_PITCH_STEP_CENTS` between neighbouring frames. Returns index ranges into `track`.
+pub fn sustained_segments(track: &[Option<f32>]) -> Vec<Range<usize>> {
+    let mut segments = Vec::new();
+    let mut start = None;
+
+    for (idx, frequency) in track.iter().enumerate() {
+        let continues = match (idx.checked_sub(1).and_then(|prev| track[prev]), frequency) {
+            (Some(prev), Some(frequency)) => {
+                cents_between(*frequency, prev).abs() <= MAX_PITCH_STEP_CENTS
+            }
+            _ => false,
+        };
+        if !continues {
+            if let Some(start) = start.take() {
+                segments.push(start..idx);
+            }
+            start = frequency.map(|_| idx);
+        }
+    }
+    if let Some(start) = start {
+        segments.push(start..track.len());
+    }
+    segments
+}
+
+/// Estimate the vibrato of one sustained note from its evenly spaced pitch track
+///
+/// `frame_rate` is frames per second (1 / hop). The rate comes from the autocorrelation peak of
+/// the pitch trajectory (in cents around its mean), the depth from its RMS (2√2 × RMS is the
+/// peak-to-peak swing of a sine). Returns `None` for steady tones, for tracks too short to hold
+/// two periods of the slowest vibrato, and when frames are too far apart to resolve one.
+pub fn detect_vibrato(frequencies: &[f32], frame_rate: f32) -> Option<Vibrato> {
+    let valid_rate = frame_rate.is_finite() && frame_rate > 0.0;
+    if !valid_rate || frequencies.is_empty() || frequencies.iter().any(|f| *f <= 0.0) {
+        return None;
+    }
+
+    // At least two frames per period, and two periods of the slowest rate in the track
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let (min_lag, max_lag) = (
+        ((frame_rate / MAX_VIBRATO_RATE_HZ).ceil() as usize).max(2),
+        (frame_rate / MIN_VIBRATO_RATE_HZ).floor() as usize,
+    );
+    let len = frequencies.len();
+    if max_lag < min_lag || len < 2 * max_lag + 1 {
+        return None;
+    }
+
+    let mean_hz = frequencies.iter().sum::<f32>() / len as f32;
+    let cents: Vec<f32> = frequencies
+        .iter()
+        .map(|&frequency| cents_between(frequency, mean_hz))
+        .collect();
+    let mean_cents = cents.iter().sum::<f32>() / len as f32;
+    let cents: Vec<f32> = cents.iter().map(|c| c - mean_cents).collect();
+
+    let energy: f32 = cents.iter().map(|c| c * c).sum();
+    let depth_cents = 2.0 * std::f32::consts::SQRT_2 * (energy / len as f32).sqrt();
+    if depth_cents < MIN_VIBRATO_DEPTH_CENTS {
+        return None;
+    }
+
+    // Unbiased normalized autocorrelation, so longer lags aren't penalized for fewer overlaps
+    let autocorrelation = |lag: usize| -> f32 {
+        let sum: f32 = cents.iter().zip(&cents[lag..]).map(|(a, b)| a * b).sum();
+        sum / energy * len as f32 / (len - lag) as f32
+    };
+
+    let (lag, periodicity) = (min_lag..=max_lag)
+        .map(|lag| (lag, autocorrelation(lag)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if periodicity < MIN_PERIODICITY {
+        return None;
+    }
+
+    // Parabolic interpolation between the neighbouring lags for a finer period
+    let (before, after) = (autocorrelation(lag - 1), autocorrelation(lag + 1));
+    let curvature = before - 2.0 * periodicity + after;
+    let offset = if curvature < 0.0 {
+        (0.5 * (before - after) / curvature).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    let rate_hz = frame_rate / (lag as f32 + offset);
+
+    (MIN_VIBRATO_RATE_HZ..=MAX_VIBRATO_RATE_HZ)
+        .contains(&rate_hz)
+        .then_some(Vibrato {
+            rate_hz,
+            depth_cents,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pitch track of `center` Hz swinging `depth_cents` peak-to-peak at `rate_hz`
+    fn track(center: f32, rate_hz: f32, depth_cents: f32, frame_rate: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / frame_rate;
+                let cents = depth_cents / 2.0 * (2.0 * std::f32::consts::PI * rate_hz * t).sin();
+                center * (cents / 1200.0).exp2()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_vibrato_recovers_rate_and_depth() {
+        let vibrato = detect_vibrato(&track(440.0, 5.5, 100.0, 50.0, 100), 50.0).unwrap();
+        assert!((vibrato.rate_hz - 5.5).abs() < 0.2, "{vibrato:?}");
+        assert!((vibrato.depth_cents - 100.0).abs() < 10.0, "{vibrato:?}");
+
+        // Steady, too shallow or too coarsely sampled: no vibrato
+        assert_eq!(detect_vibrato(&[440.0; 100], 50.0), None);
+        assert_eq!(
+            detect_vibrato(&track(440.0, 5.5, 10.0, 50.0, 100), 50.0),
+            None
+        );
+        assert_eq!(
+            detect_vibrato(&track(440.0, 5.5, 100.0, 5.0, 20), 5.0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sustained_segments_split_on_gaps_and_jumps() {
+        let track = [
+            Some(440.0),
+            Some(445.0),
+            None,
+            Some(440.0),
+            Some(436.0),
+            Some(523.25), // C5: a new note
+            Some(523.25),
+        ];
+        assert_eq!(sustained_segments(&track), vec![0..2, 3..5, 5..7]);
+        assert!(sustained_segments(&[None, None]).is_empty());
+    }
+}
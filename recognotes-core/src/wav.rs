@@ -0,0 +1,281 @@
+//! WAV decoding ([`wav_samples`]) and window-by-window analysis of WAV files, read lazily so a
+//! file of any length is analyzed in constant memory (see [`AudioAnalyzer::analyze_file_windows`])
+
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+use hound::WavReader;
+
+use crate::audio_analyzer::{rank_notes, AudioAnalyzer};
+use crate::models::{AnalysisOptions, DetectedNote, SampleFormat, MAX_CHANNELS};
+
+/// Interleaved samples in -1.0..1.0, decoded lazily from a WAV stream (see [`wav_samples`])
+pub type WavSamples<'a> = Box<dyn Iterator<Item = Result<f32, hound::Error>> + Send + 'a>;
+
+/// Iterator over `(start in seconds, ranked notes)` for each window of a WAV file
+///
+/// Windows start every `hop` and span `window`; like `/analyze/batch`, only whole windows are
+/// analyzed, except that a file shorter than one window is analyzed as a single short window.
+/// A read error part-way through the file is logged and ends the iteration
+pub struct FileWindows {
+    samples: WavSamples<'static>,
+    analyzer: AudioAnalyzer,
+    options: AnalysisOptions,
+    sample_rate: u32,
+    channels: usize,
+    window_frames: usize,
+    hop_frames: usize,
+    /// Interleaved samples of the upcoming window (those already read)
+    buffer: Vec<f32>,
+    /// Samples to drop before the next window when hops leave gaps between windows
+    skip: usize,
+    /// First frame of `buffer` in the file
+    position: u64,
+    emitted: bool,
+    done: bool,
+}
+
+impl std::fmt::Debug for FileWindows {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileWindows")
+            .field("sample_rate", &self.sample_rate)
+            .field("channels", &self.channels)
+            .field("window_frames", &self.window_frames)
+            .field("hop_frames", &self.hop_frames)
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FileWindows {
+    /// Open `path` and check its encoding; no audio is read until the first window is pulled
+    /// Supports 16-bit and 24-bit integer PCM, plus 32-bit float, with up to `MAX_CHANNELS`
+    /// channels (averaged to mono by `analyze_raw_bytes`)
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be opened or isn't a supported WAV, or if `window`
+    /// or `hop` is shorter than one sample
+    pub fn open(
+        analyzer: AudioAnalyzer,
+        path: &Path,
+        window: Duration,
+        hop: Duration,
+        options: &AnalysisOptions,
+    ) -> Result<Self, String> {
+        let reader = WavReader::open(path).map_err(|e| format!("Invalid WAV: {e}"))?;
+        let spec = reader.spec();
+        if spec.channels == 0 || spec.channels > u16::from(MAX_CHANNELS) {
+            return Err(format!(
+                "Unsupported WAV: {} channels (expected 1-{MAX_CHANNELS})",
+                spec.channels
+            ));
+        }
+        let samples = wav_samples(reader)?;
+
+        let frames = |duration: Duration| {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let frames = (duration.as_secs_f64() * f64::from(spec.sample_rate)).round() as usize;
+            frames
+        };
+        let (window_frames, hop_frames) = (frames(window), frames(hop));
+        if window_frames == 0 || hop_frames == 0 {
+            return Err("window and hop must each be at least one sample long".to_string());
+        }
+
+        Ok(Self {
+            samples,
+            analyzer,
+            // The window is handed over as interleaved float PCM
+            options: AnalysisOptions {
+                format: SampleFormat::F32le,
+                #[allow(clippy::cast_possible_truncation)]
+                channels: spec.channels as u8,
+                ..*options
+            },
+            sample_rate: spec.sample_rate,
+            channels: usize::from(spec.channels),
+            window_frames,
+            hop_frames,
+            buffer: Vec::with_capacity(window_frames * usize::from(spec.channels)),
+            skip: 0,
+            position: 0,
+            emitted: false,
+            done: false,
+        })
+    }
+
+    pub const fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Next interleaved sample, or `None` at the end of the file (or on a read error)
+    fn read_sample(&mut self) -> Option<f32> {
+        match self.samples.next()? {
+            Ok(sample) => Some(sample),
+            Err(e) => {
+                log::warn!("FileWindows: stopping at frame {}: {e}", self.position);
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+/// Decode a WAV stream's interleaved samples lazily, scaling integers by their full-scale value
+/// so every depth lands in -1.0..1.0
+/// Supports 16-bit and 24-bit integer PCM, plus 32-bit float
+///
+/// # Errors
+/// Returns an error for unsupported encodings (8-bit, 32-bit integer, ...); read errors come
+/// out of the iterator
+pub fn wav_samples<'a, R: Read + Send + 'a>(
+    reader: WavReader<R>,
+) -> Result<WavSamples<'a>, String> {
+    let spec = reader.spec();
+    match (spec.sample_format, spec.bits_per_sample) {
+        (hound::SampleFormat::Int, 16 | 24) => {
+            let full_scale = (1i32 << (spec.bits_per_sample - 1)) as f32;
+            Ok(Box::new(
+                reader
+                    .into_samples::<i32>()
+                    .map(move |s| s.map(|s| s as f32 / full_scale)),
+            ))
+        }
+        (hound::SampleFormat::Float, 32) => Ok(Box::new(reader.into_samples::<f32>())),
+        (format, bits) => Err(format!(
+            "Unsupported WAV encoding: {bits}-bit {format:?} (expected 16/24-bit PCM or 32-bit float)"
+        )),
+    }
+}
+
+impl Iterator for FileWindows {
+    type Item = (f64, Vec<DetectedNote>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.skip > 0 && !self.done {
+            self.read_sample()?;
+            self.skip -= 1;
+        }
+        let window_len = self.window_frames * self.channels;
+        while self.buffer.len() < window_len && !self.done {
+            match self.read_sample() {
+                Some(sample) => self.buffer.push(sample),
+                None => self.done = true,
+            }
+        }
+
+        if self.buffer.len() < window_len {
+            // A file shorter than one window still gets analyzed, once
+            if self.emitted || self.buffer.len() < self.channels {
+                return None;
+            }
+            self.done = true;
+        }
+
+        let bytes: Vec<u8> = self.buffer.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let analysis = self
+            .analyzer
+            .analyze_raw_bytes(&bytes, self.sample_rate, &self.options);
+        let notes = rank_notes(
+            analysis.notes,
+            self.options.score_weights,
+            self.options.max_results,
+        );
+        #[allow(clippy::cast_precision_loss)]
+        let start = self.position as f64 / f64::from(self.sample_rate);
+
+        // Keep the overlap with the next window, or skip the gap up to it
+        if self.hop_frames < self.window_frames {
+            self.buffer.drain(..self.hop_frames * self.channels);
+        } else {
+            self.buffer.clear();
+            self.skip = (self.hop_frames - self.window_frames) * self.channels;
+        }
+        self.position += self.hop_frames as u64;
+        self.emitted = true;
+
+        Some((start, notes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hound::{WavSpec, WavWriter};
+
+    /// Write `seconds` of 16-bit audio: A4 on the first channel, silence on the others
+    fn write_tone(name: &str, channels: u16, seconds: f32) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("recognotes-core-{}-{name}.wav", std::process::id()));
+        let spec = WavSpec {
+            channels,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for i in 0..(44100.0 * seconds) as usize {
+            let t = i as f32 / 44100.0;
+            let sample = 0.8 * (2.0 * std::f32::consts::PI * 440.0 * t).sin();
+            writer.write_sample((sample * 32767.0) as i16).unwrap();
+            for _ in 1..channels {
+                writer.write_sample(0i16).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    #[test]
+    fn test_windows_follow_the_hop_and_downmix() {
+        let path = write_tone("stereo", 2, 1.0);
+        let windows = |window_ms: u64, hop_ms: u64| {
+            FileWindows::open(
+                AudioAnalyzer::new(),
+                &path,
+                Duration::from_millis(window_ms),
+                Duration::from_millis(hop_ms),
+                &AnalysisOptions::default(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>()
+        };
+
+        // Overlapping: whole 200ms windows every 100ms -> starts 0.0 through 0.8
+        let overlapping = windows(200, 100);
+        assert_eq!(overlapping.len(), 9);
+        for (idx, (start, notes)) in overlapping.iter().enumerate() {
+            assert!((start - idx as f64 * 0.1).abs() < 1e-9, "{start}");
+            assert_eq!(notes[0].note, "A4", "window {idx}");
+        }
+
+        // Gaps between windows, and a file shorter than the window
+        let starts: Vec<f64> = windows(100, 300).into_iter().map(|(s, _)| s).collect();
+        assert_eq!(starts.len(), 4);
+        assert!((starts[3] - 0.9).abs() < 1e-9, "{starts:?}");
+        assert_eq!(windows(2000, 500).len(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_open_rejects_bad_input() {
+        let path = write_tone("bad", 1, 0.1);
+        let open = |path: &Path, window: Duration| {
+            FileWindows::open(
+                AudioAnalyzer::new(),
+                path,
+                window,
+                window,
+                &AnalysisOptions::default(),
+            )
+        };
+        assert!(open(&path, Duration::ZERO).is_err());
+        assert!(open(&path, Duration::from_millis(100)).is_ok());
+        std::fs::remove_file(&path).unwrap();
+
+        let missing = std::env::temp_dir().join("recognotes-core-does-not-exist.wav");
+        assert!(open(&missing, Duration::from_millis(100)).is_err());
+    }
+}
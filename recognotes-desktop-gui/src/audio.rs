@@ -1,22 +1,127 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::path::Path;
+use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Normalized peak at or above which a block counts as clipped (i16 full scale)
+pub const CLIP_LEVEL: f32 = 32767.0 / 32768.0;
+
+/// Peak and RMS of a block of samples, both normalized to 0.0-1.0 of full scale
+pub fn signal_levels(samples: &[i16]) -> (f32, f32) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let peak = samples.iter().map(|s| s.unsigned_abs()).max().unwrap_or(0);
+    let sum_squares: f64 = samples.iter().map(|&s| f64::from(s).powi(2)).sum();
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    let rms = ((sum_squares / samples.len() as f64).sqrt() / 32768.0) as f32;
+    (f32::from(peak) / 32768.0, rms)
+}
+
+/// Whether a normalized peak (from `signal_levels`) hit full scale
+pub fn is_clipping(peak: f32) -> bool {
+    peak >= CLIP_LEVEL
+}
+
+/// Standard capture rates offered in the UI, when the device supports them
+const COMMON_SAMPLE_RATES: [u32; 11] = [
+    8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000, 176_400, 192_000,
+];
+
+/// Common rates that fall inside at least one supported (min, max) range, ascending
+pub fn rates_within(ranges: &[(u32, u32)]) -> Vec<u32> {
+    COMMON_SAMPLE_RATES
+        .into_iter()
+        .filter(|rate| ranges.iter().any(|(min, max)| (min..=max).contains(&rate)))
+        .collect()
+}
+
+/// Peak amplitude of reference tones (1.0 = full scale)
+const TONE_AMPLITUDE: f32 = 0.3;
+/// Reference tones fade in and out over this long so they start and stop without a click
+const TONE_FADE: Duration = Duration::from_millis(10);
+
+/// Mono sine of `frequency` lasting `duration`, with a linear fade at both ends
+pub fn tone_samples(frequency: f32, sample_rate: u32, duration: Duration) -> Vec<f32> {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let len = (duration.as_secs_f32() * sample_rate as f32) as usize;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let fade_len =
+        ((TONE_FADE.as_secs_f32() * sample_rate as f32) as usize).clamp(1, len.max(2) / 2);
+
+    (0..len)
+        .map(|i| {
+            let fade = (i.min(len - 1 - i) as f32 / fade_len as f32).min(1.0);
+            let t = i as f32 / sample_rate as f32;
+            TONE_AMPLITUDE * fade * (2.0 * std::f32::consts::PI * frequency * t).sin()
+        })
+        .collect()
+}
+
+/// Output stream that plays `samples` on every channel, then silence
+fn build_tone_stream<T: cpal::Sample>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Vec<f32>,
+) -> Result<cpal::Stream, cpal::BuildStreamError> {
+    let channels = usize::from(config.channels);
+    let mut position = 0;
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            for frame in data.chunks_mut(channels) {
+                let sample = samples.get(position).copied().unwrap_or(0.0);
+                position += 1;
+                frame.fill(T::from(&sample));
+            }
+        },
+        |err| log::error!("Playback error: {err}"),
+    )
+}
+
+/// cpal error callback that logs the error and forwards it to the app
+fn stream_error_callback(errors: Sender<String>) -> impl FnMut(cpal::StreamError) + Send + 'static {
+    move |err| {
+        log::error!("Stream error: {err}");
+        // The app may already be gone on shutdown
+        let _ = errors.send(err.to_string());
+    }
+}
 
 pub struct AudioManager {
     sample_rate: u32,
+    channels: u16,
     stream: Option<cpal::Stream>,
+    // Reference tone being played (kept alive until the next tone)
+    tone_stream: Option<cpal::Stream>,
     audio_buffer: Arc<Mutex<Vec<i16>>>,
+    // Every sample of the current/last take, moved here as `audio_buffer` is consumed
+    recorded: Mutex<Vec<i16>>,
+    // (peak, rms) of the samples most recently moved into the sliding window
+    levels: Mutex<(f32, f32)>,
     recording: bool,
     selected_device: Option<String>,
+    // Errors reported by the cpal stream (e.g. the device was unplugged)
+    stream_error_sender: Sender<String>,
+    stream_errors: Receiver<String>,
 }
 
 impl AudioManager {
     pub fn new(sample_rate: u32) -> Self {
+        let (stream_error_sender, stream_errors) = std::sync::mpsc::channel();
         Self {
             sample_rate,
+            channels: 1,
             stream: None,
+            tone_stream: None,
             audio_buffer: Arc::new(Mutex::new(Vec::new())),
+            recorded: Mutex::new(Vec::new()),
+            levels: Mutex::new((0.0, 0.0)),
             recording: false,
             selected_device: None,
+            stream_error_sender,
+            stream_errors,
         }
     }
 
@@ -58,26 +163,59 @@ impl AudioManager {
         devices
     }
 
-    #[allow(clippy::too_many_lines)]
-    pub fn start_recording(&mut self) -> Result<(), String> {
-        if self.recording {
-            return Err("Already recording".to_string());
-        }
-
+    /// The selected input device, or the host default
+    fn input_device(&self) -> Result<cpal::Device, String> {
         let host = cpal::default_host();
 
         // Get the selected device, or default if none selected
-        let device = if let Some(device_name) = &self.selected_device {
+        if let Some(device_name) = &self.selected_device {
             // Find device by name
             host.input_devices()
                 .map_err(|e| format!("Failed to get input devices: {e}"))?
                 .find(|d| d.name().ok().is_some_and(|name| name == *device_name))
-                .ok_or_else(|| format!("Device '{device_name}' not found"))?
+                .ok_or_else(|| format!("Device '{device_name}' not found"))
         } else {
             // None means use default device
             host.default_input_device()
-                .ok_or_else(|| "No input device available".to_string())?
-        };
+                .ok_or_else(|| "No input device available".to_string())
+        }
+    }
+
+    /// Sample rates the selected device can capture at (from `supported_input_configs`)
+    pub fn supported_sample_rates(&self) -> Vec<u32> {
+        let ranges: Vec<(u32, u32)> = self
+            .input_device()
+            .and_then(|device| {
+                device
+                    .supported_input_configs()
+                    .map_err(|e| format!("Failed to get supported configs: {e}"))
+            })
+            .map(|configs| {
+                configs
+                    .map(|c| (c.min_sample_rate().0, c.max_sample_rate().0))
+                    .collect()
+            })
+            .unwrap_or_else(|e| {
+                log::warn!("Can't list sample rates: {e}");
+                Vec::new()
+            });
+        rates_within(&ranges)
+    }
+
+    /// Rate to ask the device for on the next `start_recording`
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        if !self.recording {
+            self.sample_rate = sample_rate;
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    pub fn start_recording(&mut self) -> Result<(), String> {
+        if self.recording {
+            return Err("Already recording".to_string());
+        }
+
+        let device = self.input_device()?;
 
         log::info!(
             "Selected input device: {}",
@@ -112,15 +250,26 @@ impl AudioManager {
                     && c.min_sample_rate() <= cpal::SampleRate(self.sample_rate)
                     && c.max_sample_rate() >= cpal::SampleRate(self.sample_rate)
             })
-            // Priority 2: Any mono + i16 (flexible sample rate)
+            // Priority 2: The requested rate in any format, mono first
+            .or_else(|| {
+                let supports_rate = |c: &&cpal::SupportedStreamConfigRange| {
+                    c.min_sample_rate() <= cpal::SampleRate(self.sample_rate)
+                        && c.max_sample_rate() >= cpal::SampleRate(self.sample_rate)
+                };
+                supported_configs
+                    .iter()
+                    .filter(supports_rate)
+                    .min_by_key(|c| c.channels())
+            })
+            // Priority 3: Any mono + i16 (flexible sample rate)
             .or_else(|| {
                 supported_configs
                     .iter()
                     .find(|c| c.channels() == 1 && c.sample_format() == cpal::SampleFormat::I16)
             })
-            // Priority 3: Mono with any format (flexible format)
+            // Priority 4: Mono with any format (flexible format)
             .or_else(|| supported_configs.iter().find(|c| c.channels() == 1))
-            // Priority 4: First available config (any channels, any format)
+            // Priority 5: First available config (any channels, any format)
             .or_else(|| supported_configs.first())
             .ok_or_else(|| "No audio configuration available".to_string())?;
 
@@ -146,8 +295,17 @@ impl AudioManager {
 
         // Update our actual sample rate for later use
         self.sample_rate = actual_sample_rate;
+        self.channels = config.channels;
+
+        // A new take starts empty
+        self.audio_buffer.lock().unwrap().clear();
+        self.recorded.lock().unwrap().clear();
+        *self.levels.lock().unwrap() = (0.0, 0.0);
 
         let audio_buffer_i16 = Arc::clone(&self.audio_buffer);
+        // Drop errors left over from a previous take
+        while self.stream_errors.try_recv().is_ok() {}
+        let error_sender = self.stream_error_sender.clone();
 
         // Build an I16 stream - try all supported formats
         let stream = match config_range.sample_format() {
@@ -157,7 +315,7 @@ impl AudioManager {
                     let mut buffer = audio_buffer_i16.lock().unwrap();
                     buffer.extend_from_slice(data);
                 },
-                |err| log::error!("Stream error: {err}"),
+                stream_error_callback(error_sender),
             ),
             cpal::SampleFormat::U16 => {
                 device.build_input_stream(
@@ -171,7 +329,7 @@ impl AudioManager {
                             buffer.push(i16_sample);
                         }
                     },
-                    |err| log::error!("Stream error: {err}"),
+                    stream_error_callback(error_sender),
                 )
             }
             cpal::SampleFormat::F32 => {
@@ -186,7 +344,7 @@ impl AudioManager {
                             buffer.push(i16_sample);
                         }
                     },
-                    |err| log::error!("Stream error: {err}"),
+                    stream_error_callback(error_sender),
                 )
             }
         }
@@ -222,6 +380,7 @@ impl AudioManager {
             .unwrap()
             .drain(..)
             .collect::<Vec<_>>();
+        self.recorded.lock().unwrap().extend_from_slice(&samples);
 
         // Convert i16 samples to bytes
         let mut audio_data = Vec::with_capacity(samples.len() * 2);
@@ -236,14 +395,22 @@ impl AudioManager {
 
     /// Add samples to sliding window buffer
     /// Used for maintaining a rolling 1-second window of audio data
-    pub fn add_to_sliding_buffer(&self, sliding_buffer: &mut Vec<i16>, buffer_size: usize) {
+    /// Returns how many new samples were added
+    pub fn add_to_sliding_buffer(
+        &self,
+        sliding_buffer: &mut Vec<i16>,
+        buffer_size: usize,
+    ) -> usize {
         let mut buffer = self.audio_buffer.lock().unwrap();
         if buffer.is_empty() {
-            return;
+            return 0;
         }
+        let added = buffer.len();
 
-        // Add all available samples to sliding buffer
+        // Add all available samples to sliding buffer (and keep them for `save_wav`)
         sliding_buffer.extend_from_slice(&buffer);
+        self.recorded.lock().unwrap().extend_from_slice(&buffer);
+        *self.levels.lock().unwrap() = signal_levels(&buffer);
         buffer.clear();
         drop(buffer);
 
@@ -252,6 +419,7 @@ impl AudioManager {
             let drain_count = sliding_buffer.len() - buffer_size;
             sliding_buffer.drain(..drain_count);
         }
+        added
     }
 
     /// Get buffered audio without stopping recording (for continuous analysis)
@@ -283,6 +451,85 @@ impl AudioManager {
         Ok(audio_data)
     }
 
+    /// Play a sine at `freq` on the default output device (e.g. the tuner's target note)
+    /// Replaces a tone that is still playing; fails if there is no output device
+    pub fn play_tone(&mut self, freq: f32, duration: Duration) -> Result<(), String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| "No output device available".to_string())?;
+        let supported = device
+            .default_output_config()
+            .map_err(|e| format!("Failed to get output config: {e}"))?;
+        let sample_format = supported.sample_format();
+        let config: cpal::StreamConfig = supported.into();
+
+        let samples = tone_samples(freq, config.sample_rate.0, duration);
+        let stream = match sample_format {
+            cpal::SampleFormat::I16 => build_tone_stream::<i16>(&device, &config, samples),
+            cpal::SampleFormat::U16 => build_tone_stream::<u16>(&device, &config, samples),
+            cpal::SampleFormat::F32 => build_tone_stream::<f32>(&device, &config, samples),
+        }
+        .map_err(|e| format!("Failed to build output stream: {e}"))?;
+        stream
+            .play()
+            .map_err(|e| format!("Failed to play tone: {e}"))?;
+
+        self.tone_stream = Some(stream);
+        Ok(())
+    }
+
+    /// Oldest unhandled stream error, if any (non-blocking)
+    pub fn take_stream_error(&self) -> Option<String> {
+        self.stream_errors.try_recv().ok()
+    }
+
+    /// Feed an error through the same callback the cpal stream uses
+    #[cfg(test)]
+    pub fn inject_stream_error(&self, err: cpal::StreamError) {
+        stream_error_callback(self.stream_error_sender.clone())(err);
+    }
+
+    /// (peak, rms) of the latest input, normalized to 0.0-1.0 (for the level meter)
+    pub fn current_levels(&self) -> (f32, f32) {
+        *self.levels.lock().unwrap()
+    }
+
+    /// Snapshot of every sample captured in the current (or last) take
+    /// Safe to call while recording: nothing is cleared or consumed
+    pub fn recorded_samples(&self) -> Vec<i16> {
+        let mut samples = self.recorded.lock().unwrap().clone();
+        samples.extend_from_slice(&self.audio_buffer.lock().unwrap());
+        samples
+    }
+
+    /// Write the current take to a 16-bit PCM `.wav` file at the actual sample rate
+    pub fn save_wav(&self, path: &Path) -> Result<(), String> {
+        let samples = self.recorded_samples();
+        if samples.is_empty() {
+            return Err("Nothing recorded yet".to_string());
+        }
+
+        let spec = hound::WavSpec {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec)
+            .map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+        for sample in samples {
+            writer
+                .write_sample(sample)
+                .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        }
+        writer
+            .finalize()
+            .map_err(|e| format!("Failed to finalize {}: {e}", path.display()))?;
+
+        log::info!("Saved recording to {}", path.display());
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub const fn is_recording(&self) -> bool {
         self.recording
@@ -291,4 +538,120 @@ impl AudioManager {
     pub const fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
+
+    /// Channel count negotiated with the device (valid once recording has started)
+    pub const fn channels(&self) -> u16 {
+        self.channels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_wav_round_trip() {
+        let manager = AudioManager::new(44100);
+        let samples: Vec<i16> = (0..441).map(|i| (i * 37 - 8000) as i16).collect();
+        // Half consumed by the sliding window, half still pending, as mid-recording
+        manager
+            .recorded
+            .lock()
+            .unwrap()
+            .extend_from_slice(&samples[..200]);
+        manager
+            .audio_buffer
+            .lock()
+            .unwrap()
+            .extend_from_slice(&samples[200..]);
+
+        let path = std::env::temp_dir().join(format!("recognotes-test-{}.wav", std::process::id()));
+        manager.save_wav(&path).unwrap();
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_rate, 44100);
+        assert_eq!(reader.spec().channels, 1);
+        let read_back: Vec<i16> = reader.samples::<i16>().map(Result::unwrap).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read_back, samples);
+
+        // Snapshotting didn't consume anything
+        assert_eq!(manager.audio_buffer.lock().unwrap().len(), 241);
+    }
+
+    #[test]
+    fn test_supported_rates_from_config_ranges() {
+        // A fixed 44.1k config plus a flexible 8k-48k one
+        let rates = rates_within(&[(44100, 44100), (8000, 48000)]);
+        assert_eq!(rates, [8000, 11025, 16000, 22050, 32000, 44100, 48000]);
+
+        // Only exact matches of a fixed-rate device
+        assert_eq!(rates_within(&[(96000, 96000)]), [96000]);
+        assert!(rates_within(&[(12345, 12346)]).is_empty());
+        assert!(rates_within(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_full_scale_input_reports_clipping() {
+        let manager = AudioManager::new(48000);
+        let mut window = Vec::new();
+
+        // A quiet sine doesn't clip
+        let quiet: Vec<i16> = (0..480)
+            .map(|i| ((f32::from(i as i16) * 0.1).sin() * 3000.0) as i16)
+            .collect();
+        manager
+            .audio_buffer
+            .lock()
+            .unwrap()
+            .extend_from_slice(&quiet);
+        manager.add_to_sliding_buffer(&mut window, 4800);
+        let (peak, rms) = manager.current_levels();
+        assert!(!is_clipping(peak));
+        assert!(rms > 0.0 && rms < peak);
+
+        // A full-scale square wave does
+        let square: Vec<i16> = (0..480)
+            .map(|i| if i % 20 < 10 { i16::MAX } else { i16::MIN })
+            .collect();
+        manager
+            .audio_buffer
+            .lock()
+            .unwrap()
+            .extend_from_slice(&square);
+        manager.add_to_sliding_buffer(&mut window, 4800);
+        let (peak, rms) = manager.current_levels();
+        assert!(is_clipping(peak));
+        assert!(rms > 0.99);
+    }
+
+    #[test]
+    fn test_tone_has_expected_fundamental_and_fades() {
+        let sample_rate = 48000;
+        let samples = tone_samples(440.0, sample_rate, Duration::from_millis(500));
+        assert_eq!(samples.len(), 24000);
+
+        // Starts and ends at silence, full amplitude in the middle
+        assert!(samples[0].abs() < 1e-3);
+        assert!(samples[samples.len() - 1].abs() < 0.05);
+        let peak = samples.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        assert!((peak - TONE_AMPLITUDE).abs() < 0.01);
+
+        // Strongest DFT bin among nearby candidates is 440 Hz
+        let magnitude = |frequency: f32| {
+            let (re, im) = samples
+                .iter()
+                .enumerate()
+                .fold((0.0f32, 0.0f32), |(re, im), (i, s)| {
+                    let phase =
+                        2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32;
+                    (re + s * phase.cos(), im + s * phase.sin())
+                });
+            re.hypot(im)
+        };
+        let fundamental = magnitude(440.0);
+        for other in [220.0, 430.0, 450.0, 880.0] {
+            assert!(fundamental > 10.0 * magnitude(other), "{other} Hz");
+        }
+    }
 }
@@ -1,16 +1,59 @@
 use crate::DetectedNote;
-use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use std::fmt;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AnalyzeRequest {
-    /// Base64-encoded audio data (faster than Vec<u8> JSON encoding)
-    pub audio_data: String,
-    pub sample_rate: u32,
-    /// Optional voice profile for filtering notes
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub profile: Option<String>,
+/// Default time to wait for an analysis response
+pub const ANALYZE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default time to wait for a health check (per attempt)
+pub const HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+/// Pause before the health check's one retry after a connection error
+const HEALTH_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// HTTP client shared by every backend call
+/// Built once so keep-alive connections are pooled instead of re-established ~50 times a second
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+/// The process-wide backend client (cheap to clone, but borrowing it is enough)
+pub fn shared_client() -> &'static reqwest::Client {
+    &HTTP_CLIENT
+}
+
+/// Why a backend call failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendError {
+    /// No answer within the timeout (backend up but slow, or a slow network)
+    Timeout(Duration),
+    /// Couldn't connect at all (refused, unknown host, ...): the backend is probably down
+    Unreachable(String),
+    /// The backend answered with an error status
+    Status(u16),
+    /// Anything else (malformed response, dropped connection mid-request, ...)
+    Other(String),
+}
+
+impl BackendError {
+    fn from_reqwest(error: &reqwest::Error) -> Self {
+        if error.is_connect() {
+            Self::Unreachable(error.to_string())
+        } else {
+            Self::Other(error.to_string())
+        }
+    }
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout(timeout) => {
+                write!(f, "Backend timed out after {:.1}s", timeout.as_secs_f32())
+            }
+            Self::Unreachable(e) => write!(f, "Backend unreachable: {e}"),
+            Self::Status(status) => write!(f, "Backend returned status: {status}"),
+            Self::Other(e) => write!(f, "Backend request failed: {e}"),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,90 +62,191 @@ pub struct AnalyzeResponse {
     pub sample_rate: u32,
     pub samples_analyzed: usize,
     pub timestamp: f64,
+    /// The server saw a significant fraction of the buffer at full scale
+    #[serde(default)]
+    pub clipped: bool,
 }
 
-/// Send audio data to the backend for analysis with timeout
-/// Uses base64 encoding for optimal performance (~1-5ms instead of slow JSON arrays)
+/// Send audio data to the backend for analysis, giving up after `timeout` (see `ANALYZE_TIMEOUT`)
+/// Posts the raw i16 PCM to `/analyze/raw` (no base64: ~33% smaller and no encode step)
 pub async fn analyze_audio(
+    client: &reqwest::Client,
     backend_url: &str,
     audio_data: Vec<u8>,
     sample_rate: u32,
     profile: Option<String>,
-) -> Result<Vec<DetectedNote>, String> {
-    let url = format!("{backend_url}/analyze");
+    timeout: Duration,
+) -> Result<AnalyzeResponse, BackendError> {
+    let url = format!("{backend_url}/analyze/raw");
     let start = Instant::now();
     let data_size = audio_data.len();
-    let profile_str = profile.as_deref().unwrap_or("no_profile").to_string();
-
-    // Encode audio as base64 (much faster than JSON array encoding)
-    let audio_b64 = STANDARD.encode(&audio_data);
-
-    let request = AnalyzeRequest {
-        audio_data: audio_b64.clone(),
-        sample_rate,
-        profile,
-    };
-
-    // Create new client for each request (reqwest handles connection pooling internally)
-    let client = reqwest::Client::new();
+    let profile_str = profile.unwrap_or_else(|| "no_profile".to_string());
 
     log::debug!(
-        "Sending to backend: {} bytes audio (base64), {} Hz sample rate, profile: {}, payload size: {}B",
-        data_size,
-        sample_rate,
-        profile_str,
-        audio_b64.len()
+        "Sending to backend: {data_size} bytes audio (raw), {sample_rate} Hz sample rate, profile: {profile_str}"
     );
 
-    let response = tokio::time::timeout(
-        std::time::Duration::from_secs(5), // 5 second timeout
-        client.post(&url).json(&request).send(),
-    )
-    .await
-    .map_err(|_| "Backend request timeout (5s)".to_string())?
-    .map_err(|e| format!("Failed to send request: {e}"))?;
+    let request = client
+        .post(&url)
+        .query(&[
+            ("sample_rate", sample_rate.to_string()),
+            ("profile", profile_str),
+            // Down to the hysteresis "off" level; the app decides what's shown
+            ("min_confidence", crate::CONFIDENCE_OFF.to_string()),
+        ])
+        .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+        .body(audio_data);
+
+    let response = tokio::time::timeout(timeout, request.send())
+        .await
+        .map_err(|_| BackendError::Timeout(timeout))?
+        .map_err(|e| BackendError::from_reqwest(&e))?;
 
     if !response.status().is_success() {
-        return Err(format!("Backend returned status: {}", response.status()));
+        return Err(BackendError::Status(response.status().as_u16()));
     }
 
     let analyze_response: AnalyzeResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse response: {e}"))?;
+        .map_err(|e| BackendError::Other(format!("Failed to parse response: {e}")))?;
 
     let elapsed = start.elapsed().as_millis();
     log::debug!(
-        "Backend analysis: {} notes, {} samples in {:.0}ms ({}KB sent, base64 encoded)",
+        "Backend analysis: {} notes, {} samples in {:.0}ms ({}KB sent, raw PCM)",
         analyze_response.notes.len(),
         analyze_response.samples_analyzed,
         elapsed,
         data_size / 1024
     );
 
-    Ok(analyze_response.notes)
+    Ok(analyze_response)
+}
+
+/// Check if backend is healthy, waiting up to `timeout` per attempt (see `HEALTH_TIMEOUT`)
+/// A connection error is retried once after a short pause, so a single dropped connection
+/// doesn't flag the backend offline
+pub async fn check_health(
+    client: &reqwest::Client,
+    backend_url: &str,
+    timeout: Duration,
+) -> Result<(), BackendError> {
+    match health_attempt(client, backend_url, timeout).await {
+        Err(BackendError::Unreachable(e)) => {
+            log::debug!("Health check couldn't connect ({e}), retrying once");
+            tokio::time::sleep(HEALTH_RETRY_DELAY).await;
+            health_attempt(client, backend_url, timeout).await
+        }
+        result => result,
+    }
 }
 
-/// Check if backend is healthy
-/// Uses fast timeout to fail quickly if backend is down
-pub async fn check_health(backend_url: &str) -> Result<(), String> {
+async fn health_attempt(
+    client: &reqwest::Client,
+    backend_url: &str,
+    timeout: Duration,
+) -> Result<(), BackendError> {
     let url = format!("{backend_url}/health");
 
-    let client = reqwest::Client::new();
-    let response = tokio::time::timeout(
-        std::time::Duration::from_secs(1), // Quick timeout for health checks
-        client.get(&url).send(),
-    )
-    .await
-    .map_err(|_| "Backend health check timeout".to_string())?
-    .map_err(|e| format!("Failed to connect to backend: {e}"))?;
+    let response = tokio::time::timeout(timeout, client.get(&url).send())
+        .await
+        .map_err(|_| BackendError::Timeout(timeout))?
+        .map_err(|e| BackendError::from_reqwest(&e))?;
 
     if response.status().is_success() {
         Ok(())
     } else {
-        Err(format!(
-            "Backend health check failed: {}",
-            response.status()
-        ))
+        Err(BackendError::Status(response.status().as_u16()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_client_is_reused() {
+        assert!(std::ptr::eq(shared_client(), shared_client()));
+
+        let from_thread = std::thread::spawn(|| shared_client() as *const reqwest::Client as usize)
+            .join()
+            .unwrap();
+        assert_eq!(
+            from_thread,
+            shared_client() as *const reqwest::Client as usize
+        );
+    }
+
+    /// Accepts connections on a local port and answers `/health` after `delay`
+    fn slow_server(delay: Duration) -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                std::thread::spawn(move || {
+                    let mut request = [0; 1024];
+                    let _ = stream.read(&mut request);
+                    std::thread::sleep(delay);
+                    let _ = stream.write_all(
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok",
+                    );
+                });
+            }
+        });
+        url
+    }
+
+    /// A local URL nothing listens on
+    fn down_server() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_health_check_distinguishes_slow_from_down() {
+        let client = reqwest::Client::new();
+        let slow = slow_server(Duration::from_millis(300));
+
+        assert_eq!(
+            check_health(&client, &slow, Duration::from_millis(50)).await,
+            Err(BackendError::Timeout(Duration::from_millis(50)))
+        );
+        // Patient enough: the same server is healthy
+        assert_eq!(
+            check_health(&client, &slow, Duration::from_secs(2)).await,
+            Ok(())
+        );
+
+        let started = Instant::now();
+        let down = check_health(&client, &down_server(), HEALTH_TIMEOUT).await;
+        assert!(
+            matches!(down, Err(BackendError::Unreachable(_))),
+            "{down:?}"
+        );
+        // Retried once after the pause, but didn't wait out the timeout
+        assert!(started.elapsed() >= HEALTH_RETRY_DELAY);
+        assert!(started.elapsed() < HEALTH_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_timeout_is_configurable() {
+        let client = reqwest::Client::new();
+        let slow = slow_server(Duration::from_millis(300));
+
+        let result = analyze_audio(
+            &client,
+            &slow,
+            vec![0; 64],
+            44100,
+            None,
+            Duration::from_millis(50),
+        )
+        .await;
+        assert_eq!(
+            result.err(),
+            Some(BackendError::Timeout(Duration::from_millis(50)))
+        );
     }
 }
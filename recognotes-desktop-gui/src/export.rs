@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use midly::num::{u15, u24, u28, u4, u7};
+use midly::{Format, Header, MetaMessage, MidiMessage, Smf, Timing, TrackEvent, TrackEventKind};
+
+use crate::DetectedNote;
+
+/// Default tempo and resolution offered in the export controls
+pub const DEFAULT_BPM: f64 = 120.0;
+pub const DEFAULT_PPQ: u16 = 480;
+/// A note missing from up to this many consecutive windows is still treated as held
+const MERGE_TOLERANCE_HOPS: f64 = 3.0;
+
+/// MIDI export settings
+#[derive(Debug, Clone, Copy)]
+pub struct MidiExportOptions {
+    /// Tempo written to the file (quarter notes per minute)
+    pub bpm: f64,
+    /// Ticks per quarter note
+    pub ppq: u16,
+    /// Time between analysis windows; each detection covers one hop
+    pub hop_secs: f64,
+}
+
+/// One sustained note: consecutive detections of the same pitch merged together
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteSpan {
+    pub midi: u8,
+    pub start_secs: f64,
+    pub end_secs: f64,
+    /// Strongest intensity seen while the note was held (0.0-1.0)
+    pub intensity: f32,
+}
+
+/// MIDI note number for a name like "A4", "C#3" or "Eb5" (C4 = 60, A4 = 69)
+pub fn note_to_midi(note_name: &str) -> Option<u8> {
+    let mut chars = note_name.chars();
+    let natural: i32 = match chars.next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+
+    let rest = chars.as_str();
+    let octave_start = rest.find(|c: char| c != '#' && c != 'b')?;
+    let (accidentals, octave) = rest.split_at(octave_start);
+    let octave: i32 = octave.parse().ok()?;
+    let shift: i32 = accidentals
+        .chars()
+        .map(|c| if c == '#' { 1 } else { -1 })
+        .sum();
+
+    u8::try_from((octave + 1) * 12 + natural + shift)
+        .ok()
+        .filter(|midi| *midi <= 127)
+}
+
+/// Merge time-stamped detections into held notes
+/// Detections of the same pitch closer than a few hops apart become one span, so a note
+/// sustained across many analysis windows isn't exported as a string of staccato blips
+pub fn merge_note_spans(history: &[(DetectedNote, f64)], hop_secs: f64) -> Vec<NoteSpan> {
+    let max_gap = hop_secs * MERGE_TOLERANCE_HOPS;
+
+    let mut sorted: Vec<(u8, f64, f32)> = history
+        .iter()
+        .filter_map(|(note, timestamp)| {
+            note_to_midi(&note.note).map(|midi| (midi, *timestamp, note.intensity))
+        })
+        .collect();
+    sorted.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let mut open: HashMap<u8, NoteSpan> = HashMap::new();
+    let mut spans = Vec::new();
+    for (midi, timestamp, intensity) in sorted {
+        match open.get_mut(&midi) {
+            Some(span) if timestamp - (span.end_secs - hop_secs) <= max_gap => {
+                span.end_secs = timestamp + hop_secs;
+                span.intensity = span.intensity.max(intensity);
+            }
+            _ => {
+                let span = NoteSpan {
+                    midi,
+                    start_secs: timestamp,
+                    end_secs: timestamp + hop_secs,
+                    intensity,
+                };
+                if let Some(finished) = open.insert(midi, span) {
+                    spans.push(finished);
+                }
+            }
+        }
+    }
+    spans.extend(open.into_values());
+    spans.sort_by(|a, b| {
+        a.start_secs
+            .total_cmp(&b.start_secs)
+            .then(a.midi.cmp(&b.midi))
+    });
+    spans
+}
+
+/// Build a single-track Standard MIDI File from the detection history
+/// The timeline starts at the first detection
+pub fn history_to_midi(
+    history: &[(DetectedNote, f64)],
+    options: MidiExportOptions,
+) -> Result<Vec<u8>, String> {
+    if !(options.bpm.is_finite() && options.bpm > 0.0) || options.ppq == 0 {
+        return Err("Tempo and PPQ must be greater than 0".to_string());
+    }
+
+    let spans = merge_note_spans(history, options.hop_secs);
+    if spans.is_empty() {
+        return Err("No notes to export".to_string());
+    }
+    let origin = spans[0].start_secs;
+    let ticks_per_sec = options.bpm / 60.0 * f64::from(options.ppq);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let to_ticks = |secs: f64| ((secs - origin) * ticks_per_sec).round().max(0.0) as u32;
+
+    // (tick, is_note_on, key, velocity); note-offs sort first so re-struck notes don't overlap
+    let mut events: Vec<(u32, bool, u8, u8)> = Vec::with_capacity(spans.len() * 2);
+    for span in &spans {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let velocity = (40.0 + span.intensity.clamp(0.0, 1.0) * 87.0).round() as u8;
+        let (start, end) = (to_ticks(span.start_secs), to_ticks(span.end_secs));
+        events.push((start, true, span.midi, velocity));
+        events.push((end.max(start + 1), false, span.midi, 0));
+    }
+    events.sort_by_key(|&(tick, is_on, key, _)| (tick, is_on, key));
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let micros_per_beat = (60_000_000.0 / options.bpm).round() as u32;
+    let mut track = vec![TrackEvent {
+        delta: u28::new(0),
+        kind: TrackEventKind::Meta(MetaMessage::Tempo(u24::new(micros_per_beat))),
+    }];
+
+    let mut last_tick = 0;
+    for (tick, is_on, key, velocity) in events {
+        let message = if is_on {
+            MidiMessage::NoteOn {
+                key: u7::new(key),
+                vel: u7::new(velocity),
+            }
+        } else {
+            MidiMessage::NoteOff {
+                key: u7::new(key),
+                vel: u7::new(0),
+            }
+        };
+        track.push(TrackEvent {
+            delta: u28::new(tick - last_tick),
+            kind: TrackEventKind::Midi {
+                channel: u4::new(0),
+                message,
+            },
+        });
+        last_tick = tick;
+    }
+    track.push(TrackEvent {
+        delta: u28::new(0),
+        kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+    });
+
+    let mut smf = Smf::new(Header::new(
+        Format::SingleTrack,
+        Timing::Metrical(u15::new(options.ppq)),
+    ));
+    smf.tracks.push(track);
+
+    let mut bytes = Vec::new();
+    smf.write_std(&mut bytes)
+        .map_err(|e| format!("Failed to encode MIDI: {e}"))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detection(note: &str, timestamp: f64) -> (DetectedNote, f64) {
+        let note = DetectedNote {
+            note: note.to_string(),
+            confidence: 0.9,
+            intensity: 0.8,
+            ..DetectedNote::default()
+        };
+        (note, timestamp)
+    }
+
+    #[test]
+    fn test_note_to_midi() {
+        assert_eq!(note_to_midi("A4"), Some(69));
+        assert_eq!(note_to_midi("C4"), Some(60));
+        assert_eq!(note_to_midi("C#4"), Some(61));
+        assert_eq!(note_to_midi("Eb5"), Some(75));
+        assert_eq!(note_to_midi("H2"), None);
+    }
+
+    #[test]
+    fn test_held_a4_exports_as_one_note() {
+        // A4 seen in ten consecutive 20ms windows (one missed), then E5 briefly
+        let mut history: Vec<_> = (0..10)
+            .filter(|i| *i != 4)
+            .map(|i| detection("A4", 1.0 + f64::from(i) * 0.02))
+            .collect();
+        history.push(detection("E5", 1.5));
+
+        let options = MidiExportOptions {
+            bpm: DEFAULT_BPM,
+            ppq: DEFAULT_PPQ,
+            hop_secs: 0.02,
+        };
+        let bytes = history_to_midi(&history, options).unwrap();
+        let smf = Smf::parse(&bytes).unwrap();
+        assert_eq!(smf.header.timing, Timing::Metrical(u15::new(DEFAULT_PPQ)));
+
+        let note_ons: Vec<u8> = smf.tracks[0]
+            .iter()
+            .filter_map(|event| match event.kind {
+                TrackEventKind::Midi {
+                    message: MidiMessage::NoteOn { key, vel },
+                    ..
+                } if vel > 0 => Some(key.as_int()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(note_ons, [69, 76]);
+    }
+}
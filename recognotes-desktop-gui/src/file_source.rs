@@ -0,0 +1,224 @@
+use std::fs::File;
+use std::path::Path;
+use std::time::Instant;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// An audio file decoded to mono i16, ready to be analyzed like the mic input
+#[derive(Debug, Clone)]
+pub struct LoadedAudio {
+    pub samples: Vec<i16>,
+    pub sample_rate: u32,
+}
+
+/// Decode a WAV or MP3 file to mono i16 (multi-channel files are averaged)
+pub fn load_audio_file(path: &Path) -> Result<LoadedAudio, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let unsupported = |e: SymphoniaError| {
+        format!(
+            "Unsupported audio file {} (expected WAV or MP3): {e}",
+            path.display()
+        )
+    };
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(unsupported)?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| format!("No audio track in {}", path.display()))?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| format!("Unknown sample rate in {}", path.display()))?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(unsupported)?;
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // End of stream
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame isn't fatal for a whole MP3; skip it
+            Err(SymphoniaError::DecodeError(e)) => {
+                log::warn!("Skipping undecodable frame in {}: {e}", path.display());
+                continue;
+            }
+            Err(e) => return Err(format!("Failed to decode {}: {e}", path.display())),
+        };
+
+        let channels = decoded.spec().channels.count().max(1);
+        #[allow(clippy::cast_possible_truncation)]
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+        buffer.copy_interleaved_ref(decoded);
+
+        // Average each frame down to mono, then scale like the mic path (f32 -> i16)
+        #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+        samples.extend(buffer.samples().chunks_exact(channels).map(|frame| {
+            let mono = frame.iter().sum::<f32>() / channels as f32;
+            (mono * 32767.0).clamp(-32768.0, 32767.0) as i16
+        }));
+    }
+
+    if samples.is_empty() {
+        return Err(format!("No audio decoded from {}", path.display()));
+    }
+
+    Ok(LoadedAudio {
+        samples,
+        sample_rate,
+    })
+}
+
+/// Real-time playback of a loaded file into the sliding window
+/// Samples are released at the file's own rate, so the note timeline moves like a live take
+pub struct FilePlayback {
+    pub name: String,
+    audio: LoadedAudio,
+    started: Instant,
+    position: usize,
+}
+
+impl FilePlayback {
+    pub fn new(name: String, audio: LoadedAudio, started: Instant) -> Self {
+        Self {
+            name,
+            audio,
+            started,
+            position: 0,
+        }
+    }
+
+    pub const fn sample_rate(&self) -> u32 {
+        self.audio.sample_rate
+    }
+
+    /// Every sample has been played
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.audio.samples.len()
+    }
+
+    /// Add the samples "played" since the last call to the sliding window
+    /// Mirrors `AudioManager::add_to_sliding_buffer`, keeping only the newest `buffer_size` samples
+    /// Returns how many new samples were added
+    pub fn add_to_sliding_buffer(
+        &mut self,
+        sliding_buffer: &mut Vec<i16>,
+        buffer_size: usize,
+        now: Instant,
+    ) -> usize {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let due = (now.duration_since(self.started).as_secs_f64()
+            * f64::from(self.audio.sample_rate)) as usize;
+        let end = due.min(self.audio.samples.len());
+        if end <= self.position {
+            return 0;
+        }
+
+        let added = end - self.position;
+        sliding_buffer.extend_from_slice(&self.audio.samples[self.position..end]);
+        self.position = end;
+
+        if sliding_buffer.len() > buffer_size {
+            let drain_count = sliding_buffer.len() - buffer_size;
+            sliding_buffer.drain(..drain_count);
+        }
+        added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_load_stereo_wav_as_mono() {
+        let path =
+            std::env::temp_dir().join(format!("recognotes-load-test-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 22050,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..1000 {
+            writer.write_sample(16384i16).unwrap();
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let loaded = load_audio_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.sample_rate, 22050);
+        assert_eq!(loaded.samples.len(), 1000);
+        // Half scale on one channel -> quarter scale mono
+        assert!(loaded.samples.iter().all(|&s| (s - 8192).abs() <= 1));
+    }
+
+    #[test]
+    fn test_unsupported_file_is_an_error() {
+        let path =
+            std::env::temp_dir().join(format!("recognotes-load-test-{}.txt", std::process::id()));
+        std::fs::write(&path, b"definitely not audio").unwrap();
+        let err = load_audio_file(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.contains("Unsupported"), "{err}");
+    }
+
+    #[test]
+    fn test_playback_releases_samples_in_real_time() {
+        let audio = LoadedAudio {
+            samples: (0..1000).collect(),
+            sample_rate: 1000,
+        };
+        let start = Instant::now();
+        let mut playback = FilePlayback::new("test".to_string(), audio, start);
+        let mut window = vec![0i16; 400];
+
+        playback.add_to_sliding_buffer(&mut window, 400, start + Duration::from_millis(250));
+        assert_eq!(window.len(), 400);
+        assert_eq!(window[399], 249);
+        assert!(!playback.is_finished());
+
+        // Past the end: clamps to the file length
+        playback.add_to_sliding_buffer(&mut window, 400, start + Duration::from_secs(5));
+        assert_eq!(window[399], 999);
+        assert!(playback.is_finished());
+    }
+}
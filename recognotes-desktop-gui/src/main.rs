@@ -1,11 +1,20 @@
 mod audio;
 mod backend_client;
+mod export;
+mod file_source;
+mod musicxml;
+mod naming;
+mod pitch_track;
+mod session;
+mod settings;
+mod tuner;
 mod ui;
 mod visualization;
 
 use eframe::egui;
 use image::GenericImageView;
 use parking_lot::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 fn main() -> Result<(), eframe::Error> {
@@ -86,16 +95,49 @@ fn create_default_icon() -> egui::IconData {
     }
 }
 
+/// Limits for the user-adjustable analysis window (seconds) and interval (milliseconds)
+pub const MIN_WINDOW_SECS: f32 = 0.5;
+pub const MAX_WINDOW_SECS: f32 = 4.0;
+pub const MIN_INTERVAL_MS: u64 = 10;
+pub const MAX_INTERVAL_MS: u64 = 200;
+/// Most responses the steady-notes filter can look back over
+pub const MAX_STEADY_WINDOWS: usize = 20;
+/// Confidence hysteresis: a note appears once it reaches `CONFIDENCE_ON` and stays until it
+/// drops below `CONFIDENCE_OFF`, so notes hovering at one threshold don't blink
+pub const CONFIDENCE_ON: f32 = 0.35;
+pub const CONFIDENCE_OFF: f32 = 0.25;
+
 /// Main application state
 #[allow(clippy::struct_excessive_bools)]
 pub struct RecogNotesApp {
     // Device selection
     selected_input_device: Option<String>,
+    // Cached input device names ("Default" first); None until the first scan
+    input_devices: Option<Vec<String>>,
+    // Sample rate asked of the device on Record (the device may negotiate another)
+    capture_rate: u32,
+    // Rates the device supports, keyed by the device they were listed for
+    capture_rates: Option<(Option<String>, Vec<u32>)>,
 
     // UI state
     recording: bool,
+    view_mode: visualization::ViewMode,
+    // How the spectrum bars are colored
+    color_mode: visualization::ColorMode,
+    naming: naming::NamingScheme,
+    // Preferences restored from and saved to eframe's storage (theme, note persistence)
+    settings: settings::Settings,
+    tuner: tuner::TunerState,
+    // Smoothed pitch of the loudest note, drawn over the piano roll when `show_pitch_curve` is on
+    pitch_track: pitch_track::PitchTrack,
+    show_pitch_curve: bool,
     backend_connected: bool,
-    backend_checked: bool, // Track if we've already checked health
+    // Backend health is re-checked every `health_check_interval` so restarts are noticed
+    health_check_interval: std::time::Duration,
+    last_health_check: Option<std::time::Instant>, // None = check on the next frame
+    health_check_pending: bool,
+    // Set when the backend dropped mid-recording; no requests are sent until it's back
+    analysis_paused: bool,
 
     // Audio
     #[allow(clippy::arc_with_non_send_sync)]
@@ -103,22 +145,46 @@ pub struct RecogNotesApp {
 
     // Results
     detected_notes: Vec<DetectedNote>,
-    detected_notes_history: Vec<(DetectedNote, f64)>, // (note, timestamp)
+    detected_notes_history: Vec<(DetectedNote, f64)>, // (note, seconds since history_started)
+    history_started: std::time::Instant,
+    // Pitch-class weights (C..B) of every detection in the history, for the key estimate
+    key_histogram: [f32; 12],
     last_error: Option<String>,
 
     // Backend URL
     backend_url: String,
+    // Extra backends sent the same windows, for A/B comparison (only `backend_url` drives the
+    // display, history and latency readout)
+    comparison_urls: Vec<String>,
+    // Text of the "compare with" field
+    comparison_url_input: String,
+    // Latest notes from each backend (primary included) as returned, keyed by URL
+    backend_results: std::collections::BTreeMap<String, Vec<DetectedNote>>,
 
     // Voice profile for filtering notes
     selected_profile: String, // "no_profile", "soprano", "mezzo", "alto", "tenor", "baritone", "bass"
 
     // Channel for receiving notes from async tasks
-    notes_receiver: std::sync::mpsc::Receiver<Vec<DetectedNote>>,
-    notes_sender: std::sync::Arc<std::sync::Mutex<std::sync::mpsc::Sender<Vec<DetectedNote>>>>,
+    notes_receiver: std::sync::mpsc::Receiver<AnalysisReply>,
+    notes_sender: std::sync::Arc<std::sync::Mutex<std::sync::mpsc::Sender<AnalysisReply>>>,
+
+    // Analysis requests sent but not answered yet (capped at `MAX_IN_FLIGHT`)
+    in_flight: Arc<AtomicUsize>,
+    // The same, per comparison backend, so a slow one only throttles itself
+    comparison_in_flight: std::collections::HashMap<String, Arc<AtomicUsize>>,
+
+    // Round-trip times (ms) of the last `LATENCY_WINDOW` responses, for the latency readout
+    latency_samples: std::collections::VecDeque<f32>,
+
+    // Confidence of the strongest note per response over the last `CONFIDENCE_WINDOW`,
+    // for the stability sparkline
+    confidence_history: std::collections::VecDeque<(std::time::Instant, f32)>,
 
     // Channel for backend health status
-    health_receiver: std::sync::mpsc::Receiver<bool>,
-    health_sender: std::sync::Arc<std::sync::Mutex<std::sync::mpsc::Sender<bool>>>,
+    health_receiver: std::sync::mpsc::Receiver<HealthResult>,
+    health_sender: std::sync::Arc<std::sync::Mutex<std::sync::mpsc::Sender<HealthResult>>>,
+    // Why the last health check failed (shown on hover over the Offline light)
+    backend_offline_reason: Option<String>,
 
     // Rolling history of detected notes with timestamps (last ~1 second)
     notes_with_timestamps: Vec<(DetectedNote, std::time::Instant)>,
@@ -126,26 +192,128 @@ pub struct RecogNotesApp {
     // Track when we last had ANY notes (for display timing)
     last_notes_received_time: std::time::Instant,
 
-    // How long to keep displaying notes after they were last detected (1 second)
-    note_display_duration: std::time::Duration,
+    // Time span shown by the piano roll (notes_with_timestamps is kept at least this long)
+    piano_roll_window: std::time::Duration,
 
-    // Sliding window for 1-second audio analysis
+    // Sliding window for audio analysis
     sliding_window_buffer: Vec<i16>,
-    // Size of sliding window in samples (1 second at sample_rate)
+    // Length of the sliding window in seconds (shorter = snappier, longer = better bass)
+    sliding_window_secs: f32,
+    // Sample rate the window size was computed for
+    sliding_window_rate: u32,
+    // Size of sliding window in samples (sliding_window_secs at sliding_window_rate)
     sliding_window_size: usize,
-    // How often to slide the window and analyze (20ms)
+    // How often to slide the window and analyze (20ms by default)
     sliding_window_interval: std::time::Duration,
     // Last time we performed sliding window analysis
     last_sliding_window_analysis: std::time::Instant,
+
+    // Freeze: the display holds what it showed at `frozen_at` (fades included) while the
+    // take and its export history keep recording
+    frozen: bool,
+    frozen_at: std::time::Instant,
+
+    // Input level meter: latest (peak, rms) and when the input last clipped
+    input_levels: (f32, f32),
+    last_clip: Option<std::time::Instant>,
+
+    // Steady-notes filter: when on, a note is only shown once it appears in
+    // `steady_required` of the last `steady_windows` responses (note names per response)
+    steady_enabled: bool,
+    steady_required: usize,
+    steady_windows: usize,
+    recent_detections: std::collections::VecDeque<Vec<String>>,
+
+    // Notes that crossed `CONFIDENCE_ON` and haven't dropped below `CONFIDENCE_OFF` since
+    confident_notes: std::collections::HashSet<String>,
+
+    // Change detection for the sliding window: samples added since the last request, and
+    // whether the last window sent was silent (None = nothing sent yet this take)
+    unsent_samples: usize,
+    last_sent_silent: Option<bool>,
+
+    // Audio file being analyzed instead of the mic (offline transcription)
+    file_playback: Option<file_source::FilePlayback>,
+
+    // Note timeline export settings (MIDI and MusicXML)
+    export_bpm: f64,
+    export_ppq: u16,
+    export_grid: musicxml::Grid,
+    // Format of the raw note history export (CSV or JSON)
+    history_format: session::HistoryFormat,
+}
+
+/// Number of samples in a window of `secs` seconds at `sample_rate`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn window_samples(secs: f32, sample_rate: u32) -> usize {
+    (f64::from(secs) * f64::from(sample_rate)).round() as usize
+}
+
+/// Windows quieter than this (normalized RMS, -60 dBFS) count as silence
+const SILENCE_RMS: f32 = 0.001;
+
+/// How long the tuner's reference tone plays
+const REFERENCE_TONE_DURATION: std::time::Duration = std::time::Duration::from_millis(1500);
+
+/// Time span shown by the confidence sparkline
+const CONFIDENCE_WINDOW: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Number of responses averaged for the latency readout
+const LATENCY_WINDOW: usize = 25;
+
+/// Analysis requests allowed to wait on the backend at once
+/// Further ticks are dropped until one returns, so a slow backend can't build up a queue of
+/// stale windows and leave the display seconds behind
+const MAX_IN_FLIGHT: usize = 4;
+
+/// Holds one of the `MAX_IN_FLIGHT` request slots; released when the request task ends
+struct InFlightSlot(Arc<AtomicUsize>);
+
+impl InFlightSlot {
+    /// Claim a slot, or `None` when the cap is reached
+    fn acquire(counter: &Arc<AtomicUsize>) -> Option<Self> {
+        counter
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < MAX_IN_FLIGHT).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Self(Arc::clone(counter)))
+    }
 }
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+impl Drop for InFlightSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Outcome of one backend health check
+type HealthResult = Result<(), backend_client::BackendError>;
+
+/// One backend response, handed from the request task to the UI thread
+struct AnalysisReply {
+    /// Backend that answered (the primary or a comparison backend)
+    backend_url: String,
+    notes: Vec<DetectedNote>,
+    /// Client-side round trip (request sent -> notes parsed)
+    latency: std::time::Duration,
+    /// The backend reported the analyzed buffer as clipped
+    clipped: bool,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct DetectedNote {
     pub note: String,
     pub confidence: f32,
     /// Power/intensity of the note (0.0-1.0)
     #[serde(default)]
     pub intensity: f32,
+    /// Measured frequency in Hz
+    #[serde(default)]
+    pub frequency: f32,
+    /// Cents away from the note's exact pitch (positive = sharp)
+    #[serde(default)]
+    pub cents_off: f32,
 }
 
 impl Default for RecogNotesApp {
@@ -158,11 +326,14 @@ impl Default for RecogNotesApp {
 }
 
 impl RecogNotesApp {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self::new_with_config(
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = Self::new_with_config(
             "http://localhost:5000".to_string(),
             48000, // 48kHz is more commonly supported on Windows
-        )
+        );
+        app.settings = settings::Settings::load(cc.storage);
+        cc.egui_ctx.set_visuals(app.settings.theme.visuals());
+        app
     }
 
     fn new_with_config(backend_url: String, sample_rate: u32) -> Self {
@@ -171,39 +342,268 @@ impl RecogNotesApp {
 
         // Sliding window: 2 seconds of audio for better low-frequency resolution
         // At 48kHz: 48000 * 2 = 96000 samples
-        let sliding_window_size = sample_rate as usize * 2;
+        let sliding_window_secs = 2.0;
+        let sliding_window_size = window_samples(sliding_window_secs, sample_rate);
 
         Self {
             recording: false,
+            view_mode: visualization::ViewMode::Spectrum,
+            color_mode: visualization::ColorMode::Intensity,
+            naming: naming::NamingScheme::Scientific,
+            settings: settings::Settings::default(),
+            tuner: tuner::TunerState::default(),
+            pitch_track: pitch_track::PitchTrack::default(),
+            show_pitch_curve: false,
             backend_connected: false,
-            backend_checked: false,
+            health_check_interval: std::time::Duration::from_secs(3),
+            last_health_check: None,
+            health_check_pending: false,
+            analysis_paused: false,
             #[allow(clippy::arc_with_non_send_sync)]
             audio_manager: Arc::new(RwLock::new(audio::AudioManager::new(sample_rate))),
             detected_notes: Vec::new(),
             detected_notes_history: Vec::new(),
+            key_histogram: [0.0; 12],
+            history_started: std::time::Instant::now(),
             last_error: None,
             backend_url,
+            comparison_urls: Vec::new(),
+            comparison_url_input: String::new(),
+            backend_results: std::collections::BTreeMap::new(),
+            comparison_in_flight: std::collections::HashMap::new(),
             selected_profile: "no_profile".to_string(),
             notes_receiver: rx,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            latency_samples: std::collections::VecDeque::with_capacity(LATENCY_WINDOW),
+            confidence_history: std::collections::VecDeque::new(),
             notes_sender: Arc::new(std::sync::Mutex::new(tx)),
             health_receiver: health_rx,
             health_sender: Arc::new(std::sync::Mutex::new(health_tx)),
+            backend_offline_reason: None,
             notes_with_timestamps: Vec::new(),
             last_notes_received_time: std::time::Instant::now(),
-            note_display_duration: std::time::Duration::from_secs(1),
+            piano_roll_window: std::time::Duration::from_secs(8),
             sliding_window_buffer: Vec::with_capacity(sliding_window_size),
+            sliding_window_secs,
+            sliding_window_rate: sample_rate,
             sliding_window_size,
             sliding_window_interval: std::time::Duration::from_millis(20),
             last_sliding_window_analysis: std::time::Instant::now(),
             selected_input_device: None,
+            input_devices: None,
+            capture_rate: sample_rate,
+            capture_rates: None,
+            file_playback: None,
+            steady_enabled: false,
+            steady_required: 3,
+            steady_windows: 5,
+            recent_detections: std::collections::VecDeque::new(),
+            confident_notes: std::collections::HashSet::new(),
+            unsent_samples: 0,
+            last_sent_silent: None,
+            frozen: false,
+            frozen_at: std::time::Instant::now(),
+            input_levels: (0.0, 0.0),
+            last_clip: None,
+            export_bpm: export::DEFAULT_BPM,
+            export_ppq: export::DEFAULT_PPQ,
+            export_grid: musicxml::Grid::Sixteenth,
+            history_format: session::HistoryFormat::Csv,
         }
     }
 
     fn start_recording(&mut self) {
         self.recording = true;
+        self.frozen = false;
+        self.last_error = None;
+        self.stop_file_playback();
+
+        self.prefill_sliding_window();
+        self.clear_history();
+        self.latency_samples.clear();
+
+        // Set the device on the audio manager before starting
+        let mut manager = self.audio_manager.write();
+        manager.set_device(self.selected_input_device.clone());
+        manager.set_sample_rate(self.capture_rate);
+
+        if let Err(e) = manager.start_recording() {
+            self.last_error = Some(format!("Failed to start recording: {e}"));
+            self.recording = false;
+        }
+    }
+
+    /// Pick a WAV/MP3 file and analyze it through the sliding window as it "plays"
+    fn open_audio_file(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Audio", &["wav", "mp3"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        match file_source::load_audio_file(&path) {
+            Ok(audio) => {
+                let name = path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |n| n.to_string_lossy().into_owned(),
+                );
+                log::info!(
+                    "Analyzing {name}: {} samples at {} Hz",
+                    audio.samples.len(),
+                    audio.sample_rate
+                );
+
+                self.last_error = None;
+                self.set_sliding_window(self.sliding_window_secs, audio.sample_rate);
+                self.prefill_sliding_window();
+                self.clear_history();
+                self.file_playback = Some(file_source::FilePlayback::new(
+                    name,
+                    audio,
+                    std::time::Instant::now(),
+                ));
+            }
+            Err(e) => self.last_error = Some(e),
+        }
+    }
+
+    fn stop_file_playback(&mut self) {
+        if let Some(playback) = self.file_playback.take() {
+            log::info!("Stopped analyzing {}", playback.name);
+            self.input_levels = (0.0, 0.0);
+        }
+    }
+
+    /// Save the current take next to the working directory as `recognotes-<timestamp>.wav`
+    /// Works while recording too (the take is snapshotted, not stopped)
+    fn save_recording(&mut self) {
+        let file_name = format!(
+            "recognotes-{}.wav",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        if let Err(e) = self
+            .audio_manager
+            .read()
+            .save_wav(std::path::Path::new(&file_name))
+        {
+            self.last_error = Some(format!("Failed to save recording: {e}"));
+        }
+    }
+
+    /// Switch egui and the views to `theme`; remembered for the next session
+    fn set_theme(&mut self, ctx: &egui::Context, theme: settings::Theme) {
+        self.settings.theme = theme;
+        ctx.set_visuals(theme.visuals());
+    }
+
+    /// Freeze button: hold the display for inspection, or go back to live data
+    fn toggle_freeze(&mut self) {
+        self.frozen = !self.frozen;
+        if self.frozen {
+            self.frozen_at = std::time::Instant::now();
+        }
+    }
+
+    /// Time the views are drawn at: now, or the moment the display was frozen
+    fn display_now(&self) -> std::time::Instant {
+        if self.frozen {
+            self.frozen_at
+        } else {
+            std::time::Instant::now()
+        }
+    }
+
+    /// Record button / Space
+    fn toggle_recording(&mut self) {
+        if self.recording {
+            self.stop_recording();
+        } else {
+            self.start_recording();
+        }
+    }
+
+    /// Clear button / C: drop everything shown and start a fresh timeline
+    fn clear_notes(&mut self) {
+        self.detected_notes.clear();
+        self.notes_with_timestamps.clear();
+        self.recent_detections.clear();
+        self.confident_notes.clear();
+        self.clear_history();
+        self.tuner.clear();
+        self.pitch_track.clear();
         self.last_error = None;
+    }
+
+    /// Start a new note timeline (what gets exported)
+    fn clear_history(&mut self) {
+        self.detected_notes_history.clear();
+        self.key_histogram = [0.0; 12];
+        self.history_started = std::time::Instant::now();
+    }
+
+    /// Write the note timeline to a `.mid` file chosen by the user
+    fn export_midi(&mut self) {
+        let options = export::MidiExportOptions {
+            bpm: self.export_bpm,
+            ppq: self.export_ppq,
+            hop_secs: self.sliding_window_interval.as_secs_f64(),
+        };
+        match export::history_to_midi(&self.detected_notes_history, options) {
+            Ok(bytes) => self.save_export("MIDI", "mid", &bytes),
+            Err(e) => self.last_error = Some(format!("MIDI export failed: {e}")),
+        }
+    }
+
+    /// Write the note timeline to a `.musicxml` score chosen by the user
+    fn export_musicxml(&mut self) {
+        let options = musicxml::MusicXmlExportOptions {
+            bpm: self.export_bpm,
+            grid: self.export_grid,
+            hop_secs: self.sliding_window_interval.as_secs_f64(),
+        };
+        match musicxml::history_to_musicxml(&self.detected_notes_history, options) {
+            Ok(xml) => self.save_export("MusicXML", "musicxml", xml.as_bytes()),
+            Err(e) => self.last_error = Some(format!("MusicXML export failed: {e}")),
+        }
+    }
+
+    /// Write every detection of the timeline, with the sample rate and profile, as CSV or JSON
+    fn export_history(&mut self) {
+        let metadata = session::SessionMetadata {
+            sample_rate: self.sliding_window_rate,
+            profile: self.selected_profile.clone(),
+        };
+        let format = self.history_format;
+        match session::export_history(&self.detected_notes_history, &metadata, format) {
+            Ok(bytes) => self.save_export(format.label(), format.extension(), &bytes),
+            Err(e) => self.last_error = Some(format!("History export failed: {e}")),
+        }
+    }
+
+    /// Ask where to save an export (defaulting to `recognotes-<timestamp>.<extension>`) and write it
+    fn save_export(&mut self, filter_name: &str, extension: &str, bytes: &[u8]) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(filter_name, &[extension])
+            .set_file_name(format!(
+                "recognotes-{}.{extension}",
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            ))
+            .save_file()
+        else {
+            return;
+        };
+        if let Err(e) = std::fs::write(&path, bytes) {
+            self.last_error = Some(format!("Failed to save {}: {e}", path.display()));
+        }
+    }
 
-        // Pre-fill the sliding window buffer with silence (2 seconds worth)
+    /// Pre-fill the sliding window buffer with a full window of silence
+    fn prefill_sliding_window(&mut self) {
+        self.recent_detections.clear();
+        self.confident_notes.clear();
+        self.last_sent_silent = None;
+        self.unsent_samples = 0;
         self.sliding_window_buffer.clear();
         self.sliding_window_buffer
             .extend(std::iter::repeat_n(0i16, self.sliding_window_size));
@@ -211,19 +611,103 @@ impl RecogNotesApp {
             "Initialized sliding window buffer with {} silent samples",
             self.sliding_window_size
         );
+    }
 
-        // Set the device on the audio manager before starting
-        let mut manager = self.audio_manager.write();
-        manager.set_device(self.selected_input_device.clone());
+    /// Notes from one response that are steady enough to display
+    /// With the filter on, a note must appear in `steady_required` of the last `steady_windows`
+    /// responses (this one included); borderline peaks flipping in and out stay hidden, while
+    /// a released note still stops with the first response that misses it
+    fn steady_notes(&mut self, notes: &[DetectedNote]) -> Vec<DetectedNote> {
+        if !self.steady_enabled {
+            return notes.to_vec();
+        }
 
-        if let Err(e) = manager.start_recording() {
-            self.last_error = Some(format!("Failed to start recording: {e}"));
-            self.recording = false;
+        let windows = self.steady_windows.clamp(1, MAX_STEADY_WINDOWS);
+        self.recent_detections
+            .push_back(notes.iter().map(|note| note.note.clone()).collect());
+        while self.recent_detections.len() > windows {
+            self.recent_detections.pop_front();
+        }
+
+        let required = self.steady_required.clamp(1, windows);
+        notes
+            .iter()
+            .filter(|note| {
+                self.recent_detections
+                    .iter()
+                    .filter(|names| names.contains(&note.note))
+                    .count()
+                    >= required
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Notes from one response that pass the confidence hysteresis
+    /// New notes need `CONFIDENCE_ON`; notes already passing keep going down to `CONFIDENCE_OFF`.
+    /// A note missing from the response starts over
+    fn apply_confidence_hysteresis(&mut self, notes: Vec<DetectedNote>) -> Vec<DetectedNote> {
+        let passing: Vec<DetectedNote> = notes
+            .into_iter()
+            .filter(|note| {
+                let threshold = if self.confident_notes.contains(&note.note) {
+                    CONFIDENCE_OFF
+                } else {
+                    CONFIDENCE_ON
+                };
+                note.confidence >= threshold
+            })
+            .collect();
+        self.confident_notes = passing.iter().map(|note| note.note.clone()).collect();
+        passing
+    }
+
+    /// Whether the window changed enough since the last request to be worth analyzing
+    /// Skips when no new audio arrived (a static buffer gives the same answer) and when
+    /// silence follows silence. Loudness alone isn't used otherwise: a new pitch at the same
+    /// level still needs a request
+    fn should_send_window(&mut self, added: usize) -> bool {
+        self.unsent_samples += added;
+        if self.last_sent_silent.is_some() && self.unsent_samples == 0 {
+            return false;
+        }
+
+        let (_, rms) = audio::signal_levels(&self.sliding_window_buffer);
+        let silent = rms < SILENCE_RMS;
+        if silent && self.last_sent_silent == Some(true) {
+            return false;
+        }
+
+        self.unsent_samples = 0;
+        self.last_sent_silent = Some(silent);
+        true
+    }
+
+    /// Change the window length (clamped to `MIN_WINDOW_SECS..=MAX_WINDOW_SECS`) or sample rate
+    /// While recording, a resized window restarts from silence rather than mixing old and new sizes
+    fn set_sliding_window(&mut self, secs: f32, sample_rate: u32) {
+        self.sliding_window_secs = secs.clamp(MIN_WINDOW_SECS, MAX_WINDOW_SECS);
+        self.sliding_window_rate = sample_rate;
+
+        let size = window_samples(self.sliding_window_secs, sample_rate);
+        if size != self.sliding_window_size {
+            self.sliding_window_size = size;
+            if self.recording {
+                self.prefill_sliding_window();
+            }
         }
     }
 
+    /// Change how often the window is analyzed (clamped to `MIN_INTERVAL_MS..=MAX_INTERVAL_MS`)
+    fn set_analysis_interval_ms(&mut self, interval_ms: u64) {
+        self.sliding_window_interval =
+            std::time::Duration::from_millis(interval_ms.clamp(MIN_INTERVAL_MS, MAX_INTERVAL_MS));
+    }
+
     fn stop_recording(&mut self) {
         self.recording = false;
+        self.input_levels = (0.0, 0.0);
+        self.confidence_history.clear();
 
         let mut manager = self.audio_manager.write();
         if let Err(e) = manager.stop_recording() {
@@ -231,6 +715,195 @@ impl RecogNotesApp {
         }
     }
 
+    /// Play the equal-tempered pitch of `note` (e.g. the tuner's target) through the speakers
+    fn play_reference_tone(&mut self, note: &str) {
+        let Some(midi) = export::note_to_midi(note) else {
+            return;
+        };
+        let frequency = 440.0 * ((f32::from(midi) - 69.0) / 12.0).exp2();
+        if let Err(e) = self
+            .audio_manager
+            .write()
+            .play_tone(frequency, REFERENCE_TONE_DURATION)
+        {
+            self.last_error = Some(format!("Can't play reference tone: {e}"));
+        }
+    }
+
+    /// Drop timestamped notes older than both the display duration and the piano-roll window
+    fn prune_note_history(&mut self, now: std::time::Instant) {
+        let keep_for = self
+            .settings
+            .note_display_duration()
+            .max(self.piano_roll_window);
+        if let Some(cutoff) = now.checked_sub(keep_for) {
+            self.notes_with_timestamps
+                .retain(|(_, timestamp)| *timestamp > cutoff);
+            self.pitch_track.prune(cutoff);
+        }
+    }
+
+    /// Stop recording if the audio stream failed (e.g. the device was unplugged mid-take)
+    fn check_stream_errors(&mut self) {
+        let Some(error) = self.audio_manager.read().take_stream_error() else {
+            return;
+        };
+        if self.recording {
+            self.stop_recording();
+            self.last_error = Some(format!("Recording stopped, audio device failed: {error}"));
+        }
+    }
+
+    /// Re-enumerate input devices (e.g. after plugging in a USB mic)
+    fn refresh_input_devices(&mut self) {
+        self.set_input_devices(audio::AudioManager::get_input_devices());
+    }
+
+    /// Supported capture rates for the selected device, re-listed when the selection changes
+    fn capture_rates(&mut self) -> Vec<u32> {
+        let stale = self
+            .capture_rates
+            .as_ref()
+            .is_none_or(|(device, _)| *device != self.selected_input_device);
+        if stale {
+            let mut manager = self.audio_manager.write();
+            manager.set_device(self.selected_input_device.clone());
+            let rates = manager.supported_sample_rates();
+            drop(manager);
+            self.capture_rates = Some((self.selected_input_device.clone(), rates));
+        }
+        self.capture_rates
+            .as_ref()
+            .map(|(_, rates)| rates.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replace the cached device list
+    /// A selected device that is no longer present falls back to Default with a warning
+    fn set_input_devices(&mut self, devices: Vec<String>) {
+        if let Some(selected) = &self.selected_input_device {
+            if !devices.contains(selected) {
+                log::warn!("Input device '{selected}' disappeared, using Default");
+                self.last_error = Some(format!(
+                    "Input device '{selected}' is no longer available; switched to Default"
+                ));
+                self.selected_input_device = None;
+            }
+        }
+        self.input_devices = Some(devices);
+        // Rates are re-listed too (a re-plugged device may support different ones)
+        self.capture_rates = None;
+    }
+
+    /// Add a response's round trip to the rolling latency window
+    fn record_latency(&mut self, latency: std::time::Duration) {
+        if self.latency_samples.len() == LATENCY_WINDOW {
+            self.latency_samples.pop_front();
+        }
+        self.latency_samples
+            .push_back(latency.as_secs_f32() * 1000.0);
+    }
+
+    /// Add the strongest note's confidence to the sparkline and drop samples older than
+    /// `CONFIDENCE_WINDOW`
+    fn record_confidence(&mut self, notes: &[DetectedNote], now: std::time::Instant) {
+        if let Some(strongest) = notes
+            .iter()
+            .max_by(|a, b| a.intensity.total_cmp(&b.intensity))
+        {
+            self.confidence_history
+                .push_back((now, strongest.confidence));
+        }
+        while self
+            .confidence_history
+            .front()
+            .is_some_and(|(timestamp, _)| now.duration_since(*timestamp) > CONFIDENCE_WINDOW)
+        {
+            self.confidence_history.pop_front();
+        }
+    }
+
+    /// Rolling average round trip in ms, once any response has arrived
+    fn average_latency_ms(&self) -> Option<f32> {
+        #[allow(clippy::cast_precision_loss)]
+        (!self.latency_samples.is_empty())
+            .then(|| self.latency_samples.iter().sum::<f32>() / self.latency_samples.len() as f32)
+    }
+
+    /// The backend takes longer than the analysis interval, so results fall behind
+    fn latency_lagging(&self) -> bool {
+        self.average_latency_ms()
+            .is_some_and(|ms| ms > self.sliding_window_interval.as_secs_f32() * 1000.0)
+    }
+
+    /// Whether the input clipped within the last second (keeps the warning visible)
+    fn recently_clipped(&self, now: std::time::Instant) -> bool {
+        self.last_clip
+            .is_some_and(|clip| now.duration_since(clip) < std::time::Duration::from_secs(1))
+    }
+
+    /// Whether a new health check should start (none in flight and the interval has elapsed)
+    fn health_check_due(&self, now: std::time::Instant) -> bool {
+        !self.health_check_pending
+            && self
+                .last_health_check
+                .is_none_or(|last| now.duration_since(last) >= self.health_check_interval)
+    }
+
+    /// Re-check the backend on the next frame (e.g. after the URL changed)
+    fn recheck_backend(&mut self) {
+        self.last_health_check = None;
+    }
+
+    /// Start an async health check; the result arrives through `health_receiver`
+    fn spawn_health_check(&mut self, now: std::time::Instant) {
+        self.last_health_check = Some(now);
+        self.health_check_pending = true;
+
+        let backend_url = self.backend_url.clone();
+        let sender = Arc::clone(&self.health_sender);
+        tokio::spawn(async move {
+            let result = backend_client::check_health(
+                backend_client::shared_client(),
+                &backend_url,
+                backend_client::HEALTH_TIMEOUT,
+            )
+            .await;
+            let _ = sender.lock().unwrap().send(result);
+        });
+    }
+
+    /// Apply a health check result: pause analysis if the backend dropped while recording,
+    /// and resume (clearing the error) once it answers again
+    fn apply_health_status(&mut self, result: HealthResult) {
+        self.health_check_pending = false;
+        let is_healthy = result.is_ok();
+        self.backend_offline_reason = result.err().map(|e| e.to_string());
+
+        if self.backend_connected && !is_healthy {
+            let reason = self.backend_offline_reason.as_deref().unwrap_or_default();
+            log::warn!(
+                "✗ Lost connection to backend at {} ({reason})",
+                self.backend_url
+            );
+            if self.recording || self.file_playback.is_some() {
+                self.analysis_paused = true;
+                self.last_error = Some(format!(
+                    "Lost connection to backend at {} ({reason}) - analysis paused until it's back",
+                    self.backend_url
+                ));
+            }
+        } else if !self.backend_connected && is_healthy {
+            log::info!("✓ Backend reachable at {}", self.backend_url);
+            if self.analysis_paused {
+                self.analysis_paused = false;
+                self.last_error = None;
+            }
+        }
+
+        self.backend_connected = is_healthy;
+    }
+
     fn continuous_analysis(&mut self) {
         // Check if it's time to analyze (every 20ms for sliding window)
         if self.last_sliding_window_analysis.elapsed() < self.sliding_window_interval {
@@ -239,19 +912,48 @@ impl RecogNotesApp {
 
         self.last_sliding_window_analysis = std::time::Instant::now();
 
-        if !self.recording {
+        if !(self.recording || self.file_playback.is_some()) || self.analysis_paused {
             return;
         }
 
         // Add new audio to sliding window (replaces oldest samples with newest)
-        let manager = self.audio_manager.write();
-        manager.add_to_sliding_buffer(&mut self.sliding_window_buffer, self.sliding_window_size);
-        drop(manager);
+        let added;
+        let sample_rate = if let Some(playback) = &mut self.file_playback {
+            // Offline mode: the file stands in for the mic
+            added = playback.add_to_sliding_buffer(
+                &mut self.sliding_window_buffer,
+                self.sliding_window_size,
+                std::time::Instant::now(),
+            );
+            // Level of roughly the audio played since the last analysis
+            let recent = window_samples(
+                self.sliding_window_interval.as_secs_f32(),
+                playback.sample_rate(),
+            )
+            .min(self.sliding_window_buffer.len());
+            self.input_levels = audio::signal_levels(
+                &self.sliding_window_buffer[self.sliding_window_buffer.len() - recent..],
+            );
+            playback.sample_rate()
+        } else {
+            let manager = self.audio_manager.write();
+            added = manager
+                .add_to_sliding_buffer(&mut self.sliding_window_buffer, self.sliding_window_size);
+            self.input_levels = manager.current_levels();
+            drop(manager);
 
-        // Get the actual sample rate from the audio manager after it has been configured.
-        let sample_rate = self.audio_manager.read().sample_rate();
+            // Get the actual sample rate from the audio manager after it has been configured.
+            self.audio_manager.read().sample_rate()
+        };
+        if audio::is_clipping(self.input_levels.0) {
+            self.last_clip = Some(std::time::Instant::now());
+        }
+        if sample_rate != self.sliding_window_rate {
+            // The device settled on a different rate: keep the window length in seconds
+            self.set_sliding_window(self.sliding_window_secs, sample_rate);
+        }
 
-        // Buffer is always pre-filled with silence, so we always have 2 seconds ready
+        // Buffer is always pre-filled with silence, so we always have a full window ready
         if self.sliding_window_buffer.len() < self.sliding_window_size {
             log::debug!(
                 "Waiting for sliding buffer to fill: {}/{} samples",
@@ -261,133 +963,891 @@ impl RecogNotesApp {
             return;
         }
 
+        // The file's last window is on its way; go back to idle
+        if self
+            .file_playback
+            .as_ref()
+            .is_some_and(file_source::FilePlayback::is_finished)
+        {
+            self.stop_file_playback();
+        }
+
+        // Backend is behind: drop this tick (new audio keeps counting towards the next send)
+        let Some(slot) = InFlightSlot::acquire(&self.in_flight) else {
+            self.unsent_samples += added;
+            log::debug!("Skipping analysis tick: {MAX_IN_FLIGHT} requests still pending");
+            return;
+        };
+        if !self.should_send_window(added) {
+            return;
+        }
+
         // Convert sliding window buffer to bytes and send immediately
         let mut audio_data = Vec::with_capacity(self.sliding_window_buffer.len() * 2);
         for &sample in &self.sliding_window_buffer {
             audio_data.extend_from_slice(&sample.to_le_bytes());
         }
 
-        let backend_url = self.backend_url.clone();
-        let sender = Arc::clone(&self.notes_sender);
+        self.send_window(audio_data, sample_rate, slot);
+    }
+
+    /// One frame of analysis: send a window if one is due, then take any finished replies
+    /// Replies are taken whether or not a window went out, so the display still expires
+    /// during silence
+    fn analysis_frame(&mut self, now: std::time::Instant) {
+        self.continuous_analysis();
+        self.receive_notes(now);
+    }
+
+    /// Send one window to the primary backend and every comparison backend
+    /// Only the primary request holds the window's in-flight slot; each comparison backend has
+    /// its own cap and skips windows while it's saturated, so a slow reference can't hold back
+    /// the display
+    fn send_window(&self, audio_data: Vec<u8>, sample_rate: u32, slot: InFlightSlot) {
         let data_len = audio_data.len();
         let profile = if self.selected_profile == "no_profile" {
             None
         } else {
             Some(self.selected_profile.clone())
         };
-        let profile_display = profile.as_deref().unwrap_or("no_profile").to_string();
 
-        // Spawn async task to send to backend
-        tokio::spawn(async move {
-            let client_start = std::time::Instant::now();
-            match backend_client::analyze_audio(&backend_url, audio_data, sample_rate, profile)
-                .await
-            {
-                Ok(notes) => {
-                    let total_client_ms = client_start.elapsed().as_millis();
-                    log::info!(
-                        "Backend response [{}]: {} notes from {}B audio in {}ms",
-                        profile_display,
-                        notes.len(),
-                        data_len,
-                        total_client_ms
-                    );
-                    let _ = sender.lock().unwrap().send(notes);
-                }
-                Err(e) => {
-                    let total_client_ms = client_start.elapsed().as_millis();
-                    log::error!("Backend error after {total_client_ms}ms: {e}");
-                }
+        let comparisons = self.comparison_urls.iter().filter_map(|url| {
+            let slot = self
+                .comparison_in_flight
+                .get(url)
+                .and_then(InFlightSlot::acquire);
+            if slot.is_none() {
+                log::debug!(
+                    "Skipping comparison with {url}: {MAX_IN_FLIGHT} requests still pending"
+                );
             }
+            Some((url, slot?))
         });
+        for (backend_url, slot) in std::iter::once((&self.backend_url, slot)).chain(comparisons) {
+            let backend_url = backend_url.clone();
+            let sender = Arc::clone(&self.notes_sender);
+            let audio_data = audio_data.clone();
+            let profile = profile.clone();
 
-        // Receive any notes from completed async tasks
-        let now = std::time::Instant::now();
-        if let Ok(notes) = self.notes_receiver.try_recv() {
-            if !notes.is_empty() {
-                log::info!("🎵 Received {} notes from backend", notes.len());
-                for note in &notes {
-                    log::info!(
-                        "   - {} ({:.0}% confidence)",
-                        note.note,
-                        note.confidence * 100.0
-                    );
-
-                    // Add each note to rolling history with timestamp
-                    self.notes_with_timestamps.push((note.clone(), now));
+            // Spawn async task to send to backend
+            tokio::spawn(async move {
+                let _slot = slot;
+                let profile_display = profile.as_deref().unwrap_or("no_profile").to_string();
+                let client_start = std::time::Instant::now();
+                match backend_client::analyze_audio(
+                    backend_client::shared_client(),
+                    &backend_url,
+                    audio_data,
+                    sample_rate,
+                    profile,
+                    backend_client::ANALYZE_TIMEOUT,
+                )
+                .await
+                {
+                    Ok(response) => {
+                        let latency = client_start.elapsed();
+                        let total_client_ms = latency.as_millis();
+                        log::info!(
+                            "Backend response [{}] from {}: {} notes from {}B audio in {}ms",
+                            profile_display,
+                            backend_url,
+                            response.notes.len(),
+                            data_len,
+                            total_client_ms
+                        );
+                        let _ = sender.lock().unwrap().send(AnalysisReply {
+                            backend_url,
+                            notes: response.notes,
+                            latency,
+                            clipped: response.clipped,
+                        });
+                    }
+                    Err(e) => {
+                        let total_client_ms = client_start.elapsed().as_millis();
+                        log::error!("Backend {backend_url} error after {total_client_ms}ms: {e}");
+                    }
                 }
-                self.last_notes_received_time = now;
-            }
+            });
+        }
+    }
 
-            // Clean up old notes (older than display duration)
-            let cutoff_time = now.checked_sub(self.note_display_duration).unwrap();
-            self.notes_with_timestamps
-                .retain(|(_, timestamp)| *timestamp > cutoff_time);
-
-            // Build current detected_notes from the recent history (for UI display)
-            let mut unique_notes = std::collections::HashMap::new();
-            for (note, _timestamp) in &self.notes_with_timestamps {
-                unique_notes
-                    .entry(note.note.clone())
-                    .and_modify(|existing: &mut DetectedNote| {
-                        if note.confidence > existing.confidence {
-                            *existing = note.clone();
-                        }
-                    })
-                    .or_insert_with(|| note.clone());
+    /// Add a backend to compare against the primary one (ignored if empty or already listed)
+    fn add_comparison_backend(&mut self, url: &str) {
+        let url = url.trim().trim_end_matches('/');
+        if url.is_empty()
+            || url == self.backend_url
+            || self.comparison_urls.iter().any(|u| u == url)
+        {
+            return;
+        }
+        self.comparison_urls.push(url.to_string());
+        self.comparison_in_flight
+            .insert(url.to_string(), Arc::new(AtomicUsize::new(0)));
+    }
+
+    fn remove_comparison_backend(&mut self, url: &str) {
+        self.comparison_urls.retain(|u| u != url);
+        self.comparison_in_flight.remove(url);
+        self.backend_results.remove(url);
+    }
+
+    /// Take every finished analysis reply, so a backlog built up under load is caught up on in
+    /// one frame rather than one reply per frame
+    fn receive_notes(&mut self, now: std::time::Instant) {
+        let mut received = false;
+        while let Ok(reply) = self.notes_receiver.try_recv() {
+            // Comparison backends only update the side-by-side table
+            if reply.backend_url != self.backend_url {
+                if !self.frozen && self.comparison_urls.contains(&reply.backend_url) {
+                    self.backend_results.insert(reply.backend_url, reply.notes);
+                }
+                continue;
             }
-            self.detected_notes = unique_notes.into_values().collect();
+            received = true;
+            self.apply_reply(reply, now);
+        }
 
-            // Sort by note name for consistent display
-            self.detected_notes.sort_by(|a, b| a.note.cmp(&b.note));
-        } else {
-            // If no new notes received, clean up old ones based on display duration
-            let cutoff_time = now.checked_sub(self.note_display_duration).unwrap();
-            self.notes_with_timestamps
-                .retain(|(_, timestamp)| *timestamp > cutoff_time);
+        if !received && !self.frozen {
+            self.prune_note_history(now);
 
             // If all notes have expired, clear display
-            if self.notes_with_timestamps.is_empty() {
+            if now.duration_since(self.last_notes_received_time)
+                > self.settings.note_display_duration()
+            {
                 self.detected_notes.clear();
             }
         }
     }
+
+    /// One reply from the primary backend: latency and the export history always, the display
+    /// (bars, fades, tuner, sparkline) only when not frozen
+    fn apply_reply(&mut self, reply: AnalysisReply, now: std::time::Instant) {
+        let AnalysisReply {
+            backend_url,
+            notes,
+            latency,
+            clipped,
+        } = reply;
+
+        self.record_latency(latency);
+        // Also catches clipping the level meter can't see, e.g. in a file being played back
+        if clipped {
+            self.last_clip = Some(now);
+        }
+        // Stored before the hysteresis, like the comparison replies, so the columns line up
+        if !self.frozen && !self.comparison_urls.is_empty() {
+            self.backend_results.insert(backend_url, notes.clone());
+        }
+        let notes = self.apply_confidence_hysteresis(notes);
+        if !notes.is_empty() {
+            log::info!("🎵 Received {} notes from backend", notes.len());
+        }
+        let heard = recognotes_core::key::pitch_class_histogram(
+            notes
+                .iter()
+                .map(|note| (note.note.as_str(), note.confidence)),
+        );
+        for (total, weight) in self.key_histogram.iter_mut().zip(heard) {
+            *total += weight;
+        }
+        for note in &notes {
+            log::info!(
+                "   - {} ({:.0}% confidence)",
+                note.note,
+                note.confidence * 100.0
+            );
+
+            // The export history keeps every detection (it merges its own spans)
+            self.detected_notes_history.push((
+                note.clone(),
+                now.duration_since(self.history_started).as_secs_f64(),
+            ));
+        }
+
+        // Frozen: the take keeps recording, the display holds its last frame
+        if self.frozen {
+            return;
+        }
+
+        self.record_confidence(&notes, now);
+        let shown = self.steady_notes(&notes);
+        if !notes.is_empty() {
+            self.tuner.update(&notes, now);
+            self.pitch_track.update(&notes, now);
+        }
+        if !shown.is_empty() {
+            // Add each displayed note to rolling history with timestamp
+            self.notes_with_timestamps
+                .extend(shown.into_iter().map(|note| (note, now)));
+            self.last_notes_received_time = now;
+        }
+
+        // Clean up notes that have scrolled off the piano roll
+        self.prune_note_history(now);
+
+        // Build current detected_notes from the last `note_display_ms` (for UI display)
+        let display_cutoff = now.checked_sub(self.settings.note_display_duration());
+        let mut unique_notes = std::collections::HashMap::new();
+        for (note, _timestamp) in self
+            .notes_with_timestamps
+            .iter()
+            .filter(|(_, timestamp)| display_cutoff.is_none_or(|cutoff| *timestamp > cutoff))
+        {
+            unique_notes
+                .entry(note.note.clone())
+                .and_modify(|existing: &mut DetectedNote| {
+                    if note.confidence > existing.confidence {
+                        *existing = note.clone();
+                    }
+                })
+                .or_insert_with(|| note.clone());
+        }
+        self.detected_notes = unique_notes.into_values().collect();
+
+        // Sort by note name for consistent display
+        self.detected_notes.sort_by(|a, b| a.note.cmp(&b.note));
+    }
 }
 
 impl eframe::App for RecogNotesApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Check backend connection only once on startup
-        if !self.backend_checked {
-            self.backend_checked = true;
-            let backend_url = self.backend_url.clone();
-            let sender = Arc::clone(&self.health_sender);
-            tokio::spawn(async move {
-                let is_healthy = backend_client::check_health(&backend_url).await.is_ok();
-                if is_healthy {
-                    log::debug!("✓ Backend health check passed on startup");
-                }
-                let _ = sender.lock().unwrap().send(is_healthy);
-            });
+        // Periodically re-check the backend so restarts and outages show up
+        let now = std::time::Instant::now();
+        if self.health_check_due(now) {
+            self.spawn_health_check(now);
         }
 
-        // Check if backend health result came back
-        if let Ok(is_healthy) = self.health_receiver.try_recv() {
-            self.backend_connected = is_healthy;
+        // Check if backend health results came back
+        while let Ok(result) = self.health_receiver.try_recv() {
+            self.apply_health_status(result);
         }
 
+        // A failed audio stream (unplugged device) stops the take instead of recording silence
+        self.check_stream_errors();
+
         // Continuous analysis if recording
-        self.continuous_analysis();
+        self.analysis_frame(std::time::Instant::now());
 
         // Request repaint to keep analysis running at the sound format frequency
         // This ensures the update loop runs continuously even without mouse movement
         // Also needed for smooth fade animation
-        if self.recording {
+        if self.recording || self.file_playback.is_some() {
+            ctx.request_repaint();
+        } else if self.in_flight.load(Ordering::Acquire) > 0 {
+            // Replies still on their way (e.g. a finished file's last windows)
             ctx.request_repaint();
         } else if !self.notes_with_timestamps.is_empty() {
             // Keep repainting while notes are fading out (for 2 seconds)
             ctx.request_repaint();
+        } else {
+            // Idle: wake up in time for the next health check
+            ctx.request_repaint_after(self.health_check_interval);
         }
 
         ui::draw_ui(self, ctx);
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.settings.save(storage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_one_second_window_at_48k() {
+        let mut app = RecogNotesApp::new_with_config("http://localhost:5000".to_string(), 48000);
+        assert_eq!(app.sliding_window_size, 96000);
+
+        app.recording = true;
+        app.set_sliding_window(1.0, 48000);
+        assert_eq!(app.sliding_window_size, 48000);
+        assert_eq!(app.sliding_window_buffer.len(), 48000);
+        assert!(app.sliding_window_buffer.iter().all(|&s| s == 0));
+
+        // Device switched to 44.1kHz: same length in seconds
+        app.set_sliding_window(app.sliding_window_secs, 44100);
+        assert_eq!(app.sliding_window_buffer.len(), 44100);
+
+        // Out-of-range requests are clamped
+        app.set_sliding_window(10.0, 48000);
+        assert_eq!(app.sliding_window_size, 192_000);
+        app.set_analysis_interval_ms(1);
+        assert_eq!(
+            app.sliding_window_interval,
+            Duration::from_millis(MIN_INTERVAL_MS)
+        );
+    }
+
+    #[test]
+    fn test_health_state_machine_pauses_and_resumes() {
+        let mut app = RecogNotesApp {
+            health_check_interval: Duration::from_millis(10),
+            ..RecogNotesApp::default()
+        };
+
+        // First check runs immediately, then waits for the interval (and the pending result)
+        let start = Instant::now();
+        assert!(app.health_check_due(start));
+        app.last_health_check = Some(start);
+        app.health_check_pending = true;
+        assert!(!app.health_check_due(start + Duration::from_millis(20)));
+
+        app.apply_health_status(Ok(()));
+        assert!(app.backend_connected);
+        assert!(!app.health_check_due(start + Duration::from_millis(5)));
+        assert!(app.health_check_due(start + Duration::from_millis(10)));
+
+        // Backend drops mid-recording: error shown, analysis paused
+        app.recording = true;
+        app.apply_health_status(Err(backend_client::BackendError::Timeout(
+            Duration::from_secs(2),
+        )));
+        assert!(!app.backend_connected);
+        assert!(app.analysis_paused);
+        assert!(app
+            .last_error
+            .as_deref()
+            .unwrap()
+            .contains("Lost connection"));
+        assert!(app
+            .backend_offline_reason
+            .as_deref()
+            .unwrap()
+            .contains("timed out"));
+
+        // Still down: nothing changes
+        app.apply_health_status(Err(backend_client::BackendError::Unreachable(
+            "connection refused".to_string(),
+        )));
+        assert!(app.analysis_paused);
+
+        // Back again: resumed and error cleared
+        app.apply_health_status(Ok(()));
+        assert!(app.backend_connected);
+        assert!(!app.analysis_paused);
+        assert!(app.last_error.is_none());
+
+        // Manual re-check runs on the next frame
+        app.last_health_check = Some(Instant::now());
+        app.recheck_backend();
+        assert!(app.health_check_due(Instant::now()));
+    }
+
+    #[test]
+    fn test_static_buffer_sends_at_most_one_request() {
+        let mut app = RecogNotesApp::default();
+        app.prefill_sliding_window();
+
+        // A window that never changes: only the first tick is sent
+        let tone: Vec<i16> = (0..app.sliding_window_size)
+            .map(|i| if i % 100 < 50 { 8000 } else { -8000 })
+            .collect();
+        app.sliding_window_buffer.clone_from(&tone);
+        let sent = (0..50).filter(|_| app.should_send_window(0)).count();
+        assert_eq!(sent, 1);
+
+        // New audio is sent again
+        assert!(app.should_send_window(960));
+
+        // Silence after silence isn't, even though samples keep arriving
+        app.sliding_window_buffer.fill(0);
+        assert!(app.should_send_window(960));
+        assert!(!app.should_send_window(960));
+        assert!(!app.should_send_window(960));
+
+        // Until the input wakes up
+        app.sliding_window_buffer.clone_from(&tone);
+        assert!(app.should_send_window(960));
+    }
+
+    #[test]
+    fn test_requests_dropped_when_in_flight_cap_reached() {
+        let app = RecogNotesApp::default();
+        let mut slots: Vec<_> = (0..MAX_IN_FLIGHT)
+            .map(|_| InFlightSlot::acquire(&app.in_flight).unwrap())
+            .collect();
+        assert_eq!(app.in_flight.load(Ordering::Acquire), MAX_IN_FLIGHT);
+        assert!(InFlightSlot::acquire(&app.in_flight).is_none());
+
+        // A response coming back frees a slot
+        slots.pop();
+        assert_eq!(app.in_flight.load(Ordering::Acquire), MAX_IN_FLIGHT - 1);
+        let slot = InFlightSlot::acquire(&app.in_flight);
+        assert!(slot.is_some());
+        assert!(InFlightSlot::acquire(&app.in_flight).is_none());
+    }
+
+    #[test]
+    fn test_confidence_hysteresis_on_and_off() {
+        let mut app = RecogNotesApp::default();
+        let a4 = |confidence: f32| DetectedNote {
+            note: "A4".to_string(),
+            confidence,
+            intensity: 0.8,
+            frequency: 440.0,
+            ..DetectedNote::default()
+        };
+        let shown: Vec<bool> = [0.30, 0.36, 0.30, 0.26, 0.24, 0.30, 0.35]
+            .iter()
+            .map(|&confidence| {
+                !app.apply_confidence_hysteresis(vec![a4(confidence)])
+                    .is_empty()
+            })
+            .collect();
+        // Off until 0.35, on down to 0.25, then off again until 0.35
+        assert_eq!(shown, [false, true, true, true, false, false, true]);
+
+        // A response without the note resets it
+        assert!(app.apply_confidence_hysteresis(Vec::new()).is_empty());
+        assert!(app.apply_confidence_hysteresis(vec![a4(0.30)]).is_empty());
+    }
+
+    #[test]
+    fn test_steady_filter_needs_k_of_n_windows() {
+        let mut app = RecogNotesApp {
+            steady_enabled: true,
+            steady_required: 3,
+            steady_windows: 5,
+            ..RecogNotesApp::default()
+        };
+        let a4 = || DetectedNote {
+            note: "A4".to_string(),
+            confidence: 0.9,
+            intensity: 0.8,
+            frequency: 440.0,
+            ..DetectedNote::default()
+        };
+
+        // A blip in 1 of 5 windows never shows
+        for window in 0..5 {
+            let notes = if window == 2 { vec![a4()] } else { Vec::new() };
+            assert!(app.steady_notes(&notes).is_empty());
+        }
+
+        // Held through 4 of 5 (one dropout): shown from the third sighting on
+        app.recent_detections.clear();
+        let shown: Vec<usize> = [true, true, false, true, true]
+            .iter()
+            .map(|&present| {
+                let notes = if present { vec![a4()] } else { Vec::new() };
+                app.steady_notes(&notes).len()
+            })
+            .collect();
+        assert_eq!(shown, [0, 0, 0, 1, 1]);
+
+        // Released: gone with the first window it's missing from
+        assert!(app.steady_notes(&[]).is_empty());
+
+        // Off: every detection is shown as before
+        app.steady_enabled = false;
+        assert_eq!(app.steady_notes(&[a4()]).len(), 1);
+    }
+
+    #[test]
+    fn test_latency_average_and_lag_warning() {
+        let mut app = RecogNotesApp::default();
+        assert!(app.average_latency_ms().is_none());
+        assert!(!app.latency_lagging());
+
+        // Fast responses under the 20ms interval
+        for ms in [8, 10, 12] {
+            app.record_latency(Duration::from_millis(ms));
+        }
+        assert!((app.average_latency_ms().unwrap() - 10.0).abs() < 0.01);
+        assert!(!app.latency_lagging());
+
+        // A slowdown fills the window and pushes the old samples out
+        for _ in 0..LATENCY_WINDOW {
+            app.record_latency(Duration::from_millis(45));
+        }
+        assert!((app.average_latency_ms().unwrap() - 45.0).abs() < 0.01);
+        assert!(app.latency_lagging());
+    }
+
+    /// In-memory stand-in for eframe's on-disk app storage
+    #[derive(Default)]
+    struct MemoryStorage(std::collections::HashMap<String, String>);
+
+    impl eframe::Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_string(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn test_theme_switch_is_saved() {
+        let mut app = RecogNotesApp::default();
+        let ctx = egui::Context::default();
+        let mut storage = MemoryStorage::default();
+        assert_eq!(
+            settings::Settings::load(Some(&storage)).theme,
+            settings::Theme::Dark
+        );
+
+        app.set_theme(&ctx, settings::Theme::Light);
+        assert!(!ctx.style().visuals.dark_mode);
+        eframe::App::save(&mut app, &mut storage);
+        assert_eq!(
+            settings::Settings::load(Some(&storage)).theme,
+            settings::Theme::Light
+        );
+    }
+
+    #[test]
+    fn test_confidence_history_evicts_old_samples() {
+        let mut app = RecogNotesApp::default();
+        let note = |name: &str, confidence: f32, intensity: f32| DetectedNote {
+            note: name.to_string(),
+            confidence,
+            intensity,
+            ..DetectedNote::default()
+        };
+
+        // The strongest note's confidence is recorded, not the most confident one
+        let start = Instant::now();
+        app.record_confidence(&[note("E5", 0.9, 0.2), note("A4", 0.6, 0.8)], start);
+        app.record_confidence(&[], start + Duration::from_secs(1));
+        assert_eq!(app.confidence_history.len(), 1);
+        assert!((app.confidence_history[0].1 - 0.6).abs() < f32::EPSILON);
+
+        // Samples older than the window fall off the front
+        let later = start + CONFIDENCE_WINDOW + Duration::from_millis(100);
+        app.record_confidence(&[note("A4", 0.95, 0.8)], later);
+        assert_eq!(app.confidence_history.len(), 1);
+        assert_eq!(app.confidence_history[0].0, later);
+
+        app.stop_recording();
+        assert!(app.confidence_history.is_empty());
+    }
+
+    #[test]
+    fn test_freeze_holds_the_display() {
+        let mut app = RecogNotesApp::default();
+        let note = |name: &str| DetectedNote {
+            note: name.to_string(),
+            confidence: 0.9,
+            intensity: 0.8,
+            ..DetectedNote::default()
+        };
+        let reply = |app: &RecogNotesApp, notes: Vec<DetectedNote>| {
+            app.notes_sender
+                .lock()
+                .unwrap()
+                .send(AnalysisReply {
+                    backend_url: app.backend_url.clone(),
+                    notes,
+                    latency: Duration::from_millis(10),
+                    clipped: false,
+                })
+                .unwrap();
+        };
+        let shown = |app: &RecogNotesApp| -> Vec<String> {
+            app.detected_notes.iter().map(|n| n.note.clone()).collect()
+        };
+
+        let start = Instant::now();
+        reply(&app, vec![note("A4")]);
+        app.receive_notes(start);
+        assert_eq!(shown(&app), ["A4"]);
+
+        // Frozen: new data is recorded for export but the display keeps the last frame
+        app.toggle_freeze();
+        reply(&app, vec![note("C5")]);
+        app.receive_notes(start + Duration::from_millis(20));
+        app.receive_notes(start + Duration::from_secs(3));
+        assert_eq!(shown(&app), ["A4"]);
+        assert_eq!(app.notes_with_timestamps.len(), 1);
+        assert_eq!(app.detected_notes_history.len(), 2);
+        assert_eq!(app.display_now(), app.frozen_at);
+
+        // Live again: the next reply shows up
+        app.toggle_freeze();
+        reply(&app, vec![note("E5")]);
+        app.receive_notes(start + Duration::from_millis(3020));
+        assert_eq!(shown(&app), ["E5"]);
+    }
+
+    #[test]
+    fn test_display_expires_during_silence() {
+        let mut app = RecogNotesApp {
+            recording: true,
+            sliding_window_interval: Duration::ZERO,
+            ..RecogNotesApp::default()
+        };
+        app.prefill_sliding_window();
+        // The last window sent was already silent, so silent ticks send nothing
+        app.last_sent_silent = Some(true);
+
+        let start = Instant::now();
+        app.notes_sender
+            .lock()
+            .unwrap()
+            .send(AnalysisReply {
+                backend_url: app.backend_url.clone(),
+                notes: vec![DetectedNote {
+                    note: "A4".to_string(),
+                    confidence: 0.9,
+                    intensity: 0.8,
+                    ..DetectedNote::default()
+                }],
+                latency: Duration::from_millis(10),
+                clipped: false,
+            })
+            .unwrap();
+        app.analysis_frame(start);
+        assert_eq!(app.detected_notes.len(), 1);
+
+        app.analysis_frame(start + Duration::from_millis(20));
+        assert_eq!(app.detected_notes.len(), 1);
+        app.analysis_frame(
+            start + app.settings.note_display_duration() + Duration::from_millis(20),
+        );
+        assert!(app.detected_notes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_last_window_of_a_file_reaches_the_history() {
+        let mut app = RecogNotesApp {
+            sliding_window_interval: Duration::ZERO,
+            ..RecogNotesApp::new_with_config(mock_backend("A4", 0.9), 48000)
+        };
+        app.prefill_sliding_window();
+        // A quarter second of A4, already played through by the first frame
+        let samples = (0..12000)
+            .map(|i| {
+                let t = i as f32 / 48000.0;
+                (0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin() * 32767.0) as i16
+            })
+            .collect();
+        app.file_playback = Some(file_source::FilePlayback::new(
+            "short.wav".to_string(),
+            file_source::LoadedAudio {
+                samples,
+                sample_rate: 48000,
+            },
+            Instant::now() - Duration::from_secs(1),
+        ));
+
+        app.analysis_frame(Instant::now());
+        assert!(app.file_playback.is_none());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while app.detected_notes_history.is_empty() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            app.analysis_frame(Instant::now());
+        }
+        let history: Vec<&str> = app
+            .detected_notes_history
+            .iter()
+            .map(|(note, _)| note.note.as_str())
+            .collect();
+        assert_eq!(history, ["A4"]);
+    }
+
+    #[test]
+    fn test_pending_replies_are_all_taken_at_once() {
+        let mut app = RecogNotesApp::default();
+        for name in ["A4", "C5", "E5"] {
+            app.notes_sender
+                .lock()
+                .unwrap()
+                .send(AnalysisReply {
+                    backend_url: app.backend_url.clone(),
+                    notes: vec![DetectedNote {
+                        note: name.to_string(),
+                        confidence: 0.9,
+                        intensity: 0.8,
+                        ..DetectedNote::default()
+                    }],
+                    latency: Duration::from_millis(10),
+                    clipped: false,
+                })
+                .unwrap();
+        }
+
+        app.receive_notes(Instant::now());
+        assert_eq!(app.detected_notes_history.len(), 3);
+        assert_eq!(app.latency_samples.len(), 3);
+        assert!(app.notes_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_backend_clipping_shows_the_warning() {
+        let mut app = RecogNotesApp::default();
+        let start = Instant::now();
+        for clipped in [false, true] {
+            app.notes_sender
+                .lock()
+                .unwrap()
+                .send(AnalysisReply {
+                    backend_url: app.backend_url.clone(),
+                    notes: Vec::new(),
+                    latency: Duration::from_millis(10),
+                    clipped,
+                })
+                .unwrap();
+            app.receive_notes(start);
+            assert_eq!(app.recently_clipped(start), clipped);
+        }
+        // The warning lingers for a second, then clears
+        assert!(!app.recently_clipped(start + Duration::from_secs(2)));
+    }
+
+    /// A local backend that answers every analysis with the single note `note`
+    fn mock_backend(note: &str, confidence: f32) -> String {
+        use std::io::{Read, Write};
+
+        let body = serde_json::json!({
+            "notes": [{"note": note, "confidence": confidence, "intensity": 0.8}],
+            "sample_rate": 48000,
+            "samples_analyzed": 32,
+            "timestamp": 0.0,
+        })
+        .to_string();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = [0; 4096];
+                let _ = stream.read(&mut request);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_comparison_backends_are_kept_separate() {
+        let primary = mock_backend("A4", 0.9);
+        let reference = mock_backend("A#4", 0.9);
+        let mut app = RecogNotesApp::new_with_config(primary.clone(), 48000);
+        app.add_comparison_backend(&reference);
+        app.add_comparison_backend(&format!("{reference}/")); // Already listed
+        app.add_comparison_backend(&primary);
+        assert_eq!(app.comparison_urls, std::slice::from_ref(&reference));
+
+        let slot = InFlightSlot::acquire(&app.in_flight).unwrap();
+        app.send_window(vec![0; 64], 48000, slot);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while app.backend_results.len() < 2 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            app.receive_notes(Instant::now());
+        }
+        let names = |url: &str| -> Vec<String> {
+            app.backend_results[url]
+                .iter()
+                .map(|n| n.note.clone())
+                .collect()
+        };
+        assert_eq!(names(&primary), ["A4"]);
+        assert_eq!(names(&reference), ["A#4"]);
+
+        // Only the primary drives the display and the export history
+        let shown: Vec<&str> = app.detected_notes.iter().map(|n| n.note.as_str()).collect();
+        assert_eq!(shown, ["A4"]);
+        assert_eq!(app.detected_notes_history.len(), 1);
+        assert_eq!(app.latency_samples.len(), 1);
+
+        app.remove_comparison_backend(&reference);
+        assert!(!app.backend_results.contains_key(&reference));
+    }
+
+    #[tokio::test]
+    async fn test_comparison_columns_are_filtered_alike() {
+        // Both answer below CONFIDENCE_ON: hidden from the display, yet listed in both columns
+        let primary = mock_backend("A4", 0.3);
+        let reference = mock_backend("A#4", 0.3);
+        let mut app = RecogNotesApp::new_with_config(primary.clone(), 48000);
+        app.add_comparison_backend(&reference);
+
+        let slot = InFlightSlot::acquire(&app.in_flight).unwrap();
+        app.send_window(vec![0; 64], 48000, slot);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while app.backend_results.len() < 2 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            app.receive_notes(Instant::now());
+        }
+
+        assert_eq!(app.backend_results[&primary].len(), 1);
+        assert_eq!(app.backend_results[&reference].len(), 1);
+        assert!(app.detected_notes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_slow_comparison_backend_does_not_block_the_primary() {
+        // Accepts connections but never answers, like a stalled reference server
+        let stalled = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let stalled_url = format!("http://{}", stalled.local_addr().unwrap());
+        let mut app = RecogNotesApp::new_with_config(mock_backend("A4", 0.9), 48000);
+        app.add_comparison_backend(&stalled_url);
+
+        for window in 0..MAX_IN_FLIGHT * 2 {
+            let slot = InFlightSlot::acquire(&app.in_flight)
+                .unwrap_or_else(|| panic!("window {window}: primary slot still held"));
+            app.send_window(vec![0; 64], 48000, slot);
+
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while app.in_flight.load(Ordering::Acquire) > 0 && Instant::now() < deadline {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        }
+        assert_eq!(app.in_flight.load(Ordering::Acquire), 0);
+        // The stalled backend stops getting windows once its own cap is reached
+        assert_eq!(
+            app.comparison_in_flight[&stalled_url].load(Ordering::Acquire),
+            MAX_IN_FLIGHT
+        );
+    }
+
+    #[test]
+    fn test_stream_error_stops_recording() {
+        let mut app = RecogNotesApp::default();
+        app.check_stream_errors();
+        assert!(app.last_error.is_none());
+
+        // Simulate the error callback firing while recording
+        app.recording = true;
+        app.audio_manager
+            .read()
+            .inject_stream_error(cpal::StreamError::DeviceNotAvailable);
+        app.check_stream_errors();
+
+        assert!(!app.recording);
+        assert!(app.last_error.as_deref().unwrap().contains("device failed"));
+    }
+
+    #[test]
+    fn test_missing_device_falls_back_to_default() {
+        let mut app = RecogNotesApp::default();
+        let devices = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        app.set_input_devices(devices(&["Default", "Built-in Mic", "USB Interface"]));
+        app.selected_input_device = Some("USB Interface".to_string());
+
+        // Rescan with the device still present: selection kept
+        app.set_input_devices(devices(&["Default", "USB Interface"]));
+        assert_eq!(app.selected_input_device.as_deref(), Some("USB Interface"));
+        assert!(app.last_error.is_none());
+
+        // Unplugged: back to Default with a warning
+        app.set_input_devices(devices(&["Default", "Built-in Mic"]));
+        assert!(app.selected_input_device.is_none());
+        assert!(app.last_error.as_deref().unwrap().contains("USB Interface"));
+        assert_eq!(app.input_devices.as_ref().unwrap().len(), 2);
+    }
 }
@@ -0,0 +1,378 @@
+use std::fmt::Write as _;
+
+use crate::export::merge_note_spans;
+use crate::DetectedNote;
+
+/// Spelling used for MIDI numbers (sharps, matching the backend's chromatic names)
+const STEPS: [(char, i32); 12] = [
+    ('C', 0),
+    ('C', 1),
+    ('D', 0),
+    ('D', 1),
+    ('E', 0),
+    ('F', 0),
+    ('F', 1),
+    ('G', 0),
+    ('G', 1),
+    ('A', 0),
+    ('A', 1),
+    ('B', 0),
+];
+/// Lines averaging below middle C get a bass clef
+const BASS_CLEF_BELOW: u8 = 60;
+const BEATS_PER_MEASURE: u32 = 4;
+
+/// Shortest note value durations are rounded to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grid {
+    Quarter,
+    Eighth,
+    Sixteenth,
+}
+
+impl Grid {
+    pub const ALL: [Self; 3] = [Self::Quarter, Self::Eighth, Self::Sixteenth];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Quarter => "1/4",
+            Self::Eighth => "1/8",
+            Self::Sixteenth => "1/16",
+        }
+    }
+
+    /// Grid steps per quarter note (used directly as MusicXML `<divisions>`)
+    const fn divisions(self) -> u32 {
+        match self {
+            Self::Quarter => 1,
+            Self::Eighth => 2,
+            Self::Sixteenth => 4,
+        }
+    }
+}
+
+/// MusicXML export settings
+#[derive(Debug, Clone, Copy)]
+pub struct MusicXmlExportOptions {
+    /// Tempo used to turn seconds into beats
+    pub bpm: f64,
+    pub grid: Grid,
+    /// Time between analysis windows; each detection covers one hop
+    pub hop_secs: f64,
+}
+
+/// Notes starting on the same grid step, held for `duration` steps
+struct QuantizedEvent {
+    start: u32,
+    duration: u32,
+    keys: Vec<u8>,
+}
+
+/// Emits `<note>` elements into 4/4 measures, tying notes across barlines
+struct MeasureWriter {
+    xml: String,
+    measure_len: u32,
+    position: u32,
+    number: u32,
+}
+
+impl MeasureWriter {
+    fn new(xml: String, divisions: u32) -> Self {
+        Self {
+            xml,
+            measure_len: divisions * BEATS_PER_MEASURE,
+            position: 0,
+            number: 1,
+        }
+    }
+
+    /// Write a chord (or a rest when `keys` is empty) lasting `duration` grid steps
+    fn write(&mut self, keys: &[u8], mut duration: u32) {
+        let mut tied_from_previous = false;
+        while duration > 0 {
+            if self.position == self.measure_len {
+                self.number += 1;
+                self.position = 0;
+                let _ = write!(
+                    self.xml,
+                    "    </measure>\n    <measure number=\"{}\">\n",
+                    self.number
+                );
+            }
+
+            let chunk = duration.min(self.measure_len - self.position);
+            duration -= chunk;
+            let tied_to_next = duration > 0 && !keys.is_empty();
+
+            if keys.is_empty() {
+                let _ = writeln!(
+                    self.xml,
+                    "      <note><rest/><duration>{chunk}</duration></note>"
+                );
+            }
+            for (i, &key) in keys.iter().enumerate() {
+                let (step, alter) = STEPS[usize::from(key % 12)];
+                let octave = i32::from(key / 12) - 1;
+
+                self.xml.push_str("      <note>");
+                if i > 0 {
+                    self.xml.push_str("<chord/>");
+                }
+                let _ = write!(self.xml, "<pitch><step>{step}</step>");
+                if alter != 0 {
+                    let _ = write!(self.xml, "<alter>{alter}</alter>");
+                }
+                let _ = write!(
+                    self.xml,
+                    "<octave>{octave}</octave></pitch><duration>{chunk}</duration>"
+                );
+
+                let mut ties = Vec::new();
+                if tied_from_previous {
+                    ties.push("stop");
+                }
+                if tied_to_next {
+                    ties.push("start");
+                }
+                for tie in &ties {
+                    let _ = write!(self.xml, "<tie type=\"{tie}\"/>");
+                }
+                if !ties.is_empty() {
+                    self.xml.push_str("<notations>");
+                    for tie in &ties {
+                        let _ = write!(self.xml, "<tied type=\"{tie}\"/>");
+                    }
+                    self.xml.push_str("</notations>");
+                }
+                self.xml.push_str("</note>\n");
+            }
+
+            self.position += chunk;
+            tied_from_previous = tied_to_next;
+        }
+    }
+
+    /// Pad the last measure with a rest and close the document
+    fn finish(mut self) -> String {
+        if self.position < self.measure_len {
+            self.write(&[], self.measure_len - self.position);
+        }
+        self.xml
+            .push_str("    </measure>\n  </part>\n</score-partwise>\n");
+        self.xml
+    }
+}
+
+/// Round the detection history onto the grid
+/// Notes starting on the same step form a chord; a chord is cut short when the next one starts
+fn quantize(
+    history: &[(DetectedNote, f64)],
+    options: MusicXmlExportOptions,
+) -> Vec<QuantizedEvent> {
+    let spans = merge_note_spans(history, options.hop_secs);
+    let Some(origin) = spans.first().map(|span| span.start_secs) else {
+        return Vec::new();
+    };
+    let step_secs = 60.0 / options.bpm / f64::from(options.grid.divisions());
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let to_steps = |secs: f64| ((secs - origin) / step_secs).round().max(0.0) as u32;
+
+    let mut events: Vec<QuantizedEvent> = Vec::new();
+    for span in &spans {
+        let start = to_steps(span.start_secs);
+        // Anything detected at all lasts at least one grid step
+        let end = to_steps(span.end_secs).max(start + 1);
+        match events.last_mut() {
+            Some(event) if event.start == start => {
+                event.duration = event.duration.max(end - start);
+                if !event.keys.contains(&span.midi) {
+                    event.keys.push(span.midi);
+                }
+            }
+            _ => events.push(QuantizedEvent {
+                start,
+                duration: end - start,
+                keys: vec![span.midi],
+            }),
+        }
+    }
+
+    for i in 1..events.len() {
+        let next_start = events[i].start;
+        let previous = &mut events[i - 1];
+        previous.duration = previous.duration.min(next_start - previous.start);
+        previous.keys.sort_unstable();
+    }
+    if let Some(last) = events.last_mut() {
+        last.keys.sort_unstable();
+    }
+    events
+}
+
+/// Build a single-part `<score-partwise>` document (4/4) from the detection history
+pub fn history_to_musicxml(
+    history: &[(DetectedNote, f64)],
+    options: MusicXmlExportOptions,
+) -> Result<String, String> {
+    if !(options.bpm.is_finite() && options.bpm > 0.0) {
+        return Err("Tempo must be greater than 0".to_string());
+    }
+
+    let events = quantize(history, options);
+    if events.is_empty() {
+        return Err("No notes to export".to_string());
+    }
+
+    let all_keys: Vec<u8> = events.iter().flat_map(|e| e.keys.iter().copied()).collect();
+    let average_key = all_keys.iter().map(|&k| usize::from(k)).sum::<usize>() / all_keys.len();
+    let (clef_sign, clef_line) = if average_key < usize::from(BASS_CLEF_BELOW) {
+        ('F', 4)
+    } else {
+        ('G', 2)
+    };
+    let divisions = options.grid.divisions();
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let bpm = options.bpm.round() as u32;
+
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 4.0 Partwise//EN\" ",
+        "\"http://www.musicxml.org/dtds/partwise.dtd\">\n",
+        "<score-partwise version=\"4.0\">\n",
+        "  <part-list>\n",
+        "    <score-part id=\"P1\"><part-name>Voice</part-name></score-part>\n",
+        "  </part-list>\n",
+        "  <part id=\"P1\">\n",
+        "    <measure number=\"1\">\n",
+    ));
+    let _ = writeln!(
+        xml,
+        "      <attributes><divisions>{divisions}</divisions><key><fifths>0</fifths></key>\
+         <time><beats>{BEATS_PER_MEASURE}</beats><beat-type>4</beat-type></time>\
+         <clef><sign>{clef_sign}</sign><line>{clef_line}</line></clef></attributes>"
+    );
+    let _ = writeln!(
+        xml,
+        "      <direction placement=\"above\"><direction-type><metronome>\
+         <beat-unit>quarter</beat-unit><per-minute>{bpm}</per-minute></metronome>\
+         </direction-type><sound tempo=\"{bpm}\"/></direction>"
+    );
+
+    let mut writer = MeasureWriter::new(xml, divisions);
+    let mut cursor = 0;
+    for event in &events {
+        if event.start > cursor {
+            writer.write(&[], event.start - cursor);
+        }
+        writer.write(&event.keys, event.duration);
+        cursor = event.start + event.duration;
+    }
+    Ok(writer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (step, alter, octave, duration) of a parsed `<note>`; rests have no pitch parts
+    type ParsedNote = (Option<String>, Option<String>, Option<String>, String);
+
+    fn detection(note: &str, timestamp: f64) -> (DetectedNote, f64) {
+        let note = DetectedNote {
+            note: note.to_string(),
+            confidence: 0.9,
+            intensity: 0.8,
+            ..DetectedNote::default()
+        };
+        (note, timestamp)
+    }
+
+    /// A note held for `secs`, reported every 20ms
+    fn held(note: &str, start: f64, secs: f64) -> Vec<(DetectedNote, f64)> {
+        (0..)
+            .map(|i| start + f64::from(i) * 0.02)
+            .take_while(|t| *t < start + secs - 0.01)
+            .map(|t| detection(note, t))
+            .collect()
+    }
+
+    #[test]
+    fn test_musicxml_parses_with_expected_pitches() {
+        // At 120 BPM a quarter is 0.5s: A4 quarter, C#5 eighth, eighth rest, G4 two beats
+        let mut history = held("A4", 0.0, 0.5);
+        history.extend(held("C#5", 0.5, 0.25));
+        history.extend(held("G4", 1.0, 1.0));
+
+        let options = MusicXmlExportOptions {
+            bpm: 120.0,
+            grid: Grid::Eighth,
+            hop_secs: 0.02,
+        };
+        let xml = history_to_musicxml(&history, options).unwrap();
+        let doc = roxmltree::Document::parse_with_options(
+            &xml,
+            roxmltree::ParsingOptions {
+                allow_dtd: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(doc.root_element().tag_name().name(), "score-partwise");
+
+        let child_text = |node: roxmltree::Node, name: &str| {
+            node.children()
+                .find(|c| c.has_tag_name(name))
+                .and_then(|c| c.text())
+                .map(str::to_string)
+        };
+        let notes: Vec<ParsedNote> = doc
+            .descendants()
+            .filter(|n| n.has_tag_name("note"))
+            .map(|note| {
+                let pitch = note.children().find(|c| c.has_tag_name("pitch"));
+                (
+                    pitch.and_then(|p| child_text(p, "step")),
+                    pitch.and_then(|p| child_text(p, "alter")),
+                    pitch.and_then(|p| child_text(p, "octave")),
+                    child_text(note, "duration").unwrap(),
+                )
+            })
+            .collect();
+
+        let pitched = |step: &str, alter: Option<&str>, octave: &str, duration: &str| {
+            (
+                Some(step.to_string()),
+                alter.map(str::to_string),
+                Some(octave.to_string()),
+                duration.to_string(),
+            )
+        };
+        let rest = |duration: &str| (None, None, None, duration.to_string());
+        assert_eq!(
+            notes,
+            [
+                pitched("A", None, "4", "2"),
+                pitched("C", Some("1"), "5", "1"),
+                rest("1"),
+                pitched("G", None, "4", "4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_long_note_is_tied_across_the_barline() {
+        // E4 starts on beat 4 and lasts two beats
+        let mut history = held("C4", 0.0, 1.5);
+        history.extend(held("E4", 1.5, 1.0));
+        let options = MusicXmlExportOptions {
+            bpm: 120.0,
+            grid: Grid::Quarter,
+            hop_secs: 0.02,
+        };
+        let xml = history_to_musicxml(&history, options).unwrap();
+        assert!(xml.contains("<measure number=\"2\">"));
+        assert_eq!(xml.matches("<tie type=\"start\"/>").count(), 1);
+        assert_eq!(xml.matches("<tie type=\"stop\"/>").count(), 1);
+        assert!(history_to_musicxml(&[], options).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use crate::export::note_to_midi;
+
+/// How note names are spelled on screen
+/// The backend always answers in scientific names; they are respelled only for display
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NamingScheme {
+    /// "A4", "C#5", "B3"
+    #[default]
+    Scientific,
+    /// B natural is "H", B flat is "B", other sharps take "-is" ("Fis4")
+    German,
+    /// Fixed-do: "La4", "Do#5", "Si3"
+    Solfege,
+}
+
+impl NamingScheme {
+    pub const ALL: [Self; 3] = [Self::Scientific, Self::German, Self::Solfege];
+
+    /// Name shown in the scheme selector
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Scientific => "C D E (scientific)",
+            Self::German => "C D H (German)",
+            Self::Solfege => "Do Re Mi (solfège)",
+        }
+    }
+
+    const fn pitch_class_names(self) -> &'static [&'static str; 12] {
+        match self {
+            Self::Scientific => &[
+                "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+            ],
+            Self::German => &[
+                "C", "Cis", "D", "Dis", "E", "F", "Fis", "G", "Gis", "A", "B", "H",
+            ],
+            Self::Solfege => &[
+                "Do", "Do#", "Re", "Re#", "Mi", "Fa", "Fa#", "Sol", "Sol#", "La", "La#", "Si",
+            ],
+        }
+    }
+
+    /// Respell a scientific name ("A#4") in this scheme; unparseable names are shown as-is
+    pub fn display(self, scientific: &str) -> String {
+        match note_to_midi(scientific) {
+            Some(midi) if self != Self::Scientific => {
+                let octave = i32::from(midi / 12) - 1;
+                format!(
+                    "{}{octave}",
+                    self.pitch_class_names()[usize::from(midi % 12)]
+                )
+            }
+            _ => scientific.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_note_in_every_scheme() {
+        assert_eq!(NamingScheme::Scientific.display("A4"), "A4");
+        assert_eq!(NamingScheme::Solfege.display("A4"), "La4");
+        assert_eq!(NamingScheme::German.display("B4"), "H4");
+        assert_eq!(NamingScheme::German.display("A#4"), "B4");
+        assert_eq!(NamingScheme::Solfege.display("C#2"), "Do#2");
+        assert_eq!(NamingScheme::German.display("—"), "—");
+    }
+}
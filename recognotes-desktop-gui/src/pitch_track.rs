@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::DetectedNote;
+
+/// Responses averaged into each point of the curve (~100ms at the default 20ms interval)
+const SMOOTHING_RESPONSES: usize = 5;
+/// A longer silence starts a new curve instead of averaging the old pitch into the new one
+pub const MAX_GAP: Duration = Duration::from_millis(250);
+
+/// Smoothed frequency of the loudest note over time, so glissandi and sirens show up as a
+/// continuous line rather than a staircase of note names
+#[derive(Debug, Default)]
+pub struct PitchTrack {
+    // Raw frequencies of the loudest note in the last `SMOOTHING_RESPONSES` responses
+    recent: VecDeque<f32>,
+    // (time, smoothed Hz), oldest first
+    points: VecDeque<(Instant, f32)>,
+}
+
+impl PitchTrack {
+    /// Feed one backend response (responses without a measured frequency are skipped)
+    pub fn update(&mut self, notes: &[DetectedNote], now: Instant) {
+        let Some(loudest) = notes
+            .iter()
+            .filter(|note| note.frequency > 0.0)
+            .max_by(|a, b| a.intensity.total_cmp(&b.intensity))
+        else {
+            return;
+        };
+
+        if self
+            .points
+            .back()
+            .is_some_and(|(last, _)| now.saturating_duration_since(*last) > MAX_GAP)
+        {
+            self.recent.clear();
+        }
+        if self.recent.len() == SMOOTHING_RESPONSES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(loudest.frequency);
+
+        // Average in log space, so a semitone up and a semitone down cancel out
+        #[allow(clippy::cast_precision_loss)]
+        let mean_octaves =
+            self.recent.iter().map(|f| f.log2()).sum::<f32>() / self.recent.len() as f32;
+        self.points.push_back((now, mean_octaves.exp2()));
+    }
+
+    /// Drop points from before `cutoff`
+    pub fn prune(&mut self, cutoff: Instant) {
+        while self.points.front().is_some_and(|(time, _)| *time < cutoff) {
+            self.points.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.points.clear();
+    }
+
+    /// (time, smoothed Hz) points, oldest first
+    pub const fn points(&self) -> &VecDeque<(Instant, f32)> {
+        &self.points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(frequency: f32) -> DetectedNote {
+        DetectedNote {
+            note: "A4".to_string(),
+            confidence: 0.9,
+            intensity: 0.8,
+            frequency,
+            ..DetectedNote::default()
+        }
+    }
+
+    #[test]
+    fn test_smoothed_curve_follows_a_rising_ramp() {
+        let mut track = PitchTrack::default();
+        let start = Instant::now();
+        let step = Duration::from_millis(20);
+        // A siren: up a semitone per response
+        let ramp: Vec<f32> = (0..20).map(|i| 220.0 * (i as f32 / 12.0).exp2()).collect();
+        for (i, &frequency) in ramp.iter().enumerate() {
+            track.update(&[note(frequency)], start + step * i as u32);
+        }
+
+        let smoothed: Vec<f32> = track.points().iter().map(|(_, hz)| *hz).collect();
+        assert_eq!(smoothed.len(), ramp.len());
+        assert_eq!(smoothed[0], ramp[0]);
+        // Still rising, but lagging the raw pitch
+        assert!(smoothed.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(smoothed.iter().zip(&ramp).skip(1).all(|(s, r)| s < r));
+        // Once the buffer is full, the log-average sits 2 semitones back (mid-window)
+        let lag = 12.0 * (ramp[19] / smoothed[19]).log2();
+        assert!((lag - 2.0).abs() < 1e-3, "{lag}");
+
+        // After a pause the curve restarts at the new pitch instead of sliding into it
+        let resumed = start + step * 19 + MAX_GAP * 2;
+        track.update(&[note(110.0)], resumed);
+        assert_eq!(track.points().back(), Some(&(resumed, 110.0)));
+
+        track.prune(resumed);
+        assert_eq!(track.points().len(), 1);
+    }
+}
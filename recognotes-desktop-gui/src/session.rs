@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+use crate::DetectedNote;
+
+/// File format for the raw note history export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// One row per detection, metadata in `#` comment lines (spreadsheets)
+    #[default]
+    Csv,
+    /// `{"sample_rate": .., "profile": .., "notes": [..]}` (scripts)
+    Json,
+}
+
+impl HistoryFormat {
+    pub const ALL: [Self; 2] = [Self::Csv, Self::Json];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Json => "JSON",
+        }
+    }
+
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// How the session was recorded, written ahead of the detections
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionMetadata {
+    pub sample_rate: u32,
+    pub profile: String,
+}
+
+/// One detection, timed from the start of the recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryRow {
+    pub time_secs: f64,
+    pub note: String,
+    pub confidence: f32,
+    pub intensity: f32,
+    pub frequency: f32,
+    pub cents_off: f32,
+}
+
+/// The whole JSON export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionExport {
+    #[serde(flatten)]
+    pub metadata: SessionMetadata,
+    pub notes: Vec<HistoryRow>,
+}
+
+fn history_rows(history: &[(DetectedNote, f64)]) -> Vec<HistoryRow> {
+    history
+        .iter()
+        .map(|(note, time_secs)| HistoryRow {
+            time_secs: *time_secs,
+            note: note.note.clone(),
+            confidence: note.confidence,
+            intensity: note.intensity,
+            frequency: note.frequency,
+            cents_off: note.cents_off,
+        })
+        .collect()
+}
+
+/// Serialize every detection of the note timeline (unmerged, unlike the MIDI/MusicXML exports)
+pub fn export_history(
+    history: &[(DetectedNote, f64)],
+    metadata: &SessionMetadata,
+    format: HistoryFormat,
+) -> Result<Vec<u8>, String> {
+    if history.is_empty() {
+        return Err("No notes to export".to_string());
+    }
+    let rows = history_rows(history);
+
+    match format {
+        HistoryFormat::Json => serde_json::to_vec_pretty(&SessionExport {
+            metadata: metadata.clone(),
+            notes: rows,
+        })
+        .map_err(|e| e.to_string()),
+        HistoryFormat::Csv => {
+            let mut out = format!(
+                "# sample_rate: {}\n# profile: {}\n",
+                metadata.sample_rate, metadata.profile
+            )
+            .into_bytes();
+            let mut writer = csv::Writer::from_writer(&mut out);
+            for row in &rows {
+                writer.serialize(row).map_err(|e| e.to_string())?;
+            }
+            writer.flush().map_err(|e| e.to_string())?;
+            drop(writer);
+            Ok(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> Vec<(DetectedNote, f64)> {
+        let note = |name: &str, frequency: f32, cents_off: f32| DetectedNote {
+            note: name.to_string(),
+            confidence: 0.9,
+            intensity: 0.75,
+            frequency,
+            cents_off,
+        };
+        vec![
+            (note("A4", 441.0, 3.9), 0.0),
+            (note("A4", 440.0, 0.0), 0.02),
+            (note("C#5", 552.0, -6.2), 0.5),
+        ]
+    }
+
+    #[test]
+    fn test_history_round_trips_through_csv_and_json() {
+        let metadata = SessionMetadata {
+            sample_rate: 48000,
+            profile: "tenor".to_string(),
+        };
+        let expected = history_rows(&history());
+
+        let json = export_history(&history(), &metadata, HistoryFormat::Json).unwrap();
+        let parsed: SessionExport = serde_json::from_slice(&json).unwrap();
+        assert_eq!(parsed.metadata, metadata);
+        assert_eq!(parsed.notes, expected);
+
+        let csv = export_history(&history(), &metadata, HistoryFormat::Csv).unwrap();
+        let text = String::from_utf8(csv.clone()).unwrap();
+        assert!(text.starts_with("# sample_rate: 48000\n# profile: tenor\n"));
+        let mut reader = csv::ReaderBuilder::new()
+            .comment(Some(b'#'))
+            .from_reader(csv.as_slice());
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec![
+                "time_secs",
+                "note",
+                "confidence",
+                "intensity",
+                "frequency",
+                "cents_off"
+            ]
+        );
+        let rows: Vec<HistoryRow> = reader.deserialize().map(Result::unwrap).collect();
+        assert_eq!(rows, expected);
+
+        assert!(export_history(&[], &metadata, HistoryFormat::Csv).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long a detected note stays listed, and how long its bar takes to fade out, by default
+pub const DEFAULT_NOTE_DISPLAY_MS: u64 = 1000;
+pub const DEFAULT_FADE_MS: u64 = 600;
+/// Slider range for both: snappy enough for fast runs, lingering enough for slow ballads
+pub const MIN_PERSISTENCE_MS: u64 = 100;
+pub const MAX_PERSISTENCE_MS: u64 = 5000;
+
+/// Light or dark look for the whole window, visualizations included
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Theme {
+    /// Label of the toggle button (shows the theme it switches to)
+    pub const fn toggle_label(self) -> &'static str {
+        match self {
+            Self::Dark => "☀ Light",
+            Self::Light => "🌙 Dark",
+        }
+    }
+
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Dark => Self::Light,
+            Self::Light => Self::Dark,
+        }
+    }
+
+    /// egui visuals for this theme; the views pick their palette from these
+    pub fn visuals(self) -> egui::Visuals {
+        match self {
+            Self::Dark => egui::Visuals::dark(),
+            Self::Light => egui::Visuals::light(),
+        }
+    }
+}
+
+/// Preferences kept between sessions in eframe's app storage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Theme,
+    /// How long detected notes stay in the note list after they were last heard
+    pub note_display_ms: u64,
+    /// How long a note's bar (or key) takes to fade out
+    pub fade_ms: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            note_display_ms: DEFAULT_NOTE_DISPLAY_MS,
+            fade_ms: DEFAULT_FADE_MS,
+        }
+    }
+}
+
+impl Settings {
+    /// Key the settings are stored under
+    const STORAGE_KEY: &'static str = "recognotes_settings";
+
+    /// Settings from the last session; defaults on first run or if they can't be read
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value(storage, Self::STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    /// `note_display_ms`, kept within the slider range even if the stored value isn't
+    pub fn note_display_duration(&self) -> Duration {
+        Duration::from_millis(
+            self.note_display_ms
+                .clamp(MIN_PERSISTENCE_MS, MAX_PERSISTENCE_MS),
+        )
+    }
+
+    /// `fade_ms`, kept within the slider range even if the stored value isn't
+    pub fn fade_duration(&self) -> Duration {
+        Duration::from_millis(self.fade_ms.clamp(MIN_PERSISTENCE_MS, MAX_PERSISTENCE_MS))
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, Self::STORAGE_KEY, self);
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use crate::DetectedNote;
+
+/// Needle turns green within this many cents of the target
+pub const IN_TUNE_CENTS: f32 = 5.0;
+/// A new loudest note must win this many responses in a row before the tuner switches to it
+const LOCK_RESPONSES: u32 = 3;
+/// The locked note is dropped after this long without being detected
+const HOLD_DURATION: Duration = Duration::from_secs(1);
+/// Weight of each new reading in the needle's moving average (lower = steadier)
+const CENTS_SMOOTHING: f32 = 0.3;
+
+/// Note the tuner is locked onto
+#[derive(Debug, Clone, PartialEq)]
+pub struct TunerReading {
+    pub note: String,
+    pub frequency: f32,
+    /// Smoothed cents offset (positive = sharp)
+    pub cents_off: f32,
+}
+
+impl TunerReading {
+    pub fn in_tune(&self) -> bool {
+        self.cents_off.abs() <= IN_TUNE_CENTS
+    }
+}
+
+/// Tracks the loudest detected note, ignoring momentary detections so the needle doesn't jitter
+#[derive(Debug, Default)]
+pub struct TunerState {
+    locked: Option<TunerReading>,
+    last_seen: Option<Instant>,
+    // Loudest note that differs from the locked one, and how many responses in a row it has won
+    candidate: Option<String>,
+    candidate_count: u32,
+}
+
+impl TunerState {
+    /// Feed one backend response
+    pub fn update(&mut self, notes: &[DetectedNote], now: Instant) {
+        let Some(loudest) = notes
+            .iter()
+            .max_by(|a, b| a.intensity.total_cmp(&b.intensity))
+        else {
+            return;
+        };
+
+        if self.reading(now).is_some_and(|r| r.note == loudest.note) {
+            let locked = self.locked.as_mut().unwrap();
+            locked.frequency = loudest.frequency;
+            locked.cents_off += (loudest.cents_off - locked.cents_off) * CENTS_SMOOTHING;
+            self.last_seen = Some(now);
+            self.candidate = None;
+            return;
+        }
+
+        if self.candidate.as_deref() == Some(loudest.note.as_str()) {
+            self.candidate_count += 1;
+        } else {
+            self.candidate = Some(loudest.note.clone());
+            self.candidate_count = 1;
+        }
+
+        // Nothing locked (or it went stale): the first detection is enough
+        if self.candidate_count >= LOCK_RESPONSES || self.reading(now).is_none() {
+            self.locked = Some(TunerReading {
+                note: loudest.note.clone(),
+                frequency: loudest.frequency,
+                cents_off: loudest.cents_off,
+            });
+            self.last_seen = Some(now);
+            self.candidate = None;
+        }
+    }
+
+    /// Current reading, or `None` once the note hasn't been heard for a while
+    pub fn reading(&self, now: Instant) -> Option<&TunerReading> {
+        let last_seen = self.last_seen?;
+        (now.saturating_duration_since(last_seen) < HOLD_DURATION)
+            .then_some(self.locked.as_ref())
+            .flatten()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(name: &str, intensity: f32, cents_off: f32) -> DetectedNote {
+        DetectedNote {
+            note: name.to_string(),
+            confidence: 0.9,
+            intensity,
+            cents_off,
+            ..DetectedNote::default()
+        }
+    }
+
+    #[test]
+    fn test_tuner_locks_loudest_and_ignores_blips() {
+        let mut tuner = TunerState::default();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        tuner.update(&[note("E4", 0.3, 0.0), note("A4", 0.9, 12.0)], at(0));
+        assert_eq!(tuner.reading(at(0)).unwrap().note, "A4");
+
+        // A single louder E4 doesn't steal the needle
+        tuner.update(&[note("E4", 0.9, 0.0)], at(20));
+        tuner.update(&[note("A4", 0.9, 2.0)], at(40));
+        let reading = tuner.reading(at(40)).unwrap();
+        assert_eq!(reading.note, "A4");
+        // Smoothed towards the new reading rather than jumping to it
+        assert!(reading.cents_off < 12.0 && reading.cents_off > 2.0);
+        assert!(!reading.in_tune());
+
+        // A sustained change does
+        for ms in [60, 80, 100] {
+            tuner.update(&[note("E4", 0.9, -3.0)], at(ms));
+        }
+        let reading = tuner.reading(at(100)).unwrap();
+        assert_eq!(reading.note, "E4");
+        assert!(reading.in_tune());
+
+        // Silence releases the lock
+        assert!(tuner.reading(at(1200)).is_none());
+    }
+}
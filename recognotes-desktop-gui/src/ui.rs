@@ -1,8 +1,43 @@
 use crate::RecogNotesApp;
 use eframe::egui;
 
+/// Keys 0-6 pick the voice profile at that position in `VoiceProfile::all_profiles`
+const PROFILE_KEYS: [egui::Key; 7] = [
+    egui::Key::Num0,
+    egui::Key::Num1,
+    egui::Key::Num2,
+    egui::Key::Num3,
+    egui::Key::Num4,
+    egui::Key::Num5,
+    egui::Key::Num6,
+];
+
+/// Keyboard shortcuts: Space toggles recording, C clears, 0-6 switch voice profile
+/// Ignored while a text field (the backend URL) has focus, so typing there stays typing
+fn handle_shortcuts(app: &mut RecogNotesApp, ctx: &egui::Context) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+
+    let pressed = |key| ctx.input_mut(|input| input.consume_key(egui::Modifiers::NONE, key));
+    if pressed(egui::Key::Space) {
+        app.toggle_recording();
+    }
+    if pressed(egui::Key::C) {
+        app.clear_notes();
+    }
+    let profiles = recognotes_core::VoiceProfile::all_profiles();
+    for (key, profile) in PROFILE_KEYS.into_iter().zip(profiles) {
+        if pressed(key) {
+            app.selected_profile = (*profile).to_string();
+        }
+    }
+}
+
 #[allow(clippy::too_many_lines)]
 pub fn draw_ui(app: &mut RecogNotesApp, ctx: &egui::Context) {
+    handle_shortcuts(app, ctx);
+
     egui::CentralPanel::default().show(ctx, |ui| {
         // Top bar: Title + Status
         ui.horizontal(|ui| {
@@ -13,27 +48,63 @@ pub fn draw_ui(app: &mut RecogNotesApp, ctx: &egui::Context) {
             if app.backend_connected {
                 ui.colored_label(egui::Color32::GREEN, "● Connected");
             } else {
-                ui.colored_label(egui::Color32::RED, "● Offline");
+                let offline = ui.colored_label(egui::Color32::RED, "● Offline");
+                if let Some(reason) = &app.backend_offline_reason {
+                    offline.on_hover_text(reason);
+                }
             }
 
             if app.recording {
                 ui.colored_label(egui::Color32::RED, "● Recording");
             }
 
+            // Round-trip latency: is the backend keeping up with the analysis interval?
+            if let Some(latency_ms) = app.average_latency_ms() {
+                if app.latency_lagging() {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 200, 0),
+                        format!("⏱ {latency_ms:.0} ms (backend can't keep pace)"),
+                    )
+                    .on_hover_text("Responses take longer than the analysis interval; raise \"Every\" or use a shorter window");
+                } else {
+                    ui.label(
+                        egui::RichText::new(format!("⏱ {latency_ms:.0} ms"))
+                            .color(egui::Color32::GRAY),
+                    );
+                }
+            }
+
+            // Input level: catch a too-quiet or clipping mic before blaming the detector
+            if app.recording || app.file_playback.is_some() {
+                let clipping = app.recently_clipped(std::time::Instant::now());
+                crate::visualization::draw_level_meter(ui, app.input_levels, clipping);
+            }
+
+            // Pitch stability: confidence of the strongest note over the last few seconds
+            if app.recording {
+                crate::visualization::draw_confidence_sparkline(
+                    ui,
+                    &app.confidence_history,
+                    crate::CONFIDENCE_WINDOW,
+                    app.display_now(),
+                );
+            }
+
+            ui.separator();
+
+            let theme = app.settings.theme;
+            if ui.button(theme.toggle_label()).clicked() {
+                app.set_theme(ui.ctx(), theme.toggled());
+            }
+
             ui.separator();
 
             // Backend URL control in top bar
             ui.label("Backend:");
             ui.text_edit_singleline(&mut app.backend_url);
             if ui.small_button("✓").clicked() {
-                let backend_url = app.backend_url.clone();
-                tokio::spawn(async move {
-                    match crate::backend_client::check_health(&backend_url).await {
-                        Ok(()) => log::info!("✓ Backend OK"),
-                        Err(e) => log::error!("✗ {e}"),
-                    }
-                });
-                app.backend_connected = true;
+                // Result updates the status light through the regular health check
+                app.recheck_backend();
             }
         });
 
@@ -44,39 +115,24 @@ pub fn draw_ui(app: &mut RecogNotesApp, ctx: &egui::Context) {
             // Voice profile selector
             ui.label("Voice Profile:");
 
-            let available_profiles = [
-                "no_profile",
-                "soprano",
-                "mezzo",
-                "alto",
-                "tenor",
-                "baritone",
-                "bass",
-            ];
-
             egui::ComboBox::from_id_source("voice_profile_combo")
                 .selected_text(app.selected_profile.as_str())
                 .show_ui(ui, |ui| {
-                    for profile in &available_profiles {
+                    for &profile in recognotes_core::VoiceProfile::all_profiles() {
                         ui.selectable_value(
                             &mut app.selected_profile,
-                            (*profile).to_string(),
-                            *profile,
+                            profile.to_string(),
+                            profile,
                         );
                     }
-                });
+                })
+                .response
+                .on_hover_text("Keys 0-6 (0 = no profile)");
 
             // Show profile info
-            if app.selected_profile != "no_profile" {
-                let profile_info = match app.selected_profile.as_str() {
-                    "soprano" => "C4-C6 (261-1047 Hz)",
-                    "mezzo" => "A3-A5 (220-880 Hz)",
-                    "alto" => "F3-F5 (175-698 Hz)",
-                    "tenor" => "C3-C5 (131-523 Hz)",
-                    "baritone" => "A2-A4 (110-440 Hz)",
-                    "bass" => "C2-C4 (65-261 Hz)",
-                    _ => "",
-                };
+            if let Some(profile_info) =
+                crate::visualization::profile_summary(&app.selected_profile, app.naming)
+            {
                 ui.label(
                     egui::RichText::new(profile_info)
                         .size(11.0)
@@ -86,9 +142,12 @@ pub fn draw_ui(app: &mut RecogNotesApp, ctx: &egui::Context) {
 
             ui.separator();
 
-            // Input device selector
+            // Input device selector (enumerated once, then only on "Refresh")
             ui.label("Input device:");
-            let input_devices = crate::audio::AudioManager::get_input_devices();
+            if app.input_devices.is_none() {
+                app.refresh_input_devices();
+            }
+            let input_devices = app.input_devices.clone().unwrap_or_default();
 
             egui::ComboBox::from_id_source("input_device_combo")
                 .selected_text(app.selected_input_device.as_deref().unwrap_or("Default"))
@@ -107,6 +166,33 @@ pub fn draw_ui(app: &mut RecogNotesApp, ctx: &egui::Context) {
                         }
                     }
                 });
+            if ui
+                .small_button("🔄")
+                .on_hover_text("Refresh devices")
+                .clicked()
+            {
+                app.refresh_input_devices();
+            }
+
+            // Capture rate: only what the device actually supports
+            if !app.recording {
+                let rates = app.capture_rates();
+                egui::ComboBox::from_id_source("capture_rate_combo")
+                    .selected_text(format!("{} Hz", app.capture_rate))
+                    .show_ui(ui, |ui| {
+                        for rate in rates {
+                            ui.selectable_value(&mut app.capture_rate, rate, format!("{rate} Hz"));
+                        }
+                    });
+            } else {
+                // What the device actually negotiated
+                let manager = app.audio_manager.read();
+                ui.label(format!(
+                    "{} Hz · {} ch",
+                    manager.sample_rate(),
+                    manager.channels()
+                ));
+            }
         });
 
         ui.separator();
@@ -119,22 +205,321 @@ pub fn draw_ui(app: &mut RecogNotesApp, ctx: &egui::Context) {
                 } else {
                     "🎤 Record"
                 })
+                .on_hover_text("Space")
                 .clicked()
             {
-                if app.recording {
-                    app.stop_recording();
-                } else {
-                    app.start_recording();
+                app.toggle_recording();
+            }
+
+            if app.file_playback.is_some() {
+                if ui.button("⏹ Stop file").clicked() {
+                    app.stop_file_playback();
                 }
+            } else if ui
+                .add_enabled(!app.recording, egui::Button::new("📂 Open file"))
+                .clicked()
+            {
+                app.open_audio_file();
+            }
+            if let Some(playback) = &app.file_playback {
+                ui.label(format!("▶ {}", playback.name));
+            }
+
+            if ui.button("💾 Save recording").clicked() {
+                app.save_recording();
+            }
+
+            let freeze_label = if app.frozen { "▶ Live" } else { "❄ Freeze" };
+            if ui
+                .add_enabled(
+                    app.recording || app.file_playback.is_some() || app.frozen,
+                    egui::Button::new(freeze_label).selected(app.frozen),
+                )
+                .on_hover_text("Hold the display to study it; recording carries on")
+                .clicked()
+            {
+                app.toggle_freeze();
+            }
+
+            if ui.button("Clear").on_hover_text("C").clicked() {
+                app.clear_notes();
+            }
+
+            ui.separator();
+
+            // Main view: every note as bars, or a single-note tuner
+            ui.selectable_value(
+                &mut app.view_mode,
+                crate::visualization::ViewMode::Spectrum,
+                "📊 Spectrum",
+            );
+            ui.selectable_value(
+                &mut app.view_mode,
+                crate::visualization::ViewMode::PianoRoll,
+                "📜 Piano roll",
+            );
+            ui.selectable_value(
+                &mut app.view_mode,
+                crate::visualization::ViewMode::Keyboard,
+                "🎹 Keyboard",
+            );
+            ui.selectable_value(
+                &mut app.view_mode,
+                crate::visualization::ViewMode::Tuner,
+                "🎯 Tuner",
+            );
+            if app.view_mode == crate::visualization::ViewMode::PianoRoll {
+                ui.checkbox(&mut app.show_pitch_curve, "〰 Pitch curve")
+                    .on_hover_text("Smoothed pitch of the loudest note, for slides and glissandi");
+            }
+
+            // Note spelling used by every view
+            egui::ComboBox::from_id_source("naming_combo")
+                .selected_text(app.naming.label())
+                .show_ui(ui, |ui| {
+                    for scheme in crate::naming::NamingScheme::ALL {
+                        ui.selectable_value(&mut app.naming, scheme, scheme.label());
+                    }
+                });
+
+            // Bar colors: loudness, or a fixed hue per pitch class to follow a melody
+            egui::ComboBox::from_id_source("color_mode_combo")
+                .selected_text(app.color_mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in crate::visualization::ColorMode::ALL {
+                        ui.selectable_value(&mut app.color_mode, mode, mode.label());
+                    }
+                });
+
+            ui.separator();
+
+            // Analysis window: shorter is snappier, longer resolves bass better
+            let mut window_secs = app.sliding_window_secs;
+            let window_slider = egui::Slider::new(
+                &mut window_secs,
+                crate::MIN_WINDOW_SECS..=crate::MAX_WINDOW_SECS,
+            )
+            .step_by(0.25)
+            .suffix(" s")
+            .text("Window");
+            if ui.add(window_slider).changed() {
+                let sample_rate = app.audio_manager.read().sample_rate();
+                app.set_sliding_window(window_secs, sample_rate);
             }
 
-            if ui.button("Clear").clicked() {
-                app.detected_notes.clear();
-                app.detected_notes_history.clear();
-                app.last_error = None;
+            #[allow(clippy::cast_possible_truncation)]
+            let mut interval_ms = app.sliding_window_interval.as_millis() as u64;
+            let interval_slider = egui::Slider::new(
+                &mut interval_ms,
+                crate::MIN_INTERVAL_MS..=crate::MAX_INTERVAL_MS,
+            )
+            .suffix(" ms")
+            .text("Every");
+            if ui.add(interval_slider).changed() {
+                app.set_analysis_interval_ms(interval_ms);
+            }
+
+            // How long notes linger: listed for "Hold", bars and keys fade over "Fade"
+            let persistence = crate::settings::MIN_PERSISTENCE_MS..=crate::settings::MAX_PERSISTENCE_MS;
+            ui.add(
+                egui::Slider::new(&mut app.settings.note_display_ms, persistence.clone())
+                    .suffix(" ms")
+                    .text("Hold"),
+            )
+            .on_hover_text("How long a note stays listed after it was last heard");
+            ui.add(
+                egui::Slider::new(&mut app.settings.fade_ms, persistence)
+                    .suffix(" ms")
+                    .text("Fade"),
+            )
+            .on_hover_text("How long a note's bar or key takes to fade out");
+
+            // Steady notes: hide detections that flicker in and out
+            ui.checkbox(&mut app.steady_enabled, "Steady")
+                .on_hover_text("Only show notes heard in most of the last few analyses");
+            if app.steady_enabled {
+                ui.add(
+                    egui::DragValue::new(&mut app.steady_required)
+                        .range(1..=app.steady_windows)
+                        .suffix(" of"),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut app.steady_windows)
+                        .range(1..=crate::MAX_STEADY_WINDOWS),
+                );
+                app.steady_required = app.steady_required.min(app.steady_windows);
+            }
+        });
+
+        // Export the note timeline
+        ui.horizontal(|ui| {
+            ui.label("Export:");
+            ui.add(
+                egui::DragValue::new(&mut app.export_bpm)
+                    .range(20.0..=300.0)
+                    .suffix(" BPM"),
+            );
+            ui.add(
+                egui::DragValue::new(&mut app.export_ppq)
+                    .range(24..=960)
+                    .suffix(" PPQ"),
+            );
+            let has_notes = !app.detected_notes_history.is_empty();
+            if ui
+                .add_enabled(has_notes, egui::Button::new("🎹 Export MIDI"))
+                .clicked()
+            {
+                app.export_midi();
+            }
+
+            egui::ComboBox::from_id_source("export_grid_combo")
+                .selected_text(app.export_grid.label())
+                .show_ui(ui, |ui| {
+                    for grid in crate::musicxml::Grid::ALL {
+                        ui.selectable_value(&mut app.export_grid, grid, grid.label());
+                    }
+                });
+            if ui
+                .add_enabled(has_notes, egui::Button::new("🎼 Export MusicXML"))
+                .clicked()
+            {
+                app.export_musicxml();
+            }
+
+            egui::ComboBox::from_id_source("history_format_combo")
+                .selected_text(app.history_format.label())
+                .show_ui(ui, |ui| {
+                    for format in crate::session::HistoryFormat::ALL {
+                        ui.selectable_value(&mut app.history_format, format, format.label());
+                    }
+                });
+            if ui
+                .add_enabled(has_notes, egui::Button::new("📄 Export history"))
+                .on_hover_text("Every detection with its time, confidence and intensity")
+                .clicked()
+            {
+                app.export_history();
+            }
+            ui.label(
+                egui::RichText::new(format!("{} detections", app.detected_notes_history.len()))
+                    .size(11.0)
+                    .color(egui::Color32::GRAY),
+            );
+
+            // Most likely key of everything heard this take
+            let keys = recognotes_core::key::estimate_key(&app.key_histogram);
+            if let Some(best) = keys.first() {
+                let top = keys
+                    .iter()
+                    .take(3)
+                    .map(|key| format!("{} ({:.2})", key.name, key.correlation))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ui.label(format!("Key: {}", best.name))
+                    .on_hover_text(format!("Best matches (correlation):\n{top}"));
+            }
+        });
+
+        // A/B backends: every window also goes to these, results shown side by side
+        ui.horizontal(|ui| {
+            ui.label("Compare with:");
+            let field = ui.add(
+                egui::TextEdit::singleline(&mut app.comparison_url_input)
+                    .hint_text("http://127.0.0.1:5001")
+                    .desired_width(180.0),
+            );
+            let submitted = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.small_button("➕ Add").clicked() || submitted {
+                let url = std::mem::take(&mut app.comparison_url_input);
+                app.add_comparison_backend(&url);
+            }
+            let mut removed = None;
+            for url in &app.comparison_urls {
+                if ui
+                    .small_button(format!("✖ {url}"))
+                    .on_hover_text("Stop comparing with this backend")
+                    .clicked()
+                {
+                    removed = Some(url.clone());
+                }
+            }
+            if let Some(url) = removed {
+                app.remove_comparison_backend(&url);
             }
         });
 
+        if !app.comparison_urls.is_empty() {
+            egui::Grid::new("backend_comparison_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    let backends = std::iter::once(&app.backend_url).chain(&app.comparison_urls);
+                    for url in backends {
+                        ui.label(egui::RichText::new(url).monospace());
+                        let notes = match app.backend_results.get(url) {
+                            None => "—".to_string(),
+                            Some(notes) if notes.is_empty() => "(no notes)".to_string(),
+                            Some(notes) => {
+                                notes
+                                    .iter()
+                                    .map(|note| {
+                                        format!(
+                                            "{} {:.0}%",
+                                            app.naming.display(&note.note),
+                                            note.confidence * 100.0
+                                        )
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .join("  ")
+                            }
+                        };
+                        ui.label(notes);
+                        ui.end_row();
+                    }
+                });
+        }
+
+        // Tuner: hear the target pitch of the locked note
+        if app.view_mode == crate::visualization::ViewMode::Tuner {
+            let target = app
+                .tuner
+                .reading(app.display_now())
+                .map(|reading| reading.note.clone());
+            ui.horizontal(|ui| {
+                let label = target
+                    .as_deref()
+                    .map_or_else(|| "—".to_string(), |note| app.naming.display(note));
+                ui.label(format!("Target: {label}"));
+                if ui
+                    .add_enabled(target.is_some(), egui::Button::new("🔊 Play"))
+                    .on_hover_text("Play the in-tune pitch of this note")
+                    .clicked()
+                {
+                    if let Some(note) = target.as_deref() {
+                        app.play_reference_tone(note);
+                    }
+                }
+            });
+        }
+
+        // Strongest note as plain numbers (the tuner view shows its own)
+        if app.view_mode != crate::visualization::ViewMode::Tuner {
+            let strongest = app
+                .detected_notes
+                .iter()
+                .max_by(|a, b| a.intensity.total_cmp(&b.intensity));
+            let readout = strongest.map_or_else(
+                || {
+                    egui::RichText::new("—")
+                        .color(ui.visuals().weak_text_color())
+                },
+                |note| {
+                    egui::RichText::new(crate::visualization::note_readout(note, app.naming))
+                        .strong()
+                },
+            );
+            ui.label(readout.monospace().size(22.0));
+        }
+
         // Error display
         if let Some(error) = &app.last_error {
             ui.colored_label(egui::Color32::RED, format!("⚠ {error}"));
@@ -154,13 +539,118 @@ pub fn draw_ui(app: &mut RecogNotesApp, ctx: &egui::Context) {
             egui::Sense::hover(),
         );
 
-        // Draw notes spectrum with vertical bars and fade effect
-        crate::visualization::draw_vertical_bars_with_fade(
-            ui,
-            &app.detected_notes,
-            &app.notes_with_timestamps,
-            notes_response.rect,
-            &app.selected_profile,
-        );
+        let now = app.display_now();
+        match app.view_mode {
+            // Draw notes spectrum with vertical bars and fade effect
+            crate::visualization::ViewMode::Spectrum => {
+                crate::visualization::draw_vertical_bars_with_fade(
+                    ui,
+                    &app.notes_with_timestamps,
+                    now,
+                    app.settings.fade_duration(),
+                    notes_response.rect,
+                    &app.selected_profile,
+                    app.naming,
+                    app.color_mode,
+                )
+            }
+            crate::visualization::ViewMode::PianoRoll => {
+                crate::visualization::draw_piano_roll(
+                    ui,
+                    &app.notes_with_timestamps,
+                    now,
+                    notes_response.rect,
+                    app.piano_roll_window,
+                    &app.selected_profile,
+                    app.naming,
+                );
+                if app.show_pitch_curve {
+                    crate::visualization::draw_pitch_curve(
+                        ui,
+                        &app.pitch_track,
+                        now,
+                        notes_response.rect,
+                        app.piano_roll_window,
+                        &app.selected_profile,
+                    );
+                }
+            }
+            crate::visualization::ViewMode::Keyboard => crate::visualization::draw_keyboard(
+                ui,
+                &app.notes_with_timestamps,
+                now,
+                app.settings.fade_duration(),
+                notes_response.rect,
+                &app.selected_profile,
+                app.naming,
+            ),
+            crate::visualization::ViewMode::Tuner => crate::visualization::draw_tuner(
+                ui,
+                app.tuner.reading(now),
+                notes_response.rect,
+                app.naming,
+            ),
+        }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run one frame with `key` pressed (if any): shortcuts first (as in `draw_ui`), then a
+    /// text field that holds focus when `focus_text` is set
+    fn frame(
+        app: &mut RecogNotesApp,
+        ctx: &egui::Context,
+        key: Option<egui::Key>,
+        focus_text: bool,
+    ) {
+        let input = egui::RawInput {
+            events: key
+                .map(|key| egui::Event::Key {
+                    key,
+                    physical_key: None,
+                    pressed: true,
+                    repeat: false,
+                    modifiers: egui::Modifiers::NONE,
+                })
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            handle_shortcuts(app, ctx);
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let field = ui.text_edit_singleline(&mut app.backend_url);
+                if focus_text {
+                    field.request_focus();
+                } else {
+                    field.surrender_focus();
+                }
+            });
+        });
+    }
+
+    #[test]
+    fn test_space_toggles_recording() {
+        let mut app = RecogNotesApp::default();
+        let ctx = egui::Context::default();
+
+        // Stop via Space (starting needs a real input device)
+        app.recording = true;
+        frame(&mut app, &ctx, Some(egui::Key::Space), false);
+        assert!(!app.recording);
+
+        frame(&mut app, &ctx, Some(egui::Key::Num5), false);
+        assert_eq!(app.selected_profile, "baritone");
+
+        // While the backend URL field has focus, keys are typing, not shortcuts
+        app.recording = true;
+        frame(&mut app, &ctx, None, true);
+        frame(&mut app, &ctx, Some(egui::Key::Num0), true);
+        frame(&mut app, &ctx, Some(egui::Key::Space), true);
+        assert!(app.recording);
+        assert_eq!(app.selected_profile, "baritone");
+    }
+}
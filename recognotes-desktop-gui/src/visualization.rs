@@ -1,5 +1,10 @@
+use crate::naming::NamingScheme;
+use crate::pitch_track::{PitchTrack, MAX_GAP};
+use crate::tuner::{TunerReading, IN_TUNE_CENTS};
 use crate::DetectedNote;
 use eframe::egui;
+use recognotes_core::utils::{cents_between, note_to_frequency};
+use recognotes_core::VoiceProfile;
 use std::time::Instant;
 
 /// All possible musical notes to display
@@ -11,50 +16,189 @@ const ALL_NOTES: &[&str] = &[
     "A#6", "B6", "C7", "C#7", "D7", "D#7", "E7", "F7", "F#7", "G7", "G#7", "A7", "A#7", "B7",
 ];
 
-/// Get the note range for a voice profile
-pub fn get_profile_range(profile: &str) -> Option<(usize, usize)> {
-    match profile {
-        "soprano" => Some((24, 48)), // C4-C6
-        "mezzo" => Some((21, 45)),   // A3-A5
-        "alto" => Some((17, 41)),    // F3-F5
-        "tenor" => Some((12, 36)),   // C3-C5
-        "baritone" => Some((9, 33)), // A2-A4
-        "bass" => Some((0, 24)),     // C2-C4
-        _ => None,
+/// What the main area shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewMode {
+    /// Bars for every note (default)
+    Spectrum,
+    /// Time-vs-pitch history of sustained notes
+    PianoRoll,
+    /// Piano keyboard with detected keys lit up
+    Keyboard,
+    /// Single target note with a cents needle
+    Tuner,
+}
+
+/// Background, track and label colors of the views for the active egui theme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// Panel background
+    pub background: egui::Color32,
+    /// Empty bar track
+    pub track: egui::Color32,
+    /// Empty bar track inside the voice profile's range
+    pub track_highlight: egui::Color32,
+    /// Piano roll rows for natural notes and sharps
+    pub row: egui::Color32,
+    pub row_sharp: egui::Color32,
+    /// Note labels, and labels inside the profile range
+    pub label: egui::Color32,
+    pub label_highlight: egui::Color32,
+    /// Placeholder and secondary text ("—" when nothing is detected)
+    pub muted: egui::Color32,
+    /// Readouts such as the tuner's Hz/cents line
+    pub text: egui::Color32,
+    /// Panel borders and the tuner scale
+    pub border: egui::Color32,
+    /// Grid lines and key outlines
+    pub grid: egui::Color32,
+    /// The tuner's in-tune zone
+    pub in_tune_zone: egui::Color32,
+    /// Level meter and sparkline background
+    pub meter: egui::Color32,
+}
+
+impl Palette {
+    pub const DARK: Self = Self {
+        background: egui::Color32::from_rgb(30, 30, 40),
+        track: egui::Color32::from_rgb(60, 60, 80),
+        track_highlight: egui::Color32::from_rgb(90, 90, 110),
+        row: egui::Color32::from_rgb(50, 50, 66),
+        row_sharp: egui::Color32::from_rgb(38, 38, 50),
+        label: egui::Color32::from_rgb(100, 100, 120),
+        label_highlight: egui::Color32::from_rgb(150, 150, 180),
+        muted: egui::Color32::from_rgb(80, 80, 100),
+        text: egui::Color32::LIGHT_GRAY,
+        border: egui::Color32::from_rgb(100, 100, 150),
+        grid: egui::Color32::from_rgb(60, 60, 80),
+        in_tune_zone: egui::Color32::from_rgb(40, 70, 40),
+        meter: egui::Color32::from_rgb(40, 40, 55),
+    };
+
+    pub const LIGHT: Self = Self {
+        background: egui::Color32::from_rgb(245, 245, 248),
+        track: egui::Color32::from_rgb(218, 218, 228),
+        track_highlight: egui::Color32::from_rgb(188, 188, 208),
+        row: egui::Color32::from_rgb(234, 234, 240),
+        row_sharp: egui::Color32::from_rgb(220, 220, 228),
+        label: egui::Color32::from_rgb(130, 130, 150),
+        label_highlight: egui::Color32::from_rgb(70, 70, 100),
+        muted: egui::Color32::from_rgb(165, 165, 180),
+        text: egui::Color32::DARK_GRAY,
+        border: egui::Color32::from_rgb(150, 150, 190),
+        grid: egui::Color32::from_rgb(205, 205, 215),
+        in_tune_zone: egui::Color32::from_rgb(200, 235, 200),
+        meter: egui::Color32::from_rgb(220, 220, 230),
+    };
+
+    /// Palette matching egui's current dark/light visuals
+    pub const fn for_visuals(visuals: &egui::Visuals) -> Self {
+        if visuals.dark_mode {
+            Self::DARK
+        } else {
+            Self::LIGHT
+        }
     }
 }
 
-/// Draw vertical bars for all notes with fade effect based on time
-#[allow(clippy::too_many_lines)]
-pub fn draw_vertical_bars_with_fade(
-    ui: &egui::Ui,
-    _detected_notes: &[DetectedNote],
-    notes_with_timestamps: &[(DetectedNote, Instant)],
-    rect: egui::Rect,
-    selected_profile: &str,
-) {
-    let painter = ui.painter();
+/// How the spectrum bars are colored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Green/yellow/blue by loudness (default)
+    #[default]
+    Intensity,
+    /// One hue per pitch class (C red, D orange, ...), brighter when louder
+    PitchClass,
+}
 
-    // Draw background
-    painter.rect_filled(rect, 0.0, egui::Color32::from_rgb(30, 30, 40));
+impl ColorMode {
+    pub const ALL: [Self; 2] = [Self::Intensity, Self::PitchClass];
 
-    // Get profile range for highlighting
-    let profile_range = get_profile_range(selected_profile);
+    /// Name shown in the color mode selector
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Intensity => "Color by loudness",
+            Self::PitchClass => "Color by pitch class",
+        }
+    }
+
+    /// Bar color for `note` at `intensity` (0.0-1.0)
+    fn bar_color(self, note: &str, intensity: f32) -> egui::Color32 {
+        match self {
+            Self::Intensity => intensity_to_color(intensity),
+            Self::PitchClass => {
+                let mut hsva = egui::epaint::HsvaGamma::from(pitch_class_color(note));
+                hsva.v *= 0.4 + 0.6 * intensity.clamp(0.0, 1.0);
+                hsva.into()
+            }
+        }
+    }
+}
+
+/// Index of the `ALL_NOTES` entry nearest to `frequency`, clamped to the displayed range
+fn note_index_for_frequency(frequency: f32) -> usize {
+    let lowest = note_to_frequency(ALL_NOTES[0]);
+    let semitones = (cents_between(frequency, lowest) / 100.0).round();
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let index = semitones.max(0.0) as usize;
+    index.min(ALL_NOTES.len() - 1)
+}
 
-    let now = Instant::now();
-    let fade_duration = std::time::Duration::from_millis(600);
+/// Get the note range for a voice profile, as indices into `ALL_NOTES`
+/// Derived from the backend's `VoiceProfile::freq_range`, so the highlight can't drift from
+/// what the analyzer actually filters on
+pub fn get_profile_range(profile: &str) -> Option<(usize, usize)> {
+    VoiceProfile::from_str(profile)
+        .freq_range()
+        .map(|(min_hz, max_hz)| {
+            (
+                note_index_for_frequency(min_hz),
+                note_index_for_frequency(max_hz),
+            )
+        })
+}
 
-    // Create a map of notes with their max intensity and fade factor
+/// Range of a voice profile for the selector, e.g. "C4-C6 (262-1047 Hz)"; None for no profile
+pub fn profile_summary(profile: &str, naming: NamingScheme) -> Option<String> {
+    let (min_hz, max_hz) = VoiceProfile::from_str(profile).freq_range()?;
+    let (low, high) = get_profile_range(profile)?;
+    Some(format!(
+        "{}-{} ({min_hz:.0}-{max_hz:.0} Hz)",
+        naming.display(ALL_NOTES[low]),
+        naming.display(ALL_NOTES[high])
+    ))
+}
+
+/// Indices into `ALL_NOTES` that get a bar: the profile's range, or every note
+pub fn visible_note_range(profile: &str) -> std::ops::RangeInclusive<usize> {
+    get_profile_range(profile).map_or(0..=ALL_NOTES.len() - 1, |(low, high)| {
+        low..=high.min(ALL_NOTES.len() - 1)
+    })
+}
+
+/// Label size that fits a name like "Sol#4" under a bar `bar_width` wide
+/// (tiny under 72 narrow bars, larger when a profile zooms in)
+fn label_font_size(bar_width: f32) -> f32 {
+    (bar_width / 2.4).clamp(7.0, 14.0)
+}
+
+/// Max intensity and fade factor (1.0 = just detected, 0.0 = faded out) per note name
+/// Notes older than `fade` are left out
+fn faded_note_levels(
+    notes_with_timestamps: &[(DetectedNote, Instant)],
+    now: Instant,
+    fade: std::time::Duration,
+) -> std::collections::HashMap<String, (f32, f32)> {
     let mut note_map: std::collections::HashMap<String, (f32, f32)> =
         std::collections::HashMap::new();
 
     for (note, timestamp) in notes_with_timestamps {
         let elapsed = now.saturating_duration_since(*timestamp);
-        let fade_alpha = if elapsed < fade_duration {
-            1.0 - (elapsed.as_secs_f32() / fade_duration.as_secs_f32())
-        } else {
-            0.0
-        };
+        // The history also feeds the piano roll, so it outlives the fade
+        if elapsed >= fade {
+            continue;
+        }
+        let fade_alpha = 1.0 - (elapsed.as_secs_f32() / fade.as_secs_f32());
 
         // Store max intensity and max fade_alpha for this note
         note_map
@@ -69,54 +213,57 @@ pub fn draw_vertical_bars_with_fade(
             })
             .or_insert((note.intensity, fade_alpha));
     }
+    note_map
+}
 
-    let num_notes = ALL_NOTES.len();
-    #[allow(clippy::cast_precision_loss)]
-    let bar_width = (rect.width() - 10.0) / num_notes as f32;
-    let padding_left = 5.0;
-    let padding_bottom = 30.0;
-    let max_bar_height = rect.height() - padding_bottom - 5.0;
+/// Draw vertical bars for all notes with fade effect based on time (faded over `fade` as of `now`)
+#[allow(clippy::too_many_lines, clippy::too_many_arguments)]
+pub fn draw_vertical_bars_with_fade(
+    ui: &egui::Ui,
+    notes_with_timestamps: &[(DetectedNote, Instant)],
+    now: Instant,
+    fade: std::time::Duration,
+    rect: egui::Rect,
+    selected_profile: &str,
+    naming: NamingScheme,
+    color_mode: ColorMode,
+) {
+    // A profile zooms the bars to its range; otherwise every note gets a bar
+    let in_profile_range = get_profile_range(selected_profile).is_some();
+    let visible = &ALL_NOTES[visible_note_range(selected_profile)];
 
-    // Draw each note bar
-    for (idx, &note_name) in ALL_NOTES.iter().enumerate() {
-        #[allow(clippy::cast_precision_loss, clippy::suboptimal_flops)]
-        let x = (idx as f32).mul_add(bar_width, rect.min.x + padding_left);
+    // A window squeezed this small has no room for bars (and would lay them out inside-out)
+    let Some(layout) = BarLayout::new(rect, visible.len()) else {
+        return;
+    };
+
+    let painter = ui.painter();
+    let palette = Palette::for_visuals(ui.visuals());
+
+    // Draw background
+    painter.rect_filled(rect, 0.0, palette.background);
 
-        // Check if this note is within the selected profile range
-        let in_profile_range = profile_range.is_some_and(|(start, end)| idx >= start && idx <= end);
+    let note_map = faded_note_levels(notes_with_timestamps, now, fade);
+    let font_size = label_font_size(layout.bar_width);
 
+    // Draw each note bar
+    for (idx, &note_name) in visible.iter().enumerate() {
         // Draw background track (empty bar) with different color if in profile range
         let bg_color = if in_profile_range {
-            egui::Color32::from_rgb(90, 90, 110) // Slightly brighter for profile range
+            palette.track_highlight // Slightly brighter for profile range
         } else {
-            egui::Color32::from_rgb(60, 60, 80) // Normal background
+            palette.track // Normal background
         };
 
-        painter.rect_filled(
-            egui::Rect::from_min_max(
-                egui::pos2(x + 1.0, rect.max.y - padding_bottom),
-                egui::pos2(x + bar_width - 1.0, rect.max.y - 25.0),
-            ),
-            1.0,
-            bg_color,
-        );
+        painter.rect_filled(layout.track_rect(idx), 1.0, bg_color);
 
         // Draw filled bar if note detected
         if let Some((intensity, fade_alpha)) = note_map.get(note_name) {
-            let bar_height = max_bar_height * intensity;
-            let base_color = intensity_to_color(*intensity);
+            let base_color = color_mode.bar_color(note_name, *intensity);
             let faded_color = apply_fade_to_color(base_color, *fade_alpha);
+            let bar_rect = layout.bar_rect(idx, *intensity);
 
-            let bar_top = rect.max.y - padding_bottom - bar_height;
-
-            painter.rect_filled(
-                egui::Rect::from_min_max(
-                    egui::pos2(x + 1.0, bar_top),
-                    egui::pos2(x + bar_width - 1.0, rect.max.y - 25.0),
-                ),
-                1.0,
-                faded_color,
-            );
+            painter.rect_filled(bar_rect, 1.0, faded_color);
 
             // Draw border with fade - thicker/brighter if in profile range
             let border_color = if in_profile_range {
@@ -127,45 +274,642 @@ pub fn draw_vertical_bars_with_fade(
 
             let border_width = if in_profile_range { 2.0 } else { 1.0 };
 
-            painter.rect_stroke(
-                egui::Rect::from_min_max(
-                    egui::pos2(x + 1.0, bar_top),
-                    egui::pos2(x + bar_width - 1.0, rect.max.y - 25.0),
-                ),
-                0.0,
-                egui::Stroke::new(border_width, border_color),
-            );
+            painter.rect_stroke(bar_rect, 0.0, egui::Stroke::new(border_width, border_color));
         }
 
         // Draw note label at bottom
-        let font_size = if num_notes > 48 { 7.0 } else { 9.0 };
         let label_color = if let Some((intensity, _)) = note_map.get(note_name) {
             if *intensity > 0.3 {
-                intensity_to_color(*intensity)
+                color_mode.bar_color(note_name, *intensity)
             } else {
-                egui::Color32::from_rgb(100, 100, 120)
+                palette.label
             }
         } else if in_profile_range {
-            egui::Color32::from_rgb(150, 150, 180) // Highlight profile range notes
+            palette.label_highlight // Highlight profile range notes
         } else {
-            egui::Color32::from_rgb(100, 100, 120)
+            palette.label
         };
 
         painter.text(
-            egui::pos2(x + bar_width / 2.0, rect.max.y - 10.0),
+            layout.label_pos(idx),
             egui::Align2::CENTER_CENTER,
-            note_name,
+            naming.display(note_name),
             egui::FontId::monospace(font_size),
             label_color,
         );
     }
 
     // Draw border
-    painter.rect_stroke(
-        rect,
-        0.0,
-        egui::Stroke::new(1.5, egui::Color32::from_rgb(100, 100, 150)),
+    painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.5, palette.border));
+}
+
+/// Geometry of the vertical bar chart: one bar per note, labels along the bottom
+struct BarLayout {
+    rect: egui::Rect,
+    bar_width: f32,
+    max_bar_height: f32,
+}
+
+impl BarLayout {
+    /// Horizontal margin on each side of the bars
+    const PADDING_X: f32 = 5.0;
+    /// Space under the bars: the empty track strip and the note labels
+    const PADDING_BOTTOM: f32 = 30.0;
+    const PADDING_TOP: f32 = 5.0;
+    /// Smallest chart worth drawing; below this the padding alone doesn't fit
+    const MIN_WIDTH: f32 = 2.0 * Self::PADDING_X + 1.0;
+    const MIN_HEIGHT: f32 = Self::PADDING_BOTTOM + Self::PADDING_TOP + 1.0;
+
+    /// `None` when `rect` is too small (or not finite) to hold `count` bars
+    fn new(rect: egui::Rect, count: usize) -> Option<Self> {
+        // Written so a NaN size fails the check too
+        let fits = rect.width() >= Self::MIN_WIDTH && rect.height() >= Self::MIN_HEIGHT;
+        if !fits || !rect.is_finite() || count == 0 {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let bar_width = (rect.width() - 2.0 * Self::PADDING_X) / count as f32;
+        Some(Self {
+            rect,
+            bar_width,
+            max_bar_height: rect.height() - Self::PADDING_BOTTOM - Self::PADDING_TOP,
+        })
+    }
+
+    /// Left and right edge of bar `idx`, leaving a gap of up to 1px on each side
+    /// The gap shrinks with the bar, so very narrow bars never turn inside-out
+    #[allow(clippy::cast_precision_loss)]
+    fn bar_span(&self, idx: usize) -> (f32, f32) {
+        let x = (idx as f32).mul_add(self.bar_width, self.rect.min.x + Self::PADDING_X);
+        let gap = (self.bar_width / 4.0).min(1.0);
+        (x + gap, x + self.bar_width - gap)
+    }
+
+    /// Empty track strip under bar `idx`
+    fn track_rect(&self, idx: usize) -> egui::Rect {
+        let (left, right) = self.bar_span(idx);
+        egui::Rect::from_min_max(
+            egui::pos2(left, self.rect.max.y - Self::PADDING_BOTTOM),
+            egui::pos2(right, self.rect.max.y - 25.0),
+        )
+    }
+
+    /// Bar `idx` filled to `intensity` (clamped to 0.0-1.0, NaN draws nothing)
+    fn bar_rect(&self, idx: usize, intensity: f32) -> egui::Rect {
+        let intensity = if intensity.is_nan() {
+            0.0
+        } else {
+            intensity.clamp(0.0, 1.0)
+        };
+        let (left, right) = self.bar_span(idx);
+        egui::Rect::from_min_max(
+            egui::pos2(
+                left,
+                self.rect.max.y - Self::PADDING_BOTTOM - self.max_bar_height * intensity,
+            ),
+            egui::pos2(right, self.rect.max.y - 25.0),
+        )
+    }
+
+    /// Center of the note label under bar `idx`
+    fn label_pos(&self, idx: usize) -> egui::Pos2 {
+        let (left, right) = self.bar_span(idx);
+        egui::pos2((left + right) / 2.0, self.rect.max.y - 10.0)
+    }
+}
+
+/// Position of one key on the drawn keyboard
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyRect {
+    /// Index into `ALL_NOTES`
+    pub note_idx: usize,
+    pub rect: egui::Rect,
+    pub is_black: bool,
+}
+
+/// Lay out keys `low..=high` (indices into `ALL_NOTES`) across `rect`
+/// White keys share the width evenly; black keys are narrower and shorter, straddling the
+/// boundary between their neighbours. White keys come first so black keys draw on top
+pub fn keyboard_layout(low: usize, high: usize, rect: egui::Rect) -> Vec<KeyRect> {
+    let notes = &ALL_NOTES[low..=high.min(ALL_NOTES.len() - 1)];
+    let white_count = notes.iter().filter(|n| !n.contains('#')).count().max(1);
+    #[allow(clippy::cast_precision_loss)]
+    let white_width = rect.width() / white_count as f32;
+    let black_width = white_width * 0.6;
+    let black_height = rect.height() * 0.6;
+
+    let mut whites = Vec::new();
+    let mut blacks = Vec::new();
+    let mut white_x = rect.min.x;
+    for (offset, note_name) in notes.iter().enumerate() {
+        let note_idx = low + offset;
+        if note_name.contains('#') {
+            blacks.push(KeyRect {
+                note_idx,
+                rect: egui::Rect::from_min_size(
+                    egui::pos2(white_x - black_width / 2.0, rect.min.y),
+                    egui::vec2(black_width, black_height),
+                ),
+                is_black: true,
+            });
+        } else {
+            whites.push(KeyRect {
+                note_idx,
+                rect: egui::Rect::from_min_size(
+                    egui::pos2(white_x, rect.min.y),
+                    egui::vec2(white_width, rect.height()),
+                ),
+                is_black: false,
+            });
+            white_x += white_width;
+        }
+    }
+    whites.extend(blacks);
+    whites
+}
+
+/// Draw a piano keyboard over the profile's range (or every note) and light up detected keys
+/// Uses the same intensity colors and fade-out as `draw_vertical_bars_with_fade`
+pub fn draw_keyboard(
+    ui: &egui::Ui,
+    notes_with_timestamps: &[(DetectedNote, Instant)],
+    now: Instant,
+    fade: std::time::Duration,
+    rect: egui::Rect,
+    selected_profile: &str,
+    naming: NamingScheme,
+) {
+    let painter = ui.painter();
+    let palette = Palette::for_visuals(ui.visuals());
+    painter.rect_filled(rect, 0.0, palette.background);
+
+    let note_map = faded_note_levels(notes_with_timestamps, now, fade);
+    let (low, high) = get_profile_range(selected_profile).unwrap_or((0, ALL_NOTES.len() - 1));
+    // Keep a sensible key shape on tall windows
+    let height = rect.height().min(rect.width() / 4.0);
+    let keys_rect = egui::Rect::from_center_size(rect.center(), egui::vec2(rect.width(), height));
+
+    for key in keyboard_layout(low, high, keys_rect) {
+        let note_name = ALL_NOTES[key.note_idx];
+        let base = if key.is_black {
+            egui::Color32::from_rgb(20, 20, 25)
+        } else {
+            egui::Color32::from_rgb(235, 235, 240)
+        };
+        painter.rect_filled(key.rect, 2.0, base);
+
+        if let Some((intensity, fade_alpha)) = note_map.get(note_name) {
+            let lit = apply_fade_to_color(intensity_to_color(*intensity), *fade_alpha);
+            painter.rect_filled(key.rect.shrink(1.0), 2.0, lit);
+        }
+        painter.rect_stroke(key.rect, 2.0, egui::Stroke::new(1.0, palette.grid));
+
+        if !key.is_black && note_name.starts_with('C') {
+            painter.text(
+                egui::pos2(key.rect.center().x, key.rect.max.y - 10.0),
+                egui::Align2::CENTER_CENTER,
+                naming.display(note_name),
+                egui::FontId::monospace(9.0),
+                egui::Color32::from_rgb(80, 80, 100),
+            );
+        }
+    }
+}
+
+/// One sustained note on the piano roll, as ages (seconds before now) of its ends
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollSegment {
+    /// Index into the displayed note range (`ALL_NOTES`)
+    pub note_idx: usize,
+    pub start_age: f32,
+    pub end_age: f32,
+    /// Strongest intensity seen while the note was held
+    pub intensity: f32,
+}
+
+/// Detections of the same note closer together than this are drawn as one segment
+const ROLL_MERGE_GAP: f32 = 0.15;
+/// Shortest segment drawn, so a single detection is still visible
+const ROLL_MIN_SEGMENT: f32 = 0.05;
+
+/// Merge timestamped detections into per-note segments, keeping those still inside `window`
+/// Segments that started before the window are kept; drawing clips them at the left edge
+pub fn piano_roll_segments(
+    notes_with_timestamps: &[(DetectedNote, Instant)],
+    now: Instant,
+    window: std::time::Duration,
+) -> Vec<RollSegment> {
+    let window = window.as_secs_f32();
+    let mut detections: Vec<(usize, f32, f32)> = notes_with_timestamps
+        .iter()
+        .filter_map(|(note, timestamp)| {
+            let note_idx = ALL_NOTES.iter().position(|n| *n == note.note)?;
+            let age = now.saturating_duration_since(*timestamp).as_secs_f32();
+            Some((note_idx, age, note.intensity))
+        })
+        .collect();
+    // Oldest first within each note
+    detections.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)));
+
+    let mut segments: Vec<RollSegment> = Vec::new();
+    for (note_idx, age, intensity) in detections {
+        match segments.last_mut() {
+            Some(segment)
+                if segment.note_idx == note_idx && segment.end_age - age <= ROLL_MERGE_GAP =>
+            {
+                segment.end_age = age;
+                segment.intensity = segment.intensity.max(intensity);
+            }
+            _ => segments.push(RollSegment {
+                note_idx,
+                start_age: age,
+                end_age: age,
+                intensity,
+            }),
+        }
+    }
+
+    for segment in &mut segments {
+        segment.end_age = segment
+            .end_age
+            .min(segment.start_age - ROLL_MIN_SEGMENT)
+            .max(0.0);
+    }
+    segments.retain(|segment| segment.end_age < window);
+    segments
+}
+
+/// Geometry shared by the piano roll and the pitch curve drawn over it
+struct RollLayout {
+    rect: egui::Rect,
+    /// Note range shown, as indices into `ALL_NOTES`
+    low: usize,
+    high: usize,
+    row_height: f32,
+    roll_left: f32,
+    roll_width: f32,
+    window_secs: f32,
+}
+
+impl RollLayout {
+    /// Width of the note label column
+    const LABEL_WIDTH: f32 = 36.0;
+
+    fn new(rect: egui::Rect, window: std::time::Duration, selected_profile: &str) -> Self {
+        let (low, high) = get_profile_range(selected_profile).unwrap_or((0, ALL_NOTES.len() - 1));
+        #[allow(clippy::cast_precision_loss)]
+        let row_height = rect.height() / (high - low + 1) as f32;
+        Self {
+            rect,
+            low,
+            high,
+            row_height,
+            roll_left: rect.min.x + Self::LABEL_WIDTH,
+            roll_width: rect.width() - Self::LABEL_WIDTH,
+            window_secs: window.as_secs_f32(),
+        }
+    }
+
+    fn age_to_x(&self, age: f32) -> f32 {
+        self.rect.max.x - age / self.window_secs * self.roll_width
+    }
+
+    /// Top of a note's row (highest note at the top)
+    #[allow(clippy::cast_precision_loss)]
+    fn row_top(&self, idx: usize) -> f32 {
+        self.row_center(idx as f32) - self.row_height / 2.0
+    }
+
+    /// Vertical center of a (fractional) note index, so off-pitch frequencies land between rows
+    #[allow(clippy::cast_precision_loss)]
+    fn row_center(&self, idx: f32) -> f32 {
+        self.rect.min.y + (self.high as f32 - idx + 0.5) * self.row_height
+    }
+}
+
+/// Draw a scrolling piano roll: time on x (now at the right edge), pitch on y
+/// Rows cover the voice profile's range (or every note), colored by `intensity_to_color`
+pub fn draw_piano_roll(
+    ui: &egui::Ui,
+    notes_with_timestamps: &[(DetectedNote, Instant)],
+    now: Instant,
+    rect: egui::Rect,
+    window: std::time::Duration,
+    selected_profile: &str,
+    naming: NamingScheme,
+) {
+    let painter = ui.painter().with_clip_rect(rect);
+    let palette = Palette::for_visuals(ui.visuals());
+    painter.rect_filled(rect, 0.0, palette.background);
+
+    let layout = RollLayout::new(rect, window, selected_profile);
+    let RollLayout {
+        low,
+        high,
+        row_height,
+        roll_left,
+        roll_width,
+        window_secs,
+        ..
+    } = layout;
+    let age_to_x = |age: f32| layout.age_to_x(age);
+    let row_top = |idx: usize| layout.row_top(idx);
+
+    // Rows: sharps darker like black keys, labels on every C
+    for (idx, &note_name) in ALL_NOTES.iter().enumerate().take(high + 1).skip(low) {
+        let row = egui::Rect::from_min_size(
+            egui::pos2(roll_left, row_top(idx)),
+            egui::vec2(roll_width, row_height),
+        );
+        let row_color = if note_name.contains('#') {
+            palette.row_sharp
+        } else {
+            palette.row
+        };
+        painter.rect_filled(row.shrink2(egui::vec2(0.0, 0.5)), 0.0, row_color);
+
+        if note_name.starts_with('C') && !note_name.contains('#') {
+            painter.text(
+                egui::pos2(rect.min.x + 4.0, row.center().y),
+                egui::Align2::LEFT_CENTER,
+                naming.display(note_name),
+                egui::FontId::monospace(10.0),
+                palette.label_highlight,
+            );
+        }
+    }
+
+    // One-second grid lines
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    for second in 1..=(window_secs as u32) {
+        #[allow(clippy::cast_precision_loss)]
+        let x = age_to_x(second as f32);
+        painter.line_segment(
+            [egui::pos2(x, rect.min.y), egui::pos2(x, rect.max.y)],
+            egui::Stroke::new(1.0, palette.grid),
+        );
+    }
+
+    let segments = piano_roll_segments(notes_with_timestamps, now, window);
+    for segment in segments
+        .iter()
+        .filter(|s| (low..=high).contains(&s.note_idx))
+    {
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(
+                age_to_x(segment.start_age).max(roll_left),
+                row_top(segment.note_idx) + 1.0,
+            ),
+            egui::pos2(
+                age_to_x(segment.end_age),
+                row_top(segment.note_idx) + row_height - 1.0,
+            ),
+        );
+        painter.rect_filled(bar, 2.0, intensity_to_color(segment.intensity));
+    }
+
+    painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.5, palette.border));
+}
+
+/// Overlay the smoothed pitch (see `PitchTrack`) on a piano roll drawn with the same
+/// `rect`, `window` and profile, as a continuous line between the rows
+/// The line breaks where the track paused for longer than `pitch_track::MAX_GAP`
+pub fn draw_pitch_curve(
+    ui: &egui::Ui,
+    track: &PitchTrack,
+    now: Instant,
+    rect: egui::Rect,
+    window: std::time::Duration,
+    selected_profile: &str,
+) {
+    let layout = RollLayout::new(rect, window, selected_profile);
+    let painter = ui.painter().with_clip_rect(egui::Rect::from_min_max(
+        egui::pos2(layout.roll_left, rect.min.y),
+        rect.max,
+    ));
+    let stroke = egui::Stroke::new(2.0, Palette::for_visuals(ui.visuals()).text);
+    let lowest = note_to_frequency(ALL_NOTES[0]);
+
+    let mut line: Vec<egui::Pos2> = Vec::new();
+    let mut previous: Option<Instant> = None;
+    for &(time, frequency) in track.points() {
+        if previous.is_some_and(|prev| time.saturating_duration_since(prev) > MAX_GAP) {
+            painter.add(egui::Shape::line(std::mem::take(&mut line), stroke));
+        }
+        previous = Some(time);
+        let age = now.saturating_duration_since(time).as_secs_f32();
+        let idx = cents_between(frequency, lowest) / 100.0;
+        line.push(egui::pos2(layout.age_to_x(age), layout.row_center(idx)));
+    }
+    painter.add(egui::Shape::line(line, stroke));
+}
+
+/// Bottom of the level meter scale in dBFS
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// Map a normalized level (0.0-1.0 of full scale) onto the meter's 0.0-1.0 dB scale
+fn meter_fraction(level: f32) -> f32 {
+    let db = 20.0 * level.max(1e-6).log10();
+    ((db - METER_FLOOR_DB) / -METER_FLOOR_DB).clamp(0.0, 1.0)
+}
+
+/// Draw a compact horizontal VU meter: RMS bar (green -> yellow -> red) plus a peak tick
+/// Shows a "CLIPPING" warning when `clipping` is set, or "silent" when there's no signal
+pub fn draw_level_meter(ui: &mut egui::Ui, (peak, rms): (f32, f32), clipping: bool) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(140.0, 12.0), egui::Sense::hover());
+    let painter = ui.painter();
+    painter.rect_filled(rect, 2.0, Palette::for_visuals(ui.visuals()).meter);
+
+    let fraction = meter_fraction(rms);
+    let color = if fraction > 0.9 {
+        egui::Color32::from_rgb(255, 90, 90) // Red
+    } else if fraction > 0.75 {
+        egui::Color32::from_rgb(255, 200, 0) // Yellow
+    } else {
+        egui::Color32::GREEN
+    };
+    let mut level = rect;
+    level.set_width(rect.width() * fraction);
+    painter.rect_filled(level, 2.0, color);
+
+    let peak_x = rect.min.x + rect.width() * meter_fraction(peak);
+    painter.line_segment(
+        [
+            egui::pos2(peak_x, rect.min.y),
+            egui::pos2(peak_x, rect.max.y),
+        ],
+        egui::Stroke::new(2.0, ui.visuals().strong_text_color()),
+    );
+
+    if clipping {
+        ui.colored_label(egui::Color32::RED, "⚠ CLIPPING")
+            .on_hover_text(
+                "The input is hitting full scale, which distorts detection: lower the gain",
+            );
+    } else if fraction == 0.0 {
+        ui.colored_label(egui::Color32::GRAY, "silent");
+    }
+}
+
+/// Draw a compact sparkline of the strongest note's confidence over the last `window`
+/// (0% at the bottom, 100% at the top, newest sample on the right edge)
+/// A steady voice reads as a flat, high line; a wobbling pitch as jitter
+pub fn draw_confidence_sparkline(
+    ui: &mut egui::Ui,
+    history: &std::collections::VecDeque<(Instant, f32)>,
+    window: std::time::Duration,
+    now: Instant,
+) {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(120.0, 18.0), egui::Sense::hover());
+    let painter = ui.painter();
+    painter.rect_filled(rect, 2.0, Palette::for_visuals(ui.visuals()).meter);
+
+    let window_secs = window.as_secs_f32().max(f32::EPSILON);
+    let points: Vec<egui::Pos2> = history
+        .iter()
+        .map(|(timestamp, confidence)| {
+            let age = now.duration_since(*timestamp).as_secs_f32();
+            egui::pos2(
+                rect.max.x - rect.width() * (age / window_secs).min(1.0),
+                rect.max.y - rect.height() * confidence.clamp(0.0, 1.0),
+            )
+        })
+        .collect();
+    if points.len() > 1 {
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(1.5, egui::Color32::from_rgb(100, 200, 255)),
+        ));
+    }
+
+    if let Some((_, latest)) = history.back() {
+        response.on_hover_text(format!(
+            "Confidence of the strongest note: {:.0}%",
+            latest * 100.0
+        ));
+    }
+}
+
+/// Text readout of a note: name, measured frequency and cents off, e.g. "A4  440.0 Hz  +0c"
+pub fn note_readout(note: &DetectedNote, naming: NamingScheme) -> String {
+    format!(
+        "{}  {:.1} Hz  {:+.0}c",
+        naming.display(&note.note),
+        note.frequency,
+        note.cents_off
+    )
+}
+
+/// Draw the tuner: the locked note's name over a -50..+50 cent scale with a needle
+/// The needle turns green within `IN_TUNE_CENTS`
+pub fn draw_tuner(
+    ui: &egui::Ui,
+    reading: Option<&TunerReading>,
+    rect: egui::Rect,
+    naming: NamingScheme,
+) {
+    const RANGE_CENTS: f32 = 50.0;
+
+    let painter = ui.painter();
+    let palette = Palette::for_visuals(ui.visuals());
+    painter.rect_filled(rect, 0.0, palette.background);
+
+    let center = rect.center();
+    let half_width = (rect.width() * 0.4).min(400.0);
+    let scale_y = center.y + 40.0;
+    let cents_to_x =
+        |cents: f32| center.x + cents.clamp(-RANGE_CENTS, RANGE_CENTS) / RANGE_CENTS * half_width;
+
+    // Scale: in-tune zone, then ticks every 10 cents
+    let zone = egui::Rect::from_min_max(
+        egui::pos2(cents_to_x(-IN_TUNE_CENTS), scale_y - 30.0),
+        egui::pos2(cents_to_x(IN_TUNE_CENTS), scale_y + 30.0),
+    );
+    painter.rect_filled(zone, 2.0, palette.in_tune_zone);
+    painter.line_segment(
+        [
+            egui::pos2(center.x - half_width, scale_y),
+            egui::pos2(center.x + half_width, scale_y),
+        ],
+        egui::Stroke::new(1.5, palette.border),
+    );
+    for tick in (-50..=50).step_by(10) {
+        #[allow(clippy::cast_precision_loss)]
+        let x = cents_to_x(tick as f32);
+        let height = if tick == 0 { 20.0 } else { 10.0 };
+        painter.line_segment(
+            [
+                egui::pos2(x, scale_y - height),
+                egui::pos2(x, scale_y + height),
+            ],
+            egui::Stroke::new(1.0, palette.border),
+        );
+        painter.text(
+            egui::pos2(x, scale_y + 24.0),
+            egui::Align2::CENTER_TOP,
+            format!("{tick:+}"),
+            egui::FontId::proportional(11.0),
+            egui::Color32::GRAY,
+        );
+    }
+
+    let Some(reading) = reading else {
+        painter.text(
+            egui::pos2(center.x, scale_y - 80.0),
+            egui::Align2::CENTER_CENTER,
+            "—",
+            egui::FontId::proportional(64.0),
+            palette.muted,
+        );
+        return;
+    };
+
+    let color = if reading.in_tune() {
+        egui::Color32::GREEN
+    } else if reading.cents_off.abs() <= 20.0 {
+        egui::Color32::from_rgb(255, 200, 0) // Yellow
+    } else {
+        egui::Color32::from_rgb(255, 90, 90) // Red
+    };
+
+    painter.text(
+        egui::pos2(center.x, scale_y - 80.0),
+        egui::Align2::CENTER_CENTER,
+        naming.display(&reading.note),
+        egui::FontId::proportional(64.0),
+        color,
+    );
+    painter.text(
+        egui::pos2(center.x, scale_y + 50.0),
+        egui::Align2::CENTER_TOP,
+        format!("{:.1} Hz  {:+.0}c", reading.frequency, reading.cents_off),
+        egui::FontId::proportional(16.0),
+        palette.text,
     );
+
+    let needle_x = cents_to_x(reading.cents_off);
+    painter.line_segment(
+        [
+            egui::pos2(needle_x, scale_y - 35.0),
+            egui::pos2(needle_x, scale_y + 35.0),
+        ],
+        egui::Stroke::new(4.0, color),
+    );
+}
+
+/// Fixed color for a note's pitch class, going round the color wheel from C (red) through
+/// D (orange), E (yellow) ... to B (magenta); the same in every octave
+/// Names that can't be parsed are gray
+pub fn pitch_class_color(note: &str) -> egui::Color32 {
+    crate::export::note_to_midi(note).map_or(egui::Color32::GRAY, |midi| {
+        egui::epaint::HsvaGamma {
+            h: f32::from(midi % 12) / 12.0,
+            s: 1.0,
+            v: 1.0,
+            a: 1.0,
+        }
+        .into()
+    })
 }
 
 /// Convert intensity value to color (brighter = more intense)
@@ -318,3 +1062,200 @@ pub const fn draw_spectrum_visualization(ui: &egui::Ui, notes: &[DetectedNote])
     let _ = notes; // unused
     let _ = ui; // unused
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn detected(note: &str, intensity: f32) -> DetectedNote {
+        DetectedNote {
+            note: note.to_string(),
+            confidence: 0.9,
+            intensity,
+            ..DetectedNote::default()
+        }
+    }
+
+    #[test]
+    fn test_bar_layout_survives_tiny_rects() {
+        let tiny = egui::Rect::from_min_size(egui::pos2(10.0, 10.0), egui::vec2(1.0, 1.0));
+        assert!(BarLayout::new(tiny, ALL_NOTES.len()).is_none());
+        let nan = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(f32::NAN, 100.0));
+        assert!(BarLayout::new(nan, ALL_NOTES.len()).is_none());
+
+        // Just big enough: every bar is a sliver, but a well-formed one inside the chart
+        let rect = egui::Rect::from_min_size(
+            egui::pos2(10.0, 10.0),
+            egui::vec2(BarLayout::MIN_WIDTH, BarLayout::MIN_HEIGHT),
+        );
+        let layout = BarLayout::new(rect, ALL_NOTES.len()).unwrap();
+        for idx in 0..ALL_NOTES.len() {
+            for bar in [
+                layout.track_rect(idx),
+                layout.bar_rect(idx, 1.5),
+                layout.bar_rect(idx, f32::NAN),
+            ] {
+                assert!(bar.is_finite() && bar.is_positive(), "{bar:?}");
+                assert!(rect.contains_rect(bar), "{bar:?} outside {rect:?}");
+            }
+            assert!(rect.contains(layout.label_pos(idx)));
+        }
+    }
+
+    #[test]
+    fn test_fade_follows_the_chosen_duration() {
+        let start = Instant::now();
+        let now = start + Duration::from_millis(500);
+        let history = [(detected("A4", 0.8), start)];
+
+        // Half way through a 1 s fade, and long gone after a 300 ms one
+        let slow = faded_note_levels(&history, now, Duration::from_secs(1));
+        let (intensity, alpha) = slow["A4"];
+        assert!((intensity - 0.8).abs() < f32::EPSILON);
+        assert!((alpha - 0.5).abs() < 0.01, "{alpha}");
+        assert!(faded_note_levels(&history, now, Duration::from_millis(300)).is_empty());
+    }
+
+    #[test]
+    fn test_note_readout() {
+        let a4 = DetectedNote {
+            frequency: 440.0,
+            ..detected("A4", 0.8)
+        };
+        assert_eq!(
+            note_readout(&a4, NamingScheme::Scientific),
+            "A4  440.0 Hz  +0c"
+        );
+
+        let flat = DetectedNote {
+            frequency: 436.2,
+            cents_off: -15.2,
+            ..detected("A4", 0.8)
+        };
+        assert_eq!(
+            note_readout(&flat, NamingScheme::Solfege),
+            "La4  436.2 Hz  -15c"
+        );
+    }
+
+    #[test]
+    fn test_palette_follows_theme() {
+        assert_eq!(Palette::for_visuals(&egui::Visuals::dark()), Palette::DARK);
+        assert_eq!(
+            Palette::for_visuals(&egui::Visuals::light()),
+            Palette::LIGHT
+        );
+    }
+
+    #[test]
+    fn test_profile_range_matches_backend_filter() {
+        for &name in VoiceProfile::all_profiles() {
+            let profile = VoiceProfile::from_str(name);
+            let Some((min_hz, max_hz)) = profile.freq_range() else {
+                assert_eq!(get_profile_range(name), None);
+                continue;
+            };
+            let (low, high) = get_profile_range(name).unwrap();
+
+            // The highlighted ends are the notes the backend's bounds sit on
+            for (idx, hz) in [(low, min_hz), (high, max_hz)] {
+                let cents = cents_between(note_to_frequency(ALL_NOTES[idx]), hz);
+                assert!(
+                    cents.abs() < 1.0,
+                    "{name}: {} is {cents} cents off",
+                    ALL_NOTES[idx]
+                );
+            }
+        }
+        assert_eq!(get_profile_range("bass"), Some((0, 24)));
+        assert_eq!(
+            profile_summary("tenor", NamingScheme::Scientific).as_deref(),
+            Some("C3-C5 (131-523 Hz)")
+        );
+        assert_eq!(
+            profile_summary("no_profile", NamingScheme::Scientific),
+            None
+        );
+    }
+
+    #[test]
+    fn test_profile_zooms_bars_to_its_range() {
+        let all = visible_note_range("no_profile");
+        assert_eq!(all.clone().count(), ALL_NOTES.len());
+
+        // Tenor: C3-C5, two octaves plus the top C
+        let tenor = visible_note_range("tenor");
+        assert_eq!(ALL_NOTES[*tenor.start()], "C3");
+        assert_eq!(ALL_NOTES[*tenor.end()], "C5");
+        assert_eq!(tenor.count(), 25);
+
+        // Wider bars get bigger labels, within limits
+        assert!(label_font_size(1390.0 / 25.0) > label_font_size(1390.0 / 72.0));
+        assert!((label_font_size(2.0) - 7.0).abs() < f32::EPSILON);
+        assert!((label_font_size(500.0) - 14.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_pitch_class_colors_repeat_every_octave() {
+        assert_eq!(pitch_class_color("C4"), pitch_class_color("C2"));
+        assert_eq!(pitch_class_color("F#3"), pitch_class_color("F#6"));
+        assert_ne!(pitch_class_color("C4"), pitch_class_color("C#4"));
+        assert_eq!(pitch_class_color("??"), egui::Color32::GRAY);
+
+        // C is red
+        let c = pitch_class_color("C4");
+        assert!(c.r() > 200 && c.g() < 60 && c.b() < 60, "{c:?}");
+
+        // Louder bars of the same pitch class are brighter
+        let quiet = ColorMode::PitchClass.bar_color("A4", 0.1);
+        let loud = ColorMode::PitchClass.bar_color("A4", 1.0);
+        assert!(egui::epaint::HsvaGamma::from(loud).v > egui::epaint::HsvaGamma::from(quiet).v);
+        assert_eq!(loud, pitch_class_color("A4"));
+    }
+
+    #[test]
+    fn test_keyboard_layout_one_octave() {
+        let c4 = ALL_NOTES.iter().position(|n| *n == "C4").unwrap();
+        let rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(700.0, 100.0));
+        let keys = keyboard_layout(c4, c4 + 11, rect);
+
+        let (blacks, whites): (Vec<KeyRect>, Vec<KeyRect>) = keys.iter().partition(|k| k.is_black);
+        assert_eq!((whites.len(), blacks.len()), (7, 5));
+        assert!((whites[6].rect.max.x - 700.0).abs() < 1e-3);
+        // C#4 straddles the C4/D4 boundary and is drawn on top
+        let c_sharp = blacks[0];
+        assert_eq!(ALL_NOTES[c_sharp.note_idx], "C#4");
+        assert!(c_sharp.rect.contains(egui::pos2(100.0, 10.0)));
+        assert!(c_sharp.rect.width() < whites[0].rect.width());
+        assert!(!c_sharp.rect.contains(egui::pos2(100.0, 90.0)));
+    }
+
+    #[test]
+    fn test_piano_roll_merges_sustained_notes_and_scrolls() {
+        let now = Instant::now();
+        let ago = |ms| now.checked_sub(Duration::from_millis(ms)).unwrap();
+
+        // A4 held from 3s ago to 2s ago, re-struck 500ms ago; C4 long gone
+        let mut history: Vec<_> = (0..=50)
+            .map(|i| (detected("A4", 0.5), ago(3000 - i * 20)))
+            .collect();
+        history.push((detected("A4", 0.9), ago(500)));
+        history.push((detected("C4", 0.9), ago(9000)));
+
+        let segments = piano_roll_segments(&history, now, Duration::from_secs(8));
+        let a4 = ALL_NOTES.iter().position(|n| *n == "A4").unwrap();
+        assert_eq!(segments.len(), 2, "{segments:?}");
+        assert!(segments.iter().all(|s| s.note_idx == a4));
+        assert!((segments[0].start_age - 3.0).abs() < 0.01);
+        assert!((segments[0].end_age - 2.0).abs() < 0.01);
+        assert!((segments[1].intensity - 0.9).abs() < f32::EPSILON);
+
+        // Later on, the first segment has scrolled off the left edge
+        let later = now + Duration::from_secs(7);
+        assert_eq!(
+            piano_roll_segments(&history, later, Duration::from_secs(8)).len(),
+            1
+        );
+    }
+}
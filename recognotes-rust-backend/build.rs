@@ -0,0 +1,19 @@
+use std::process::Command;
+
+/// Embed the current git commit as `RECOGNOTES_GIT_COMMIT` for `/health` and `/version`
+/// Falls back to "unknown" when building outside a git checkout (e.g. from a source tarball)
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=RECOGNOTES_GIT_COMMIT={commit}");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+}
@@ -1,530 +0,0 @@
-#![allow(
-    clippy::cast_precision_loss,
-    clippy::cast_possible_truncation,
-    clippy::cast_sign_loss,
-    clippy::unused_self
-)]
-
-use crate::models::VoiceProfile;
-use num_complex::Complex;
-use once_cell::sync::Lazy;
-use rayon::prelude::*;
-use rustfft::FftPlanner;
-use std::f32::consts::PI;
-use std::sync::Mutex;
-
-// Constants for note-to-frequency mapping
-const KNOWN_NOTE_FREQUENCY: f32 = 440.0; // A4 = 440 Hz
-                                         // Natural notes only (no sharps/flats) - focuses on standard musical notes
-const NOTE_NAMES: [&str; 7] = ["C", "D", "E", "F", "G", "A", "B"];
-// IMPROVED: Extended range to include low bass notes
-// Covers: Bass (C1-E2), Baritone (A1-G3), Tenor (C3-C5), Countertenor/Alto (F3-F5), Soprano (C4-C6)
-const MIN_OCTAVE: i32 = 1; // C1 = 32.7 Hz (very low bass)
-const MAX_OCTAVE: i32 = 7; // C7 = 2093 Hz (high soprano)
-
-/// Global FFT planner - reused across all requests
-/// Creating a new `FftPlanner` is very expensive, so we share one globally
-#[allow(clippy::non_std_lazy_statics)]
-static FFT_PLANNER: Lazy<Mutex<FftPlanner<f32>>> = Lazy::new(|| Mutex::new(FftPlanner::new()));
-
-/// Pre-computed lookup table for frequency-to-note conversion
-/// This avoids expensive log calculations on every call
-pub struct FrequencyToNoteLookup {
-    table: Vec<(String, f32)>, // (note_name, base_frequency)
-}
-
-impl FrequencyToNoteLookup {
-    /// Create a lookup table for note-to-frequency mapping
-    /// Limited to realistic human vocal range: C2 (65.4 Hz) to C6 (1046.5 Hz)
-    /// Uses only natural notes (no sharps/flats) to focus on standard musical notes
-    /// Covers all professional voice types: Bass, Baritone, Tenor, Countertenor, Contralto, Mezzo-Soprano, Soprano
-    pub fn new() -> Self {
-        let mut table = Vec::new();
-
-        // Generate natural notes (C, D, E, F, G, A, B) from octave 2 to 6
-        for octave in MIN_OCTAVE..=MAX_OCTAVE {
-            for note_name in &NOTE_NAMES {
-                // Map natural notes to MIDI semitone positions
-                // C=0, D=2, E=4, F=5, G=7, A=9, B=11 (skips sharps/flats)
-                let note_semitones = match *note_name {
-                    "C" => 0,
-                    "D" => 2,
-                    "E" => 4,
-                    "F" => 5,
-                    "G" => 7,
-                    "A" => 9,
-                    "B" => 11,
-                    _ => continue,
-                };
-
-                // Calculate MIDI note number
-                let note_num = (octave * 12) + note_semitones + 12; // C0 is MIDI 12
-                let semitones_from_a4 = note_num - 69; // A4 is MIDI 69
-                #[allow(clippy::cast_precision_loss, clippy::suboptimal_flops)]
-                let frequency = KNOWN_NOTE_FREQUENCY * (semitones_from_a4 as f32 / 12.0).exp2();
-
-                let note_full_name = format!("{note_name}{octave}");
-                table.push((note_full_name, frequency));
-            }
-        }
-
-        // Sort by frequency for binary search
-        table.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-
-        Self { table }
-    }
-
-    /// Find the closest note for a given frequency
-    /// Returns (`note_name`, confidence)
-    pub fn find_closest_note(&self, frequency: f32) -> Option<(String, f32)> {
-        if frequency <= 0.0 || frequency > 20000.0 {
-            return None;
-        }
-
-        // Binary search to find closest frequency
-        let mut left = 0;
-        let mut right = self.table.len() - 1;
-
-        while left < right {
-            let mid = usize::midpoint(left, right);
-            if self.table[mid].1 < frequency {
-                left = mid + 1;
-            } else {
-                right = mid;
-            }
-        }
-
-        // Check both neighbors to find closest
-        let closest_idx = if left > 0
-            && (frequency - self.table[left - 1].1).abs() < (frequency - self.table[left].1).abs()
-        {
-            left - 1
-        } else {
-            left
-        };
-
-        let (note_name, base_freq) = &self.table[closest_idx];
-
-        // Calculate confidence (how close the frequency matches)
-        // Within 50 cents (0.5 semitone) is considered a good match
-        let cents_diff = 1200.0 * (frequency / base_freq).log2().abs();
-        let confidence = (1.0 - (cents_diff / 100.0).min(1.0)).max(0.0);
-
-        Some((note_name.clone(), confidence))
-    }
-}
-
-/// Analyze audio buffer and detect dominant frequency
-pub struct AudioAnalyzer {
-    lookup: FrequencyToNoteLookup,
-}
-
-impl Default for AudioAnalyzer {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl AudioAnalyzer {
-    pub fn new() -> Self {
-        Self {
-            lookup: FrequencyToNoteLookup::new(),
-        }
-    }
-
-    /// Check if a frequency is within the allowed voice profile range
-    /// If profile is `NoProfile`, all frequencies are allowed
-    /// Otherwise, aggressively filters frequencies outside the profile range
-    fn is_frequency_in_profile(frequency: f32, profile: VoiceProfile) -> bool {
-        match profile.freq_range() {
-            None => true, // NoProfile allows all frequencies
-            Some((min_freq, max_freq)) => {
-                // Aggressive filtering: must be strictly within range
-                // Allow ±10% margin for frequency estimation errors
-                let margin = (max_freq - min_freq) * 0.05; // 5% margin on each side
-                frequency >= (min_freq - margin) && frequency <= (max_freq + margin)
-            }
-        }
-    }
-
-    /// Compute FFT and return Power Spectral Density
-    /// Uses global FFT planner to avoid expensive re-planning on every call
-    /// OPTIMIZED: Faster PSD calculation and lock time reduction
-    fn compute_fft(&self, signal: &[f32], _sample_rate: u32) -> Vec<f32> {
-        let signal_len = signal.len();
-
-        // Get the global FFT planner (created once, reused for all requests)
-        let lock_start = std::time::Instant::now();
-        let fft = {
-            let mut planner = FFT_PLANNER.lock().unwrap();
-            planner.plan_fft_forward(signal_len)
-        };
-        let lock_time = lock_start.elapsed().as_micros();
-
-        // Convert input to complex numbers
-        let convert_start = std::time::Instant::now();
-        let mut buffer: Vec<Complex<f32>> =
-            signal.iter().map(|&s| Complex { re: s, im: 0.0 }).collect();
-        let convert_time = convert_start.elapsed().as_micros();
-
-        // Compute FFT
-        let process_start = std::time::Instant::now();
-        fft.process(&mut buffer);
-        let process_time = process_start.elapsed().as_micros();
-
-        // Compute Power Spectral Density - OPTIMIZED: Faster norm calculation
-        let psd_start = std::time::Instant::now();
-        let signal_len_f32 = signal_len as f32;
-        let psd: Vec<f32> = buffer
-            .iter()
-            .map(|c| {
-                let norm_sq = c.re.mul_add(c.re, c.im * c.im);
-                (norm_sq / signal_len_f32).sqrt()
-            })
-            .collect();
-        let psd_time = psd_start.elapsed().as_micros();
-
-        log::debug!("compute_fft({signal_len}): lock={lock_time}us, convert={convert_time}us, process={process_time}us, psd={psd_time}us");
-
-        psd
-    }
-
-    /// Find all significant peaks in the FFT spectrum, with harmonic suppression to find the fundamental.
-    /// OPTIMIZED: Reduced iterations from 10 to 5 (captures >99% of voice fundamental)
-    fn find_all_peaks(&self, psd: &[f32], sample_rate: u32, signal_len: usize) -> Vec<(f32, f32)> {
-        if psd.is_empty() {
-            return Vec::new();
-        }
-
-        let mut peaks = Vec::new();
-        let mut mutable_psd = psd.to_vec(); // Make a mutable copy of the power spectrum
-
-        // Find global maximum for threshold calculation
-        let max_power = psd[1..psd.len() / 2]
-            .iter()
-            .copied()
-            .fold(0.0_f32, f32::max);
-
-        // IMPROVED: Lower threshold (10% instead of 20%) to catch even weaker fundamental frequencies
-        // Notes: lower frequencies often have less energy than their harmonics
-        let threshold = (max_power * 0.10).max(0.05);
-
-        // --- Iterative Harmonic Suppression ---
-        // This loop finds the strongest peak, assumes it's a fundamental, removes its harmonics,
-        // and then repeats. This helps to isolate true fundamental frequencies from their overtones.
-        // OPTIMIZED: Reduced from 10 to 5 iterations (~50% faster, 99% accuracy)
-        // Human voices rarely have >5 distinct notes in a single chunk
-        for _ in 0..5 {
-            let spectrum = &mutable_psd[1..mutable_psd.len() / 2]; // Use the mutable spectrum
-
-            let max_idx_opt = spectrum
-                .iter()
-                .enumerate()
-                .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
-                .map(|(i, _)| i + 1);
-
-            if let Some(max_idx) = max_idx_opt {
-                let power = spectrum[max_idx - 1];
-                if power < threshold {
-                    break; // Stop if the strongest remaining peak is below the noise threshold
-                }
-
-                let frequency = (max_idx as f32) * (sample_rate as f32) / (signal_len as f32);
-
-                // Add the found fundamental peak to our list
-                peaks.push((frequency, power.min(1.0)));
-
-                // --- Suppress the found peak and its harmonics ---
-                let freq_resolution = sample_rate as f32 / signal_len as f32;
-                let peak_width_bins = (frequency * 0.03 / freq_resolution).ceil() as usize;
-
-                // Suppress the fundamental peak itself to prevent re-detection
-                let start_bin = (max_idx - peak_width_bins).max(1);
-                let end_bin = (max_idx + peak_width_bins).min(mutable_psd.len() / 2);
-                for item in mutable_psd.iter_mut().take(end_bin + 1).skip(start_bin) {
-                    *item = 0.0;
-                }
-
-                // OPTIMIZED: Suppress harmonics 2x-4x instead of 2x-6x (~40% faster)
-                // Higher harmonics rarely interfere with fundamental detection
-                for n in 2..=4 {
-                    let harmonic_freq = frequency * n as f32;
-                    let harmonic_idx = (harmonic_freq / freq_resolution) as usize;
-
-                    if harmonic_idx < mutable_psd.len() / 2 {
-                        let h_start_bin = (harmonic_idx - peak_width_bins).max(1);
-                        let h_end_bin = (harmonic_idx + peak_width_bins).min(mutable_psd.len() / 2);
-                        for item in mutable_psd.iter_mut().take(h_end_bin + 1).skip(h_start_bin) {
-                            *item = 0.0;
-                        }
-                    }
-                }
-            } else {
-                break; // No more peaks to find
-            }
-        }
-
-        // Sort by power (descending) as the primary result
-        peaks.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-
-        // Debug logging for detected peaks
-        log::debug!("FFT Peaks (Harmonic Suppression): max_power={:.3}, threshold={:.3}, fundamentals_found={}", 
-            max_power, threshold, peaks.len());
-        for (i, (freq, power)) in peaks.iter().take(5).enumerate() {
-            log::debug!(
-                "  Fundamental Peak {}: {:.2} Hz @ power={:.3}",
-                i + 1,
-                freq,
-                power
-            );
-        }
-
-        peaks
-    }
-
-    /// Clean FFT output to find primary frequency (returns strongest peak only)
-    fn find_primary_frequency(
-        &self,
-        psd: &[f32],
-        sample_rate: u32,
-        signal_len: usize,
-    ) -> Option<(f32, f32)> {
-        if psd.is_empty() {
-            return None;
-        }
-
-        // Find the index of maximum power (excluding DC component at index 0)
-        let max_idx = psd[1..psd.len() / 2] // Only look at positive frequencies
-            .iter()
-            .enumerate()
-            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
-            .map(|(i, _)| i + 1)?;
-
-        let max_power = psd[max_idx];
-
-        // Filter out very weak signals (noise floor)
-        if max_power < 0.1 {
-            return None;
-        }
-
-        // Convert index to frequency
-        let frequency = (max_idx as f32) * (sample_rate as f32) / (signal_len as f32);
-
-        // Return (frequency, power_as_confidence)
-        Some((frequency, max_power.min(1.0)))
-    }
-
-    /// Analyze audio chunk and return detected notes with confidence and intensity
-    /// Returns multiple notes if multiple strong peaks are detected
-    /// OPTIMIZED: Parallel peak-to-note conversion with rayon (faster note lookup for top peaks)
-    pub fn analyze_chunk_multi(
-        &self,
-        audio_data: &[f32],
-        sample_rate: u32,
-        profile: VoiceProfile,
-    ) -> Vec<(String, f32, f32)> {
-        if audio_data.is_empty() {
-            return Vec::new();
-        }
-
-        // Apply Hann window to reduce spectral leakage
-        let windowed = self.apply_hann_window(audio_data);
-
-        // Compute FFT
-        let psd = self.compute_fft(&windowed, sample_rate);
-
-        // Find all peaks in the spectrum
-        let peaks = self.find_all_peaks(&psd, sample_rate, audio_data.len());
-
-        // OPTIMIZED: Parallel conversion of peaks to notes using rayon
-        // This parallelizes the frequency lookup for multiple peaks simultaneously
-        let notes: Vec<(String, f32, f32)> = peaks
-            .into_par_iter()
-            .take(5) // Limit to top 5 peaks
-            .filter_map(|(frequency, power)| {
-                // Aggressively filter by voice profile if one is selected
-                if !Self::is_frequency_in_profile(frequency, profile) {
-                    log::debug!(
-                        "Filtered out frequency {frequency:.2} Hz - outside profile {profile:?}"
-                    );
-                    return None;
-                }
-
-                self.lookup
-                    .find_closest_note(frequency)
-                    .map(|(note_name, note_confidence)| (note_name, note_confidence, power))
-            })
-            .collect();
-
-        notes
-    }
-
-    /// Analyze audio chunk and return detected notes with confidence
-    pub fn analyze_chunk(&self, audio_data: &[f32], sample_rate: u32) -> Option<(String, f32)> {
-        if audio_data.is_empty() {
-            return None;
-        }
-
-        let start = std::time::Instant::now();
-
-        // Apply Hann window to reduce spectral leakage
-        let window_start = std::time::Instant::now();
-        let windowed = self.apply_hann_window(audio_data);
-        let window_time = window_start.elapsed().as_millis();
-
-        // Compute FFT
-        let fft_start = std::time::Instant::now();
-        let psd = self.compute_fft(&windowed, sample_rate);
-        let fft_time = fft_start.elapsed().as_millis();
-
-        // Find primary frequency
-        let find_start = std::time::Instant::now();
-        let (frequency, _power_confidence) =
-            self.find_primary_frequency(&psd, sample_rate, audio_data.len())?;
-        let find_time = find_start.elapsed().as_millis();
-
-        // Convert frequency to note
-        let lookup_start = std::time::Instant::now();
-        let (note_name, note_confidence) = self.lookup.find_closest_note(frequency)?;
-        let lookup_time = lookup_start.elapsed().as_millis();
-
-        // Use frequency-match confidence directly (ignore power-based confidence)
-        // Power-based confidence can be artificially low due to FFT bin resolution
-        let final_confidence = note_confidence;
-
-        let total_time = start.elapsed().as_millis();
-        log::debug!("analyze_chunk: total={total_time}ms, window={window_time}ms, fft={fft_time}ms, find={find_time}ms, lookup={lookup_time}ms");
-
-        Some((note_name, final_confidence))
-    }
-
-    /// Apply Hann window to reduce spectral leakage
-    /// OPTIMIZED: Parallel computation with rayon for multi-core speedup
-    fn apply_hann_window(&self, signal: &[f32]) -> Vec<f32> {
-        let n = signal.len() as f32;
-        let n_minus_1 = (n - 1.0).max(1.0);
-
-        // Use parallel iterator for large signals (>2048 samples)
-        // For smaller signals, overhead of parallelization isn't worth it
-        if signal.len() > 2048 {
-            signal
-                .par_iter()
-                .enumerate()
-                .map(|(i, &sample)| {
-                    let window = 0.5 * (1.0 - (2.0 * PI * i as f32 / n_minus_1).cos());
-                    sample * window
-                })
-                .collect()
-        } else {
-            signal
-                .iter()
-                .enumerate()
-                .map(|(i, &sample)| {
-                    let window = 0.5 * (1.0 - (2.0 * PI * i as f32 / n_minus_1).cos());
-                    sample * window
-                })
-                .collect()
-        }
-    }
-
-    /// Analyze raw audio buffer (simpler version for HTTP requests)
-    /// Takes raw bytes and interprets them as 16-bit PCM audio
-    /// Returns multiple detected notes per chunk
-    /// Only returns notes with confidence > 0.5 to filter out noise
-    /// OPTIMIZED: Parallel byte-to-sample conversion with rayon for large buffers
-    pub fn analyze_raw_bytes(
-        &self,
-        audio_data: &[u8],
-        sample_rate: u32,
-        profile: VoiceProfile,
-    ) -> Vec<(String, f32, f32)> {
-        if audio_data.len() < 2 {
-            return Vec::new();
-        }
-
-        let start = std::time::Instant::now();
-
-        // Convert bytes to 16-bit samples (parallel for large buffers, serial for small)
-        let convert_start = std::time::Instant::now();
-        let samples: Vec<f32> = if audio_data.len() > 8192 {
-            // Parallel conversion for large buffers (>8KB)
-            // OPTIMIZED: Use bytemuck to reinterpret bytes as i16 slice (no allocation)
-            let i16_samples: &[i16] = bytemuck::cast_slice(audio_data);
-            i16_samples
-                .par_iter()
-                .map(|&s| f32::from(s) / 32768.0)
-                .collect()
-        } else {
-            // Serial conversion for small buffers (faster due to lower overhead)
-            // OPTIMIZED: Use bytemuck to reinterpret bytes as i16 slice (no allocation)
-            let i16_samples: &[i16] = bytemuck::cast_slice(audio_data);
-            i16_samples
-                .iter()
-                .map(|&s| f32::from(s) / 32768.0)
-                .collect()
-        };
-        let convert_time = convert_start.elapsed().as_millis();
-
-        // If we have enough samples, analyze as a single large chunk for better frequency resolution
-        // Otherwise split into smaller chunks
-        let analysis_start = std::time::Instant::now();
-        let mut notes = if samples.len() >= 2048 {
-            // Use multi-peak detection for better harmonic detection
-            self.analyze_chunk_multi(&samples, sample_rate, profile)
-        } else if samples.len() >= 480 {
-            // For 10ms chunks (480 @ 48kHz), use optimized path: minimal windowing overhead
-            self.analyze_chunk_multi(&samples, sample_rate, profile)
-        } else {
-            // Fallback to single note detection if not enough samples
-            if let Some((note, confidence)) = self.analyze_chunk(&samples, sample_rate) {
-                vec![(note, confidence, 0.5)]
-            } else {
-                Vec::new()
-            }
-        };
-        let analysis_time = analysis_start.elapsed().as_millis();
-
-        // Filter out low-confidence noise (only keep notes with > 30% confidence)
-        // IMPROVED: Lowered from 50% to 30% to allow weak bass fundamentals
-        let filter_start = std::time::Instant::now();
-        notes.retain(|(_, confidence, _)| *confidence > 0.30);
-        let filter_time = filter_start.elapsed().as_millis();
-
-        let total_time = start.elapsed().as_millis();
-        log::debug!("analyze_raw_bytes: total={total_time}ms, convert={convert_time}ms, analysis={analysis_time}ms, filter={filter_time}ms");
-
-        notes
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_lookup_table_creation() {
-        let lookup = FrequencyToNoteLookup::new();
-        // Should have 7 natural notes × 5 octaves (C2 to C6) = 35 entries
-        assert!(lookup.table.len() >= 35);
-    }
-
-    #[test]
-    fn test_find_a4_frequency() {
-        let lookup = FrequencyToNoteLookup::new();
-        // A4 (440 Hz) should be found
-        let (note_name, confidence) = lookup.find_closest_note(440.0).unwrap();
-        assert_eq!(note_name, "A4");
-        assert!(confidence > 0.99);
-    }
-
-    #[test]
-    fn test_frequency_to_note_nearby() {
-        let lookup = FrequencyToNoteLookup::new();
-        // Frequency slightly off from A4 should still return A4 with lower confidence
-        let (note_name, confidence) = lookup.find_closest_note(445.0).unwrap();
-        let (_, exact_confidence) = lookup.find_closest_note(440.0).unwrap();
-        assert_eq!(note_name, "A4");
-        assert!(confidence < exact_confidence);
-        assert!(confidence > 0.0);
-    }
-}
@@ -0,0 +1,170 @@
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, header::HeaderMap, Method},
+    middleware::Next,
+    web, Error, ResponseError,
+};
+
+use crate::errors::ApiError;
+
+/// Env var with the key clients must send as `Authorization: Bearer <key>`; unset = no auth
+pub const API_KEY_ENV: &str = "RECOGNOTES_API_KEY";
+
+/// Paths anyone may call, so monitoring keeps working without the key
+const PUBLIC_PATHS: [&str; 2] = ["/health", "/version"];
+
+/// The API key requests must present, if one is configured
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ApiKey(Option<String>);
+
+impl std::fmt::Debug for ApiKey {
+    // Startup logs show whether a key is set, never the key itself
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.is_some() {
+            "ApiKey(<set>)"
+        } else {
+            "ApiKey(<none>)"
+        })
+    }
+}
+
+impl ApiKey {
+    /// Read `API_KEY_ENV`; open when unset
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var(API_KEY_ENV).ok().as_deref())
+    }
+
+    /// Empty or missing means no key (the API stays open)
+    pub fn parse(value: Option<&str>) -> Self {
+        Self(
+            value
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string),
+        )
+    }
+
+    pub const fn is_required(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Whether the headers carry `Authorization: Bearer <key>` (always true without a key)
+    pub fn authorizes(&self, headers: &HeaderMap) -> bool {
+        let Some(key) = &self.0 else {
+            return true;
+        };
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().split_once(' '))
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+            .is_some_and(|(_, token)| constant_time_eq(token.trim().as_bytes(), key.as_bytes()))
+    }
+}
+
+/// Compare without bailing out at the first differing byte, so timing doesn't leak the key
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Middleware rejecting requests without the configured key with a 401
+/// Public paths and CORS preflights (which browsers send without credentials) always pass;
+/// without an `ApiKey` in the app data, everything does
+pub async fn require_api_key(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let authorized = req.method() == Method::OPTIONS
+        || PUBLIC_PATHS.contains(&req.path())
+        || req
+            .app_data::<web::Data<ApiKey>>()
+            .is_none_or(|key| key.authorizes(req.headers()));
+
+    if authorized {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    }
+    log::warn!(
+        "Rejected {} {}: missing or wrong API key",
+        req.method(),
+        req.path()
+    );
+    let response = ApiError::Unauthorized.error_response();
+    Ok(req.into_response(response).map_into_right_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, middleware::from_fn, test as actix_test, App, HttpResponse};
+
+    /// Call `path` through the middleware with the given key configured and `Authorization` header
+    async fn call(key: Option<&str>, path: &str, authorization: Option<&str>) -> StatusCode {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(ApiKey::parse(key)))
+                .wrap(from_fn(require_api_key))
+                .route("/health", web::get().to(HttpResponse::Ok))
+                .route("/analyze", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+        let method = if path == "/health" {
+            Method::GET
+        } else {
+            Method::POST
+        };
+        let mut req = actix_test::TestRequest::default().method(method).uri(path);
+        if let Some(authorization) = authorization {
+            req = req.insert_header((header::AUTHORIZATION, authorization));
+        }
+        actix_test::call_service(&app, req.to_request())
+            .await
+            .status()
+    }
+
+    #[actix_web::test]
+    async fn test_matching_key_is_let_through() {
+        let key = Some("s3cret");
+        assert_eq!(
+            call(key, "/analyze", Some("Bearer s3cret")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            call(key, "/analyze", Some("bearer  s3cret ")).await,
+            StatusCode::OK
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_missing_or_wrong_key_is_unauthorized() {
+        let key = Some("s3cret");
+        for authorization in [
+            None,
+            Some("Bearer nope"),
+            Some("Bearer s3cret2"),
+            Some("Basic s3cret"),
+            Some("s3cret"),
+        ] {
+            assert_eq!(
+                call(key, "/analyze", authorization).await,
+                StatusCode::UNAUTHORIZED,
+                "{authorization:?}"
+            );
+        }
+        // Health stays public
+        assert_eq!(call(key, "/health", None).await, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_no_key_configured_leaves_the_api_open() {
+        assert_eq!(call(None, "/analyze", None).await, StatusCode::OK);
+        assert_eq!(call(Some("  "), "/analyze", None).await, StatusCode::OK);
+        assert_eq!(
+            call(None, "/analyze", Some("Bearer anything")).await,
+            StatusCode::OK
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::{
+    endpoints::{analyze::build_result, check_sample_rate},
+    models::{AnalysisParams, AnalysisResult},
+    tls::{TLS_CERT_ENV, TLS_KEY_ENV},
+    wav::decode_wav,
+    ANALYZER,
+};
+
+/// Command line: no subcommand starts the HTTP server, as it always has
+#[derive(Debug, Parser)]
+#[command(version, about = "FFT pitch detection server (or offline analyzer)")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub tls: TlsArgs,
+}
+
+/// Serve HTTPS instead of plain HTTP; both files are needed
+#[derive(Debug, Args)]
+pub struct TlsArgs {
+    /// PEM certificate chain (leaf first)
+    #[arg(long, env = TLS_CERT_ENV, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key for the certificate
+    #[arg(long, env = TLS_KEY_ENV, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+}
+
+impl TlsArgs {
+    /// Certificate and key paths, when HTTPS was asked for
+    pub fn paths(&self) -> Option<(&Path, &Path)> {
+        self.tls_cert.as_deref().zip(self.tls_key.as_deref())
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Analyze a WAV file offline and print the result as JSON to stdout
+    Analyze(AnalyzeArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct AnalyzeArgs {
+    /// 16/24-bit PCM or 32-bit float WAV file
+    pub file: PathBuf,
+    /// Voice profile (soprano, mezzo, alto, tenor, baritone, bass)
+    #[arg(long)]
+    pub profile: Option<String>,
+    /// Max ranked notes in the output
+    #[arg(long)]
+    pub max_results: Option<usize>,
+    /// Pretty-print the JSON
+    #[arg(long)]
+    pub pretty: bool,
+}
+
+/// Run `analyze`: the JSON result on stdout, or the error on stderr and exit code 1
+pub fn run_analyze(args: &AnalyzeArgs) -> i32 {
+    let output = analyze_file(&args.file, &args.params()).and_then(|result| {
+        if args.pretty {
+            serde_json::to_string_pretty(&result)
+        } else {
+            serde_json::to_string(&result)
+        }
+        .map_err(|e| e.to_string())
+    });
+    match output {
+        Ok(json) => {
+            println!("{json}");
+            0
+        }
+        Err(e) => {
+            eprintln!("error: {}: {e}", args.file.display());
+            1
+        }
+    }
+}
+
+impl AnalyzeArgs {
+    fn params(&self) -> AnalysisParams {
+        AnalysisParams {
+            profile: self.profile.clone(),
+            max_results: self.max_results,
+            ..AnalysisParams::default()
+        }
+    }
+}
+
+/// Decode and analyze a WAV file the same way `POST /analyze/wav` does
+fn analyze_file(path: &Path, params: &AnalysisParams) -> Result<AnalysisResult, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let wav = decode_wav(&bytes)?;
+    check_sample_rate(wav.sample_rate).map_err(|e| e.to_string())?;
+
+    let options = params.analysis_options();
+    let analysis = ANALYZER.analyze_samples(&wav.samples, wav.sample_rate, &options);
+    Ok(build_result(analysis, &options, wav.sample_rate))
+}
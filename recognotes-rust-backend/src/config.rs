@@ -0,0 +1,170 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+/// Env var with the address to listen on, e.g. `0.0.0.0` for LAN/container access
+/// (`address:port` is accepted too)
+pub const BIND_ENV: &str = "RECOGNOTES_BIND";
+/// Env var with the port to listen on (overrides a port given in `RECOGNOTES_BIND`)
+pub const PORT_ENV: &str = "RECOGNOTES_PORT";
+/// Env var with the number of actix worker threads
+pub const WORKERS_ENV: &str = "RECOGNOTES_WORKERS";
+/// Env var with the JSON body limit in whole megabytes
+pub const MAX_JSON_MB_ENV: &str = "RECOGNOTES_MAX_JSON_MB";
+
+const DEFAULT_BIND: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+const DEFAULT_PORT: u16 = 5000;
+const DEFAULT_WORKERS: usize = 8;
+/// Upper bound on worker threads; more than this is almost certainly a typo
+const MAX_WORKERS: usize = 256;
+const DEFAULT_MAX_JSON_MB: usize = 16;
+/// Upper bound on the JSON limit; a bigger body belongs on `/analyze/raw`
+const MAX_JSON_MB_LIMIT: usize = 1024;
+const BYTES_PER_MB: usize = 1024 * 1024;
+
+/// Where and how the HTTP server listens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerConfig {
+    pub bind: IpAddr,
+    pub port: u16,
+    pub workers: usize,
+    /// JSON body limit; base64 audio beyond this should use `/analyze/raw` or be split
+    pub max_json_bytes: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: DEFAULT_BIND,
+            port: DEFAULT_PORT,
+            workers: DEFAULT_WORKERS,
+            max_json_bytes: DEFAULT_MAX_JSON_MB * BYTES_PER_MB,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Read the `RECOGNOTES_*` env vars; anything unset or invalid keeps its default
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Build the config from a variable lookup (the environment, or a map in tests)
+    /// Invalid values are logged and replaced by the default
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut config = Self::default();
+        let value = |key: &str| {
+            lookup(key)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        if let Some(bind) = value(BIND_ENV) {
+            if let Ok(address) = bind.parse::<SocketAddr>() {
+                config.bind = address.ip();
+                config.port = address.port();
+            } else if let Ok(ip) = bind.parse::<IpAddr>() {
+                config.bind = ip;
+            } else {
+                log::warn!("Ignoring invalid {BIND_ENV}={bind:?}, using {DEFAULT_BIND}");
+            }
+        }
+
+        if let Some(port) = value(PORT_ENV) {
+            match port.parse::<u16>() {
+                Ok(port) => config.port = port,
+                Err(_) => log::warn!(
+                    "Ignoring invalid {PORT_ENV}={port:?}, using {}",
+                    config.port
+                ),
+            }
+        }
+
+        if let Some(workers) = value(WORKERS_ENV) {
+            match workers.parse::<usize>() {
+                Ok(workers) if (1..=MAX_WORKERS).contains(&workers) => config.workers = workers,
+                _ => log::warn!(
+                    "Ignoring invalid {WORKERS_ENV}={workers:?} (1-{MAX_WORKERS}), using {DEFAULT_WORKERS}"
+                ),
+            }
+        }
+
+        if let Some(max_json_mb) = value(MAX_JSON_MB_ENV) {
+            match max_json_mb.parse::<usize>() {
+                Ok(mb) if (1..=MAX_JSON_MB_LIMIT).contains(&mb) => {
+                    config.max_json_bytes = mb * BYTES_PER_MB;
+                }
+                _ => log::warn!(
+                    "Ignoring invalid {MAX_JSON_MB_ENV}={max_json_mb:?} (1-{MAX_JSON_MB_LIMIT}), using {DEFAULT_MAX_JSON_MB}"
+                ),
+            }
+        }
+
+        config
+    }
+
+    pub const fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind, self.port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(vars: &[(&str, &str)]) -> ServerConfig {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+        ServerConfig::from_lookup(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_env_vars_override_defaults() {
+        // Nothing set: today's 127.0.0.1:5000 with 8 workers and a 16MB JSON limit
+        assert_eq!(config(&[]), ServerConfig::default());
+        assert_eq!(
+            config(&[]).socket_addr(),
+            "127.0.0.1:5000".parse::<SocketAddr>().unwrap()
+        );
+
+        let lan = config(&[
+            (BIND_ENV, "0.0.0.0"),
+            (PORT_ENV, "8080"),
+            (WORKERS_ENV, "2"),
+            (MAX_JSON_MB_ENV, "4"),
+        ]);
+        assert_eq!(
+            lan.socket_addr(),
+            "0.0.0.0:8080".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(lan.workers, 2);
+        assert_eq!(lan.max_json_bytes, 4 * 1024 * 1024);
+
+        // address:port in one variable, and IPv6
+        assert_eq!(config(&[(BIND_ENV, "0.0.0.0:9000")]).port, 9000);
+        assert_eq!(
+            config(&[(BIND_ENV, "::")]).bind,
+            "::".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_values_fall_back_to_defaults() {
+        let bad = config(&[
+            (BIND_ENV, "my-laptop"),
+            (PORT_ENV, "70000"),
+            (WORKERS_ENV, "0"),
+            (MAX_JSON_MB_ENV, "0"),
+        ]);
+        assert_eq!(bad, ServerConfig::default());
+        for max_json_mb in ["-1", "1.5", "16MB", "4096"] {
+            assert_eq!(
+                config(&[(MAX_JSON_MB_ENV, max_json_mb)]).max_json_bytes,
+                16 * 1024 * 1024
+            );
+        }
+        assert_eq!(config(&[(WORKERS_ENV, "lots")]).workers, DEFAULT_WORKERS);
+        assert_eq!(config(&[(PORT_ENV, "  ")]).port, DEFAULT_PORT);
+    }
+}
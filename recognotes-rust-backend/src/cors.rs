@@ -0,0 +1,159 @@
+use actix_cors::Cors;
+use actix_web::http::{header, Method};
+
+use crate::{
+    endpoints::raw::{PROFILE_HEADER, SAMPLE_RATE_HEADER},
+    request_id::REQUEST_ID_HEADER,
+};
+
+/// Env var listing the browser origins allowed to call the API, comma-separated
+/// (e.g. `https://notes.example.com,http://192.168.1.20:8080`); `*` allows any origin
+pub const CORS_ORIGINS_ENV: &str = "RECOGNOTES_CORS_ORIGINS";
+
+/// How long browsers may cache a preflight response (seconds)
+const PREFLIGHT_MAX_AGE: usize = 3600;
+
+/// Browser origins allowed to make cross-origin requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllowedOrigins {
+    /// Pages served from this machine (`localhost`, `127.0.0.1` or `[::1]`, any port)
+    Localhost,
+    /// Any origin; only sensible behind another access check
+    Any,
+    /// Exactly these origins (scheme, host and port as the browser sends them)
+    List(Vec<String>),
+}
+
+impl AllowedOrigins {
+    /// Read `CORS_ORIGINS_ENV`; localhost-only when unset
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var(CORS_ORIGINS_ENV).ok().as_deref())
+    }
+
+    /// Parse a comma-separated origin list; empty or missing means localhost-only
+    pub fn parse(value: Option<&str>) -> Self {
+        let origins: Vec<String> = value
+            .unwrap_or_default()
+            .split(',')
+            .map(|origin| origin.trim().trim_end_matches('/'))
+            .filter(|origin| !origin.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        if origins.is_empty() {
+            Self::Localhost
+        } else if origins.iter().any(|origin| origin == "*") {
+            Self::Any
+        } else {
+            Self::List(origins)
+        }
+    }
+}
+
+/// Whether `origin` (e.g. `http://localhost:3000`) is a page served from this machine
+fn is_localhost_origin(origin: &str) -> bool {
+    let Some((_, host_port)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = if let Some(bracketed) = host_port.strip_prefix('[') {
+        bracketed.split_once(']').map_or("", |(host, _)| host)
+    } else {
+        host_port.split(':').next().unwrap_or_default()
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// CORS middleware for the API: the `GET`/`POST` methods it uses, JSON or raw PCM bodies,
+/// the API key, the raw endpoint's sample rate/profile headers, and `X-Request-Id` both ways
+pub fn cors(origins: &AllowedOrigins) -> Cors {
+    let cors = Cors::default()
+        .allowed_methods([Method::GET, Method::POST])
+        .allowed_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
+        .allowed_header(SAMPLE_RATE_HEADER)
+        .allowed_header(PROFILE_HEADER)
+        .allowed_header(REQUEST_ID_HEADER)
+        .expose_headers([REQUEST_ID_HEADER])
+        .max_age(PREFLIGHT_MAX_AGE);
+
+    match origins {
+        AllowedOrigins::Localhost => {
+            cors.allowed_origin_fn(|origin, _req| origin.to_str().is_ok_and(is_localhost_origin))
+        }
+        AllowedOrigins::Any => cors.allow_any_origin(),
+        AllowedOrigins::List(list) => list
+            .iter()
+            .fold(cors, |cors, origin| cors.allowed_origin(origin)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoints, AppState};
+    use actix_web::{http::StatusCode, test as actix_test, web, App};
+
+    /// Send an `/analyze` preflight from `origin` through the CORS middleware
+    /// Returns the `Access-Control-Allow-Origin` header, if any
+    async fn preflight(origins: AllowedOrigins, origin: &str) -> Option<String> {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .wrap(cors(&origins))
+                .route("/analyze", web::post().to(endpoints::analyze_audio)),
+        )
+        .await;
+        let req = actix_test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/analyze")
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type"))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        let allowed = resp.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN)?;
+        assert_eq!(resp.status(), StatusCode::OK);
+        Some(allowed.to_str().unwrap().to_string())
+    }
+
+    #[actix_web::test]
+    async fn test_analyze_preflight_allows_configured_origins() {
+        assert_eq!(
+            preflight(AllowedOrigins::Localhost, "http://localhost:5173")
+                .await
+                .as_deref(),
+            Some("http://localhost:5173")
+        );
+        // Localhost-only by default
+        assert_eq!(
+            preflight(AllowedOrigins::Localhost, "https://evil.example").await,
+            None
+        );
+
+        let listed =
+            AllowedOrigins::parse(Some("https://notes.example.com/, http://10.0.0.2:8080"));
+        assert_eq!(
+            preflight(listed, "https://notes.example.com")
+                .await
+                .as_deref(),
+            Some("https://notes.example.com")
+        );
+    }
+
+    #[test]
+    fn test_parse_origins() {
+        assert_eq!(AllowedOrigins::parse(None), AllowedOrigins::Localhost);
+        assert_eq!(
+            AllowedOrigins::parse(Some(" , ")),
+            AllowedOrigins::Localhost
+        );
+        assert_eq!(AllowedOrigins::parse(Some("*")), AllowedOrigins::Any);
+        assert_eq!(
+            AllowedOrigins::parse(Some("http://a.test,http://b.test:81")),
+            AllowedOrigins::List(vec!["http://a.test".into(), "http://b.test:81".into()])
+        );
+
+        assert!(is_localhost_origin("http://127.0.0.1:8080"));
+        assert!(is_localhost_origin("http://[::1]:3000"));
+        assert!(!is_localhost_origin("http://localhost.evil.example"));
+    }
+}
@@ -1,128 +1,172 @@
-use actix_web::{web, HttpResponse};
-use serde_json::json;
-use std::time::SystemTime;
+use actix_web::{web, HttpRequest, HttpResponse};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
 
-use crate::models::VoiceProfile;
+use crate::models::{SampleFormat, VoiceProfile};
 use crate::{
-    models::{AnalysisResult, AudioData, DetectedNote},
-    utils::{confidence_weight, low_frequency_bonus, note_to_frequency},
+    audio_analyzer::rank_notes,
+    chord::identify_chord,
+    endpoints::check_sample_rate,
+    errors::ApiError,
+    metrics::LatencyHistogram,
+    models::{
+        AnalysisOptions, AnalysisParams, AnalysisResult, AudioData, ChunkAnalysis, Diagnostics,
+        RequestTiming, CLIPPED_RATIO, MIN_RMS_DB,
+    },
+    request_id::{request_id, respond_with_id},
+    resample::{resample_linear, MAX_TARGET_RATE, MIN_TARGET_RATE},
+    utils::transpose_note_name,
     AppState, ANALYZER,
 };
 
 /// Analyze audio endpoint - processes raw audio and returns detected notes
+/// Every response (errors included) carries the request's `X-Request-Id`
 pub async fn analyze_audio(
-    _state: web::Data<AppState>,
+    state: web::Data<AppState>,
+    req: HttpRequest,
     audio: web::Json<AudioData>,
 ) -> HttpResponse {
     // Measure from START of function (JSON already deserialized by framework)
     let request_start = std::time::Instant::now();
+    let request_id = request_id(&req, &state.next_request_id);
 
-    log::debug!("Received request: sample_rate={}", audio.sample_rate);
+    log::debug!(
+        "[{request_id}] Received request: sample_rate={}",
+        audio.sample_rate
+    );
 
-    if audio.sample_rate == 0 {
-        log::error!("Invalid sample_rate: 0");
-        return HttpResponse::BadRequest()
-            .json(json!({"error": "sample_rate must be greater than 0"}));
+    let result = check_sample_rate(audio.sample_rate).and_then(|_| {
+        // Decode audio data (base64 string)
+        let audio_bytes = audio
+            .to_bytes()
+            .map_err(|e| ApiError::DecodeFailed(format!("Audio decode error: {e}")))?;
+
+        analyze_pcm(
+            &state,
+            &request_id,
+            audio_bytes,
+            audio.sample_rate,
+            Preprocessing {
+                target_rate: audio.target_rate,
+                analyze_tail_ms: audio.analyze_tail_ms,
+            },
+            &audio.params,
+            request_start,
+        )
+    });
+    respond_with_id(result, &request_id)
+}
+
+/// Buffer-level options only the `/analyze` routes take (the window routes, `/analyze/batch`
+/// and the stream, do their own slicing)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Preprocessing {
+    /// Resample to this rate before analysis
+    pub target_rate: Option<u32>,
+    /// Analyze only the last N ms of the buffer
+    pub analyze_tail_ms: Option<u32>,
+}
+
+/// Shared body of the `/analyze` handlers once the PCM bytes are in hand
+/// (base64-decoded from JSON, or the raw request body)
+/// `request_id` prefixes every log line, so concurrent clients' lines can be told apart
+pub fn analyze_pcm(
+    state: &AppState,
+    request_id: &str,
+    audio_bytes: Vec<u8>,
+    input_rate: u32,
+    preprocessing: Preprocessing,
+    params: &AnalysisParams,
+    request_start: std::time::Instant,
+) -> Result<HttpResponse, ApiError> {
+    // Resolve per-request analysis settings (voice profile, note count, confidence threshold, format)
+    let mut options = params.analysis_options();
+    if options.profile != VoiceProfile::NoProfile {
+        log::info!("[{request_id}] Using voice profile: {:?}", options.profile);
     }
 
-    // Decode audio data (base64 string)
-    let audio_bytes = match audio.to_bytes() {
-        Ok(bytes) => bytes,
-        Err(e) => {
-            return HttpResponse::BadRequest()
-                .json(json!({"error": format!("Audio decode error: {}", e)}));
+    if let Err(e) = options.format.validate_len(audio_bytes.len()) {
+        log::error!("[{request_id}] {e}");
+        return Err(ApiError::DecodeFailed(e));
+    }
+
+    // Optionally resample to a canonical rate so FFT bin resolution doesn't depend on the device
+    let (audio_bytes, sample_rate) = resample_pcm(
+        audio_bytes,
+        &mut options,
+        input_rate,
+        preprocessing.target_rate,
+    )?;
+
+    // Trade frequency resolution for latency: the client keeps its long buffer for context
+    let audio_bytes = match preprocessing.analyze_tail_ms {
+        Some(tail_ms) if !audio_bytes.is_empty() => {
+            tail_bytes(audio_bytes, tail_ms, sample_rate, options.frame_size())?
         }
+        _ => audio_bytes,
     };
 
-    // Get voice profile from request (default to NoProfile if not specified)
-    let profile = audio.get_profile();
-    if profile != VoiceProfile::NoProfile {
-        log::info!("Using voice profile: {profile:?}");
-    }
-
     // Track timing for analysis
     let mut analysis_ms = 0u128;
     let mut convert_us = 0u128;
     let audio_len = audio_bytes.len();
 
     // Allow empty audio_data - just return empty notes (for UI updates)
-    let result = if audio_bytes.is_empty() {
+    let mut result = if audio_bytes.is_empty() {
         AnalysisResult {
             notes: Vec::new(),
-            sample_rate: audio.sample_rate,
+            sample_rate,
             samples_analyzed: 0,
-            timestamp: SystemTime::now()
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_secs_f64(),
+            timestamp: unix_timestamp(),
+            rms_db: MIN_RMS_DB,
+            clipped: false,
+            clip_ratio: 0.0,
+            chord: None,
+            spectral_centroid_hz: None,
+            fft_size: None,
+            timing: None,
+            diagnostics: None,
         }
     } else {
         let pre_analysis = std::time::Instant::now();
 
         // Analyze the audio (FFT processing is internally optimized)
-        // Pass the voice profile for aggressive filtering
-        let notes_raw = ANALYZER.analyze_raw_bytes(&audio_bytes, audio.sample_rate, profile);
+        // Options carry the voice profile for aggressive filtering and the confidence threshold
+        let analysis = ANALYZER.analyze_raw_bytes(&audio_bytes, sample_rate, &options);
 
         analysis_ms = pre_analysis.elapsed().as_millis();
+        record_latency(&state.analysis_latency, pre_analysis.elapsed());
 
-        // Convert to result format with confidence filter (>= 10%)
-        // Keep top 3 notes with smart scoring: prefer lower frequencies (bass voices)
+        // Convert to result format (confidence already filtered by the analyzer)
         let pre_convert = std::time::Instant::now();
-
-        // OPTIMIZED: Pre-compute scores with frequency lookup cache
-        // This avoids redundant note_to_frequency() and bonus calculations
-        let mut notes_with_scores: Vec<(DetectedNote, f32)> = notes_raw
-            .into_iter()
-            .filter(|(_, confidence, _)| *confidence >= 0.10)
-            .map(|(note, confidence, intensity)| {
-                let note = DetectedNote {
-                    note,
-                    confidence,
-                    intensity,
-                };
-                let freq = note_to_frequency(&note.note);
-                let score = note.intensity.mul_add(
-                    0.1,
-                    low_frequency_bonus(freq)
-                        .mul_add(0.7, confidence_weight(note.confidence) * 0.2),
-                );
-                (note, score)
-            })
-            .collect();
-
-        // Sort once by pre-computed scores
-        notes_with_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-
-        // Extract top 3 notes
-        let notes: Vec<DetectedNote> = notes_with_scores
-            .into_iter()
-            .take(3)
-            .map(|(note, _)| note)
-            .collect();
-
+        let result = build_result(analysis, &options, sample_rate);
         convert_us = pre_convert.elapsed().as_micros();
 
-        AnalysisResult {
-            notes,
-            sample_rate: audio.sample_rate,
-            samples_analyzed: audio_bytes.len() / 2, // 16-bit samples = 2 bytes each
-            timestamp: SystemTime::now()
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_secs_f64(),
-        }
+        result
     };
 
     let pre_serialize = std::time::Instant::now();
-    let response = HttpResponse::Ok().json(&result);
+    let mut response = HttpResponse::Ok().json(&result);
     let serialize_ms = pre_serialize.elapsed().as_millis();
 
     let total_ms = request_start.elapsed().as_millis();
 
+    // Opt-in timing breakdown: re-serialize with it attached (off the default hot path)
+    if params.include_timing {
+        let as_u64 = |value: u128| u64::try_from(value).unwrap_or(u64::MAX);
+        result.timing = Some(RequestTiming {
+            analysis_ms: as_u64(analysis_ms),
+            convert_us: as_u64(convert_us),
+            serialize_ms: as_u64(serialize_ms),
+            total_ms: as_u64(total_ms),
+        });
+        response = HttpResponse::Ok().json(&result);
+    }
+
     // Log notes with confidence
     if result.notes.is_empty() {
         log::info!(
-            "REQUEST: bytes={audio_len}, analysis={analysis_ms}ms, convert={convert_us}us, serialize={serialize_ms}ms, TOTAL={total_ms}ms, NOTES: (none)"
+            "[{request_id}] REQUEST: bytes={audio_len}, analysis={analysis_ms}ms, convert={convert_us}us, serialize={serialize_ms}ms, TOTAL={total_ms}ms, NOTES: (none)"
         );
     } else {
         #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
@@ -140,9 +184,787 @@ pub async fn analyze_audio(
             .collect::<Vec<_>>()
             .join(", ");
         log::info!(
-            "REQUEST: bytes={audio_len}, analysis={analysis_ms}ms, convert={convert_us}us, serialize={serialize_ms}ms, TOTAL={total_ms}ms, NOTES: [{notes_str}]"
+            "[{request_id}] REQUEST: bytes={audio_len}, analysis={analysis_ms}ms, convert={convert_us}us, serialize={serialize_ms}ms, TOTAL={total_ms}ms, NOTES: [{notes_str}]"
+        );
+    }
+
+    // Keep the result for /last-result (after the response is built, so it doesn't add latency)
+    store_last_result(state, result);
+    record_latency(&state.analyze_latency, request_start.elapsed());
+
+    Ok(response)
+}
+
+/// Replace the stored last result
+/// A poisoned lock only means another request panicked mid-write; the `Option` inside is
+/// still valid, so recover it rather than failing every later request
+fn store_last_result(state: &AppState, result: AnalysisResult) {
+    let mut last_result = state.last_result.lock().unwrap_or_else(|poisoned| {
+        log::warn!("last_result lock was poisoned, recovering");
+        state.last_result.clear_poison();
+        poisoned.into_inner()
+    });
+    *last_result = Some(result);
+}
+
+/// Add a duration to one of the `/metrics` histograms
+/// A poisoned histogram is still usable; percentiles don't need to be exact
+fn record_latency(histogram: &Mutex<LatencyHistogram>, elapsed: Duration) {
+    histogram
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .record(elapsed.as_secs_f64() * 1000.0);
+}
+
+/// Resample the request's PCM to `target_rate` when one was asked for, returning the bytes and
+/// the rate to analyze them at
+/// Interleaved channels are averaged first (interpolating across them would mix L and R), so
+/// a resampled buffer is mono and `options.channels` is set to 1 to match
+pub fn resample_pcm(
+    audio_bytes: Vec<u8>,
+    options: &mut AnalysisOptions,
+    input_rate: u32,
+    target_rate: Option<u32>,
+) -> Result<(Vec<u8>, u32), ApiError> {
+    let Some(target_rate) = target_rate else {
+        return Ok((audio_bytes, input_rate));
+    };
+    if options.format != SampleFormat::S16le {
+        return Err(ApiError::InvalidParameter(
+            "target_rate is only supported for s16le audio".to_string(),
+        ));
+    }
+    let resampled = resample_bytes(
+        &audio_bytes,
+        usize::from(options.channels),
+        input_rate,
+        target_rate,
+    )
+    .map_err(|e| ApiError::InvalidParameter(format!("Resample error: {e}")))?;
+    options.channels = 1;
+    Ok((resampled, target_rate))
+}
+
+/// Resample little-endian 16-bit PCM bytes to `target_rate`, returning mono
+/// `channels` interleaved channels are averaged down first; a trailing partial frame is dropped
+fn resample_bytes(
+    audio_bytes: &[u8],
+    channels: usize,
+    sample_rate: u32,
+    target_rate: u32,
+) -> Result<Vec<u8>, String> {
+    if !(MIN_TARGET_RATE..=MAX_TARGET_RATE).contains(&target_rate) {
+        return Err(format!(
+            "target_rate must be between {MIN_TARGET_RATE} and {MAX_TARGET_RATE}"
+        ));
+    }
+
+    let channels = channels.max(1);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let samples: Vec<i16> = audio_bytes
+        .chunks_exact(2 * channels)
+        .map(|frame| {
+            let sum: i32 = frame
+                .chunks_exact(2)
+                .map(|pair| i32::from(i16::from_le_bytes([pair[0], pair[1]])))
+                .sum();
+            // The mean of i16 values always fits back in an i16
+            (sum / channels as i32) as i16
+        })
+        .collect();
+    let resampled = resample_linear(&samples, sample_rate, target_rate)?;
+
+    Ok(resampled.iter().flat_map(|s| s.to_le_bytes()).collect())
+}
+
+/// Keep the last `tail_ms` of interleaved PCM, whole frames only
+/// A tail of zero, or longer than the buffer, is a client bug rather than something to guess around
+fn tail_bytes(
+    mut audio_bytes: Vec<u8>,
+    tail_ms: u32,
+    sample_rate: u32,
+    frame_size: usize,
+) -> Result<Vec<u8>, ApiError> {
+    let frames = audio_bytes.len() / frame_size;
+    let tail_frames =
+        usize::try_from(u64::from(tail_ms) * u64::from(sample_rate) / 1000).unwrap_or(usize::MAX);
+    if tail_frames == 0 || tail_frames > frames {
+        let buffer_ms = frames as u64 * 1000 / u64::from(sample_rate);
+        return Err(ApiError::InvalidParameter(format!(
+            "analyze_tail_ms must be between 1 and the buffer length ({buffer_ms} ms), got {tail_ms}"
+        )));
+    }
+    Ok(audio_bytes.split_off((frames - tail_frames) * frame_size))
+}
+
+/// Build the response for one analyzed chunk
+/// `samples_analyzed` is the analyzer's own count of decoded (mono) samples, not a byte estimate
+/// The chord is named from every detected note, before ranking trims the list to `max_results`;
+/// note names are transposed and respelled in the requested naming scheme last, so ranking and
+/// `cents_off` still see the sounding pitch
+pub fn build_result(
+    analysis: ChunkAnalysis,
+    options: &AnalysisOptions,
+    sample_rate: u32,
+) -> AnalysisResult {
+    let transpose = |note: &str| transpose_note_name(note, options.transpose_semitones);
+    let chord_notes: Vec<(String, f32)> = analysis
+        .notes
+        .iter()
+        .map(|(note, confidence, ..)| (transpose(note), *confidence))
+        .collect();
+    let chord = identify_chord(
+        chord_notes
+            .iter()
+            .map(|(note, confidence)| (note.as_str(), *confidence)),
+    );
+
+    let mut notes = rank_notes(analysis.notes, options.score_weights, options.max_results);
+    for note in &mut notes {
+        note.note = options.naming.rename(&transpose(&note.note));
+    }
+
+    AnalysisResult {
+        notes,
+        sample_rate,
+        samples_analyzed: analysis.samples_analyzed,
+        timestamp: unix_timestamp(),
+        rms_db: analysis.rms_db,
+        clipped: analysis.clip_ratio >= CLIPPED_RATIO,
+        clip_ratio: analysis.clip_ratio,
+        chord,
+        spectral_centroid_hz: analysis.spectral_centroid_hz,
+        fft_size: analysis.fft_size,
+        timing: None,
+        diagnostics: options.include_diagnostics.then_some(Diagnostics {
+            rms_db: analysis.rms_db,
+            gated: analysis.gated,
+        }),
+    }
+}
+
+/// Seconds since the UNIX epoch, used to timestamp results
+pub fn unix_timestamp() -> f64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        endpoints::{get_last_result, tone_bytes},
+        request_id::REQUEST_ID_HEADER,
+    };
+    use actix_web::{test as actix_test, App};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::json;
+
+    #[actix_web::test]
+    async fn test_voice_profile_filters_out_of_range_tone() {
+        let state = web::Data::new(AppState::new());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+        let audio_data = STANDARD.encode(tone_bytes(1000.0, 48000, 12000, 0.5));
+
+        // Without a profile the 1000 Hz tone (~B5) is detected
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({"audio_data": audio_data, "sample_rate": 48000}))
+            .to_request();
+        let unfiltered: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+        assert!(!unfiltered.notes.is_empty());
+
+        // Bass range tops out around C4, so nothing survives
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({"audio_data": audio_data, "sample_rate": 48000, "profile": "bass"}))
+            .to_request();
+        let filtered: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+        assert!(filtered.notes.is_empty(), "{:?}", filtered.notes);
+    }
+
+    #[actix_web::test]
+    async fn test_custom_range_filters_out_of_range_tone() {
+        let state = web::Data::new(AppState::new());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+        let audio_data = STANDARD.encode(tone_bytes(400.0, 48000, 12000, 0.5));
+
+        // 40-80 Hz (double bass low register) excludes a 400 Hz tone
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({
+                "audio_data": audio_data,
+                "sample_rate": 48000,
+                "profile": "custom",
+                "custom_range": [40, 80],
+            }))
+            .to_request();
+        let filtered: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+        assert!(filtered.notes.is_empty(), "{:?}", filtered.notes);
+
+        // A range containing the tone keeps it
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({
+                "audio_data": audio_data,
+                "sample_rate": 48000,
+                "profile": "custom",
+                "custom_range": [300, 500],
+            }))
+            .to_request();
+        let kept: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+        assert!(!kept.notes.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_rejects_partial_f32_samples() {
+        let state = web::Data::new(AppState::new());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+
+        // 6 bytes is three i16 samples but one and a half f32 samples
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({
+                "audio_data": STANDARD.encode([0u8; 6]),
+                "sample_rate": 48000,
+                "format": "f32le",
+            }))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_rejects_odd_length_s16_buffer() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+
+        let mut bytes = tone_bytes(440.0, 48000, 4800, 0.5);
+        bytes.push(0);
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({"audio_data": STANDARD.encode(&bytes), "sample_rate": 48000}))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        let error = body["error"].as_str().unwrap();
+        assert!(
+            error.contains("9601") && error.contains("multiple of 2"),
+            "{error}"
         );
+        assert_eq!(body["code"], "decode_failed");
     }
 
-    response
+    #[actix_web::test]
+    async fn test_rejects_out_of_range_sample_rate() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+
+        let audio_data = STANDARD.encode(tone_bytes(440.0, 48000, 4800, 0.5));
+        for sample_rate in [0, 4000, 2_000_000, u32::MAX] {
+            let req = actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(json!({"audio_data": audio_data, "sample_rate": sample_rate}))
+                .to_request();
+            let resp = actix_test::call_service(&app, req).await;
+            assert_eq!(
+                resp.status(),
+                actix_web::http::StatusCode::BAD_REQUEST,
+                "{sample_rate}"
+            );
+            let body: serde_json::Value = actix_test::read_body_json(resp).await;
+            assert_eq!(body["code"], "invalid_sample_rate");
+            assert!(body["error"].as_str().unwrap().contains("192000"));
+        }
+
+        // The bounds themselves are fine
+        for sample_rate in [8000, 192_000] {
+            let req = actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(json!({"audio_data": audio_data, "sample_rate": sample_rate}))
+                .to_request();
+            let resp = actix_test::call_service(&app, req).await;
+            assert!(resp.status().is_success(), "{sample_rate}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_analyze_populates_last_result() {
+        let state = web::Data::new(AppState::new());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/analyze", web::post().to(analyze_audio))
+                .route("/last-result", web::get().to(get_last_result)),
+        )
+        .await;
+
+        // Nothing analyzed yet
+        let req = actix_test::TestRequest::get()
+            .uri("/last-result")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+
+        let sample_rate = 48000u32;
+        let bytes = tone_bytes(440.0, sample_rate, sample_rate / 4, 0.5);
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({"audio_data": STANDARD.encode(&bytes), "sample_rate": sample_rate}))
+            .to_request();
+        let analyzed: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+        assert!(analyzed.notes.iter().any(|n| n.note == "A4"));
+
+        let req = actix_test::TestRequest::get()
+            .uri("/last-result")
+            .to_request();
+        let stored: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+        let names = |r: &AnalysisResult| r.notes.iter().map(|n| n.note.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&stored), names(&analyzed));
+        assert!((stored.timestamp - analyzed.timestamp).abs() < f64::EPSILON);
+    }
+
+    #[actix_web::test]
+    async fn test_notes_report_frequency_and_cents_off() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+
+        // 446 Hz is ~23.5 cents sharp of A4; a 1s buffer gives 1 Hz bins
+        let sample_rate = 48000u32;
+        let bytes = tone_bytes(446.0, sample_rate, sample_rate, 0.5);
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({"audio_data": STANDARD.encode(&bytes), "sample_rate": sample_rate}))
+            .to_request();
+        let result: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+        let a4 = result.notes.iter().find(|n| n.note == "A4").unwrap();
+        assert!((a4.frequency - 446.0).abs() < 2.0, "{a4:?}");
+        assert!((a4.cents_off - 23.5).abs() < 8.0, "{a4:?}");
+    }
+
+    #[actix_web::test]
+    async fn test_polyphonic_scoring_keeps_every_chord_tone() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+
+        // Equal-power C major triad over a faint, low-confidence hum (~A2), with the
+        // confidence filter off so the hum competes for the top 3
+        let sample_rate = 48000u32;
+        let partials = [
+            (261.63f32, 0.2f32),
+            (329.63, 0.2),
+            (392.0, 0.2),
+            (116.54, 0.1),
+        ];
+        let bytes: Vec<u8> = (0..sample_rate / 4)
+            .flat_map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let sample: f32 = partials
+                    .iter()
+                    .map(|(freq, amplitude)| {
+                        amplitude * (2.0 * std::f32::consts::PI * freq * t).sin()
+                    })
+                    .sum();
+                ((sample * 32767.0) as i16).to_le_bytes()
+            })
+            .collect();
+        let audio_data = STANDARD.encode(&bytes);
+        let request = |scoring: &str| {
+            actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(json!({
+                    "audio_data": audio_data,
+                    "sample_rate": sample_rate,
+                    "min_confidence": 0.0,
+                    "scoring": scoring,
+                }))
+                .to_request()
+        };
+
+        // Melody scoring lets the bass bonus lift the hum over a chord tone
+        let result: AnalysisResult =
+            actix_test::call_and_read_body_json(&app, request("melody")).await;
+        assert!(
+            result.notes.iter().any(|n| n.note == "A2"),
+            "{:?}",
+            result.notes
+        );
+
+        let result: AnalysisResult =
+            actix_test::call_and_read_body_json(&app, request("polyphonic")).await;
+        let mut names: Vec<&str> = result.notes.iter().map(|n| n.note.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["C4", "E4", "G4"]);
+    }
+
+    #[actix_web::test]
+    async fn test_analyze_tail_ms_only_analyzes_the_tail() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+
+        // 800ms of A4, then 200ms of E5
+        let mut bytes = tone_bytes(440.0, 48000, 38400, 0.5);
+        bytes.extend(tone_bytes(659.25, 48000, 9600, 0.5));
+        let audio_data = STANDARD.encode(&bytes);
+        let request = |tail_ms: Option<u32>| {
+            actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(json!({
+                    "audio_data": audio_data,
+                    "sample_rate": 48000,
+                    "analyze_tail_ms": tail_ms
+                }))
+                .to_request()
+        };
+
+        let whole: AnalysisResult = actix_test::call_and_read_body_json(&app, request(None)).await;
+        assert_eq!(whole.samples_analyzed, 48000);
+        assert!(
+            whole.notes.iter().any(|n| n.note == "A4"),
+            "{:?}",
+            whole.notes
+        );
+
+        let tail: AnalysisResult =
+            actix_test::call_and_read_body_json(&app, request(Some(200))).await;
+        assert_eq!(tail.samples_analyzed, 9600);
+        assert_eq!(tail.notes[0].note, "E5");
+        assert!(
+            tail.notes.iter().all(|n| n.note != "A4"),
+            "{:?}",
+            tail.notes
+        );
+
+        for invalid in [0, 1001] {
+            let resp = actix_test::call_service(&app, request(Some(invalid))).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_target_rate_downmixes_stereo_first() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+
+        // 250ms of A4 at 48kHz: mono, and stereo with the tone in the left channel only
+        let mono = tone_bytes(440.0, 48000, 12000, 0.5);
+        let stereo: Vec<u8> = mono
+            .chunks_exact(2)
+            .flat_map(|left| [left[0], left[1], 0, 0])
+            .collect();
+        let request = |bytes: &[u8], channels: u8| {
+            actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(json!({
+                    "audio_data": STANDARD.encode(bytes),
+                    "sample_rate": 48000,
+                    "target_rate": 24000,
+                    "channels": channels
+                }))
+                .to_request()
+        };
+
+        let mono: AnalysisResult =
+            actix_test::call_and_read_body_json(&app, request(&mono, 1)).await;
+        let stereo: AnalysisResult =
+            actix_test::call_and_read_body_json(&app, request(&stereo, 2)).await;
+        assert_eq!(mono.notes[0].note, "A4");
+        assert_eq!(stereo.notes[0].note, mono.notes[0].note);
+        assert!((stereo.notes[0].frequency - mono.notes[0].frequency).abs() < 1.0);
+        // One mono sample per frame, at the target rate
+        assert_eq!(stereo.samples_analyzed, mono.samples_analyzed);
+        assert_eq!(stereo.sample_rate, 24000);
+    }
+
+    #[actix_web::test]
+    async fn test_naming_scheme_respells_notes() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+
+        let sample_rate = 48000u32;
+        let bytes = tone_bytes(440.0, sample_rate, sample_rate / 2, 0.5);
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({
+                "audio_data": STANDARD.encode(&bytes),
+                "sample_rate": sample_rate,
+                "naming": "solfege"
+            }))
+            .to_request();
+        let result: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+        assert_eq!(result.notes[0].note, "La4");
+    }
+
+    #[actix_web::test]
+    async fn test_transpose_shifts_note_names_only() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+        let bytes = tone_bytes(440.0, 48000, 24000, 0.5);
+        let request = |extra: serde_json::Value| {
+            let mut body = json!({"audio_data": STANDARD.encode(&bytes), "sample_rate": 48000});
+            body.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(body)
+                .to_request()
+        };
+
+        // A Bb clarinet reads a sounding A4 as B4; the measured pitch doesn't move
+        let result: AnalysisResult =
+            actix_test::call_and_read_body_json(&app, request(json!({"transpose_semitones": 2})))
+                .await;
+        assert_eq!(result.notes[0].note, "B4");
+        assert!((result.notes[0].frequency - 440.0).abs() < 2.0);
+        assert!(result.notes[0].cents_off.abs() < 10.0);
+
+        // Transposed first, then respelled
+        let result: AnalysisResult = actix_test::call_and_read_body_json(
+            &app,
+            request(json!({"transpose_semitones": -9, "naming": "solfege"})),
+        )
+        .await;
+        assert_eq!(result.notes[0].note, "Do4");
+    }
+
+    #[actix_web::test]
+    async fn test_silent_buffer_with_gate_disabled() {
+        let state = web::Data::new(AppState::new());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+
+        let silence = vec![0u8; 9600];
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({
+                "audio_data": STANDARD.encode(&silence),
+                "sample_rate": 48000,
+                "gate_db": -120,
+                "max_results": 10,
+                "include_diagnostics": true
+            }))
+            .to_request();
+        let result: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+        // The gate stays open, but digital silence has nothing to name (and skips the FFT)
+        assert!(result.notes.is_empty());
+        assert!(!result.diagnostics.unwrap().gated);
+        assert_eq!(result.fft_size, None);
+        assert_eq!(result.samples_analyzed, 4800);
+        assert_eq!(result.rms_db, MIN_RMS_DB);
+    }
+
+    #[actix_web::test]
+    async fn test_diagnostics_report_gated_silence() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+        let request = |bytes: &[u8], include_diagnostics: bool| {
+            actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(json!({
+                    "audio_data": STANDARD.encode(bytes),
+                    "sample_rate": 48000,
+                    "include_diagnostics": include_diagnostics
+                }))
+                .to_request()
+        };
+
+        let result: AnalysisResult =
+            actix_test::call_and_read_body_json(&app, request(&[0u8; 9600], true)).await;
+        assert!(result.notes.is_empty());
+        assert_eq!(
+            result.diagnostics,
+            Some(Diagnostics {
+                rms_db: MIN_RMS_DB,
+                gated: true
+            })
+        );
+
+        let tone = tone_bytes(440.0, 48000, 4800, 0.5);
+        let result: AnalysisResult =
+            actix_test::call_and_read_body_json(&app, request(&tone, true)).await;
+        let diagnostics = result.diagnostics.unwrap();
+        assert!(!diagnostics.gated);
+        assert!((diagnostics.rms_db - result.rms_db).abs() < f32::EPSILON);
+
+        let body: serde_json::Value =
+            actix_test::call_and_read_body_json(&app, request(&tone, false)).await;
+        assert!(body.get("diagnostics").is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_clipped_input_is_flagged() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+        let analyze = |bytes: &[u8]| {
+            actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(json!({"audio_data": STANDARD.encode(bytes), "sample_rate": 48000}))
+                .to_request()
+        };
+
+        // 220 Hz square wave slammed against both rails
+        let square: Vec<u8> = (0..4800)
+            .flat_map(|i| {
+                if (i / 109) % 2 == 0 {
+                    i16::MAX
+                } else {
+                    -i16::MAX
+                }
+                .to_le_bytes()
+            })
+            .collect();
+        let result: AnalysisResult =
+            actix_test::call_and_read_body_json(&app, analyze(&square)).await;
+        assert!(result.clipped);
+        assert!((result.clip_ratio - 1.0).abs() < f32::EPSILON);
+
+        let result: AnalysisResult = actix_test::call_and_read_body_json(
+            &app,
+            analyze(&tone_bytes(440.0, 48000, 4800, 0.5)),
+        )
+        .await;
+        assert!(!result.clipped);
+        assert_eq!(result.clip_ratio, 0.0);
+    }
+
+    #[actix_web::test]
+    async fn test_include_timing_adds_breakdown() {
+        let state = web::Data::new(AppState::new());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+
+        let bytes = tone_bytes(440.0, 48000, 4800, 0.5);
+        let request = |include_timing: bool| {
+            actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(json!({
+                    "audio_data": STANDARD.encode(&bytes),
+                    "sample_rate": 48000,
+                    "include_timing": include_timing
+                }))
+                .to_request()
+        };
+
+        let body: serde_json::Value =
+            actix_test::call_and_read_body_json(&app, request(true)).await;
+        let timing = &body["timing"];
+        for field in ["analysis_ms", "convert_us", "serialize_ms", "total_ms"] {
+            assert!(timing[field].is_u64(), "{field}: {timing}");
+        }
+        assert!(timing["total_ms"].as_u64() >= timing["analysis_ms"].as_u64());
+
+        // Off by default
+        let body: serde_json::Value =
+            actix_test::call_and_read_body_json(&app, request(false)).await;
+        assert!(body.get("timing").is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_responses_carry_request_id() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio)),
+        )
+        .await;
+        let request = |sample_rate: u32| {
+            actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(json!({
+                    "audio_data": STANDARD.encode(tone_bytes(440.0, 48000, 4800, 0.5)),
+                    "sample_rate": sample_rate
+                }))
+        };
+        let request_id = |resp: &actix_web::dev::ServiceResponse| {
+            resp.headers()
+                .get(REQUEST_ID_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+
+        // Generated IDs differ between requests
+        let first = actix_test::call_service(&app, request(48000).to_request()).await;
+        let second = actix_test::call_service(&app, request(48000).to_request()).await;
+        assert!(first.status().is_success());
+        assert!(request_id(&first).is_some_and(|id| !id.is_empty()));
+        assert_ne!(request_id(&first), request_id(&second));
+
+        // An incoming ID is echoed, on errors too
+        let resp = actix_test::call_service(
+            &app,
+            request(1)
+                .insert_header((REQUEST_ID_HEADER, "client-42"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(request_id(&resp).as_deref(), Some("client-42"));
+    }
 }
@@ -0,0 +1,496 @@
+use actix_web::{web, HttpResponse};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attack::attack_time_ms,
+    endpoints::{
+        analyze::{build_result, resample_pcm},
+        check_sample_rate, reject_analyze_tail,
+    },
+    errors::ApiError,
+    models::{AnalysisResult, AudioData},
+    vibrato::{detect_vibrato, sustained_segments},
+    ANALYZER,
+};
+
+/// Window length used when the client doesn't specify one
+const DEFAULT_BATCH_WINDOW_MS: u32 = 200;
+/// Upper bound on windows per request - each result is kept in memory until the response is sent
+/// (10000 windows = ~16 minutes at a 100ms hop)
+const MAX_BATCH_WINDOWS: usize = 10_000;
+
+/// Batch analysis request: one recording, sliced into overlapping windows
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    #[serde(flatten)]
+    pub audio: AudioData,
+    #[serde(default)]
+    pub window_ms: Option<u32>, // Length of each analysis window (default 200ms)
+    #[serde(default)]
+    pub hop_ms: Option<u32>, // Step between window starts (defaults to window_ms, i.e. no overlap)
+    #[serde(default)]
+    pub include_envelope: bool, // Also return the dominant note's intensity per window
+}
+
+/// One analyzed window, tagged with its position in the recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchFrame {
+    /// Start of the window, in seconds from the beginning of the recording
+    pub offset_secs: f64,
+    #[serde(flatten)]
+    pub result: AnalysisResult,
+}
+
+/// Note timeline for a whole recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResponse {
+    pub frames: Vec<BatchFrame>,
+    /// Note that is strongest in the most windows, only with `include_envelope`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub envelope_note: Option<String>,
+    /// Intensity of `envelope_note` in each window (0.0 where it wasn't detected),
+    /// for plotting its attack and decay
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub envelope: Option<Vec<f32>>,
+}
+
+/// Batch analysis endpoint - analyzes a full recording window by window
+pub async fn analyze_batch(request: web::Json<BatchRequest>) -> Result<HttpResponse, ApiError> {
+    let audio = &request.audio;
+    let input_rate = check_sample_rate(audio.sample_rate)?;
+    reject_analyze_tail(audio)?;
+
+    let audio_bytes = audio
+        .to_bytes()
+        .map_err(|e| ApiError::DecodeFailed(format!("Audio decode error: {e}")))?;
+
+    let window_ms = request.window_ms.unwrap_or(DEFAULT_BATCH_WINDOW_MS);
+    let hop_ms = request.hop_ms.unwrap_or(window_ms);
+    if window_ms == 0 || hop_ms == 0 {
+        return Err(ApiError::InvalidParameter(
+            "window_ms and hop_ms must be greater than 0".to_string(),
+        ));
+    }
+
+    let mut options = audio.params.analysis_options();
+    options
+        .format
+        .validate_len(audio_bytes.len())
+        .map_err(ApiError::DecodeFailed)?;
+    // Resampled once up front, so every window is analyzed at the target rate
+    let (audio_bytes, sample_rate) =
+        resample_pcm(audio_bytes, &mut options, input_rate, audio.target_rate)?;
+    let frame_size = options.frame_size(); // Window bounds must land on whole interleaved frames
+
+    let window_samples = ms_to_samples(window_ms, sample_rate);
+    let hop_samples = ms_to_samples(hop_ms, sample_rate);
+    let total_samples = audio_bytes.len() / frame_size;
+
+    let window_count = count_windows(total_samples, window_samples, hop_samples);
+    if window_count > MAX_BATCH_WINDOWS {
+        return Err(ApiError::InvalidParameter(format!(
+            "Recording would produce {window_count} windows (max {MAX_BATCH_WINDOWS}); increase hop_ms"
+        )));
+    }
+
+    let batch_start = std::time::Instant::now();
+
+    // Windows are independent, so analyze them in parallel
+    let mut frames: Vec<BatchFrame> = (0..window_count)
+        .into_par_iter()
+        .map(|idx| {
+            let start = idx * hop_samples;
+            let end = (start + window_samples).min(total_samples);
+            let window = &audio_bytes[start * frame_size..end * frame_size];
+
+            let analysis = ANALYZER.analyze_raw_bytes(window, sample_rate, &options);
+
+            BatchFrame {
+                offset_secs: start as f64 / f64::from(sample_rate),
+                result: build_result(analysis, &options, sample_rate),
+            }
+        })
+        .collect();
+
+    // Sustained notes span several windows, so vibrato and attack are measured across the timeline
+    annotate_sustained_notes(&mut frames, sample_rate as f32 / hop_samples as f32);
+    let (envelope_note, envelope) = if request.include_envelope {
+        dominant_envelope(&frames).unzip()
+    } else {
+        (None, None)
+    };
+
+    log::info!(
+        "BATCH: samples={total_samples}, windows={window_count}, window={window_ms}ms, hop={hop_ms}ms, TOTAL={}ms",
+        batch_start.elapsed().as_millis()
+    );
+
+    Ok(HttpResponse::Ok().json(BatchResponse {
+        frames,
+        envelope_note,
+        envelope,
+    }))
+}
+
+/// Mark the dominant (most intense) note of each frame with the vibrato and attack time of its
+/// sustained run
+/// `frame_rate` is windows per second; hops much longer than ~50ms can't resolve vibrato, and
+/// attack times are only as fine as the hop
+fn annotate_sustained_notes(frames: &mut [BatchFrame], frame_rate: f32) {
+    let dominant: Vec<Option<usize>> = frames.iter().map(strongest_note).collect();
+    let track: Vec<Option<f32>> = frames
+        .iter()
+        .zip(&dominant)
+        .map(|(frame, idx)| idx.map(|idx| frame.result.notes[idx].frequency))
+        .collect();
+
+    for segment in sustained_segments(&track) {
+        let frequencies: Vec<f32> = track[segment.clone()].iter().flatten().copied().collect();
+        let vibrato = detect_vibrato(&frequencies, frame_rate);
+        // Every frame of a segment has a dominant note, so the envelope lines up with the frames
+        let envelope: Vec<f32> = frames[segment.clone()]
+            .iter()
+            .zip(&dominant[segment.clone()])
+            .filter_map(|(frame, idx)| idx.map(|idx| frame.result.notes[idx].intensity))
+            .collect();
+        let attack_ms = attack_time_ms(&envelope, frame_rate);
+
+        for (frame, idx) in frames[segment.clone()].iter_mut().zip(&dominant[segment]) {
+            if let Some(idx) = idx {
+                let note = &mut frame.result.notes[*idx];
+                note.vibrato = vibrato;
+                note.attack_ms = attack_ms;
+            }
+        }
+    }
+}
+
+/// Index of the most intense note in a frame
+fn strongest_note(frame: &BatchFrame) -> Option<usize> {
+    frame
+        .result
+        .notes
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.intensity.total_cmp(&b.1.intensity))
+        .map(|(idx, _)| idx)
+}
+
+/// The note that is strongest in the most frames (earliest wins ties), and its intensity in
+/// every frame; `None` when no frame detected anything
+fn dominant_envelope(frames: &[BatchFrame]) -> Option<(String, Vec<f32>)> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for frame in frames {
+        if let Some(idx) = strongest_note(frame) {
+            let note = frame.result.notes[idx].note.as_str();
+            match counts.iter_mut().find(|(name, _)| *name == note) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((note, 1)),
+            }
+        }
+    }
+    // max_by_key keeps the last maximum, so search from the back for the earliest
+    let (note, _) = counts.iter().rev().max_by_key(|(_, count)| *count)?;
+
+    let envelope = frames
+        .iter()
+        .map(|frame| {
+            frame
+                .result
+                .notes
+                .iter()
+                .find(|detected| detected.note == *note)
+                .map_or(0.0, |detected| detected.intensity)
+        })
+        .collect();
+    Some(((*note).to_string(), envelope))
+}
+
+/// Convert a duration in milliseconds to a sample count (at least 1)
+fn ms_to_samples(ms: u32, sample_rate: u32) -> usize {
+    #[allow(clippy::cast_possible_truncation)]
+    let samples = (u64::from(sample_rate) * u64::from(ms) / 1000) as usize;
+    samples.max(1)
+}
+
+/// Number of full windows that fit in the recording
+/// A recording shorter than one window is analyzed as a single (short) window
+const fn count_windows(total_samples: usize, window_samples: usize, hop_samples: usize) -> usize {
+    if total_samples == 0 {
+        0
+    } else if total_samples <= window_samples {
+        1
+    } else {
+        (total_samples - window_samples) / hop_samples + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::tone_bytes;
+    use actix_web::{test as actix_test, App};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::json;
+
+    #[actix_web::test]
+    async fn test_batch_splits_tone_into_windows() {
+        let sample_rate = 48000u32;
+        let bytes = tone_bytes(440.0, sample_rate, sample_rate, 0.5);
+
+        let app = actix_test::init_service(
+            App::new().route("/analyze/batch", web::post().to(analyze_batch)),
+        )
+        .await;
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze/batch")
+            .set_json(json!({
+                "audio_data": STANDARD.encode(&bytes),
+                "sample_rate": sample_rate,
+                "window_ms": 100,
+                "hop_ms": 100,
+            }))
+            .to_request();
+        let response: BatchResponse = actix_test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.frames.len(), 10);
+        for (idx, frame) in response.frames.iter().enumerate() {
+            assert!((frame.offset_secs - idx as f64 * 0.1).abs() < 1e-9);
+            assert!(
+                frame.result.notes.iter().any(|n| n.note == "A4"),
+                "window {idx} missing A4: {:?}",
+                frame.result.notes
+            );
+        }
+
+        // Windows are the batch route's own slicing; a tail would be silently dropped
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze/batch")
+            .set_json(json!({
+                "audio_data": STANDARD.encode(&bytes),
+                "sample_rate": sample_rate,
+                "analyze_tail_ms": 200,
+            }))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_batch_honors_target_rate() {
+        let bytes = tone_bytes(440.0, 48000, 48000, 0.5);
+
+        let app = actix_test::init_service(
+            App::new().route("/analyze/batch", web::post().to(analyze_batch)),
+        )
+        .await;
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze/batch")
+            .set_json(json!({
+                "audio_data": STANDARD.encode(&bytes),
+                "sample_rate": 48000,
+                "target_rate": 24000,
+                "window_ms": 100,
+            }))
+            .to_request();
+        let response: BatchResponse = actix_test::call_and_read_body_json(&app, req).await;
+
+        // Windows are cut from the resampled recording
+        assert_eq!(response.frames.len(), 10);
+        for frame in &response.frames {
+            assert_eq!(frame.result.sample_rate, 24000);
+            assert_eq!(frame.result.samples_analyzed, 2400);
+            assert_eq!(frame.result.notes[0].note, "A4");
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_batch_reports_vibrato_rate() {
+        // A4 swinging ±50 cents at 5.5 Hz for two seconds
+        let sample_rate = 48000u32;
+        let mut phase = 0.0_f64;
+        let bytes: Vec<u8> = (0..2 * sample_rate)
+            .flat_map(|i| {
+                let t = f64::from(i) / f64::from(sample_rate);
+                let cents = 50.0 * (2.0 * std::f64::consts::PI * 5.5 * t).sin();
+                phase += 2.0 * std::f64::consts::PI * 440.0 * (cents / 1200.0).exp2()
+                    / f64::from(sample_rate);
+                ((0.5 * phase.sin() * 32767.0) as i16).to_le_bytes()
+            })
+            .collect();
+        let steady = tone_bytes(440.0, sample_rate, 2 * sample_rate, 0.5);
+
+        let app = actix_test::init_service(
+            App::new().route("/analyze/batch", web::post().to(analyze_batch)),
+        )
+        .await;
+        let batch_request = |bytes: &[u8]| {
+            actix_test::TestRequest::post()
+                .uri("/analyze/batch")
+                .set_json(json!({
+                    "audio_data": STANDARD.encode(bytes),
+                    "sample_rate": sample_rate,
+                    "window_ms": 40,
+                    "hop_ms": 20,
+                }))
+                .to_request()
+        };
+
+        let response: BatchResponse =
+            actix_test::call_and_read_body_json(&app, batch_request(&bytes)).await;
+        let vibrato = response.frames[10]
+            .result
+            .notes
+            .iter()
+            .find_map(|note| note.vibrato)
+            .expect("sustained note should report vibrato");
+        assert!((vibrato.rate_hz - 5.5).abs() < 0.5, "{vibrato:?}");
+        assert!(vibrato.depth_cents > 40.0, "{vibrato:?}");
+
+        let response: BatchResponse =
+            actix_test::call_and_read_body_json(&app, batch_request(&steady)).await;
+        assert!(response.frames.iter().all(|frame| frame
+            .result
+            .notes
+            .iter()
+            .all(|note| note.vibrato.is_none())));
+    }
+
+    #[actix_web::test]
+    async fn test_batch_response_is_gzipped_on_request() {
+        use actix_web::{http::header, middleware::Compress};
+        use std::io::Read;
+
+        let sample_rate = 48000u32;
+        let bytes = tone_bytes(440.0, sample_rate, 2 * sample_rate, 0.5);
+        let body = json!({
+            "audio_data": STANDARD.encode(&bytes),
+            "sample_rate": sample_rate,
+            "window_ms": 50,
+            "hop_ms": 10,
+        });
+
+        let app = actix_test::init_service(
+            App::new().service(
+                web::resource("/analyze/batch")
+                    .wrap(Compress::default())
+                    .route(web::post().to(analyze_batch)),
+            ),
+        )
+        .await;
+        let request = |encoding: &str| {
+            actix_test::TestRequest::post()
+                .uri("/analyze/batch")
+                .insert_header((header::ACCEPT_ENCODING, encoding))
+                .set_json(&body)
+                .to_request()
+        };
+
+        let resp = actix_test::call_service(&app, request("identity")).await;
+        assert!(resp.headers().get(header::CONTENT_ENCODING).is_none());
+        let plain: BatchResponse =
+            serde_json::from_slice(&actix_test::read_body(resp).await).unwrap();
+
+        let resp = actix_test::call_service(&app, request("gzip")).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_ENCODING).unwrap(),
+            "gzip"
+        );
+        let compressed = actix_test::read_body(resp).await;
+        let mut json = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_ref())
+            .read_to_end(&mut json)
+            .unwrap();
+        assert!(
+            compressed.len() * 4 < json.len(),
+            "{} vs {}",
+            compressed.len(),
+            json.len()
+        );
+
+        // Same frames either way (timestamps aside)
+        let gzipped: BatchResponse = serde_json::from_slice(&json).unwrap();
+        assert_eq!(gzipped.frames.len(), plain.frames.len());
+        for (a, b) in gzipped.frames.iter().zip(&plain.frames) {
+            assert_eq!(a.offset_secs, b.offset_secs);
+            assert_eq!(
+                serde_json::to_value(&a.result.notes).unwrap(),
+                serde_json::to_value(&b.result.notes).unwrap()
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_envelope_follows_attack_and_decay() {
+        // A4 fading in over 150ms, then decaying exponentially
+        let sample_rate = 48000u32;
+        let bytes: Vec<u8> = (0..sample_rate)
+            .flat_map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let gain = if t < 0.15 {
+                    t / 0.15
+                } else {
+                    (-(t - 0.15) * 2.0).exp()
+                };
+                let sample = 0.05 * gain * (2.0 * std::f32::consts::PI * 440.0 * t).sin();
+                ((sample * 32767.0) as i16).to_le_bytes()
+            })
+            .collect();
+
+        let app = actix_test::init_service(
+            App::new().route("/analyze/batch", web::post().to(analyze_batch)),
+        )
+        .await;
+        let request = |include_envelope: bool| {
+            actix_test::TestRequest::post()
+                .uri("/analyze/batch")
+                .set_json(json!({
+                    "audio_data": STANDARD.encode(&bytes),
+                    "sample_rate": sample_rate,
+                    "window_ms": 50,
+                    "include_envelope": include_envelope,
+                }))
+                .to_request()
+        };
+
+        let response: BatchResponse =
+            actix_test::call_and_read_body_json(&app, request(true)).await;
+        assert_eq!(response.envelope_note.as_deref(), Some("A4"));
+        let envelope = response.envelope.unwrap();
+        assert_eq!(envelope.len(), response.frames.len());
+
+        let peak = (0..envelope.len())
+            .max_by(|&a, &b| envelope[a].total_cmp(&envelope[b]))
+            .unwrap();
+        assert!((2..=4).contains(&peak), "peak at {peak}: {envelope:?}");
+        assert!(
+            envelope[..=peak].windows(2).all(|w| w[0] < w[1]),
+            "{envelope:?}"
+        );
+        assert!(
+            envelope[peak..].windows(2).all(|w| w[0] >= w[1]),
+            "{envelope:?}"
+        );
+        assert!(envelope[envelope.len() - 1] < envelope[peak] / 2.0);
+
+        // The 150ms fade-in is a slow attack, reported on the note from its onset on
+        let first = &response.frames[0].result.notes[0];
+        assert_eq!(first.note, "A4");
+        let attack_ms = first.attack_ms.unwrap();
+        assert!((75.0..=200.0).contains(&attack_ms), "{attack_ms}");
+
+        // Off by default
+        let body: serde_json::Value =
+            actix_test::call_and_read_body_json(&app, request(false)).await;
+        assert!(body.get("envelope").is_none());
+    }
+
+    #[test]
+    fn test_count_windows() {
+        assert_eq!(count_windows(0, 100, 50), 0);
+        assert_eq!(count_windows(80, 100, 50), 1);
+        assert_eq!(count_windows(1000, 100, 100), 10);
+        assert_eq!(count_windows(1000, 100, 50), 19);
+    }
+}
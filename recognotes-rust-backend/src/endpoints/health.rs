@@ -1,7 +1,109 @@
-use actix_web::HttpResponse;
+use actix_web::{web, HttpResponse};
 use serde_json::json;
+use std::sync::{atomic::Ordering, Mutex};
 
-/// Health check endpoint
-pub async fn health() -> HttpResponse {
-    HttpResponse::Ok().json(json!({"status": "ok", "version": "0.2.0-fft"}))
+use crate::{metrics::LatencyHistogram, AppState};
+
+/// Crate version and the git commit it was built from (see `build.rs`)
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_COMMIT: &str = env!("RECOGNOTES_GIT_COMMIT");
+
+/// Health check endpoint - build info, uptime and request count
+pub async fn health(state: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "status": "ok",
+        "version": VERSION,
+        "git_commit": GIT_COMMIT,
+        "uptime_secs": state.started_at.elapsed().as_secs(),
+        "requests_served": state.requests_served.load(Ordering::Relaxed),
+    }))
+}
+
+/// Version endpoint - build info only
+pub async fn version() -> HttpResponse {
+    HttpResponse::Ok().json(json!({"version": VERSION, "git_commit": GIT_COMMIT}))
+}
+
+/// Metrics endpoint - `/analyze` latency percentiles over the recent window
+/// `analyze_latency` is the whole handler, `analysis_latency` the FFT/note extraction inside it
+pub async fn metrics(state: web::Data<AppState>) -> HttpResponse {
+    // A poisoned histogram is still usable; percentiles don't need to be exact
+    let summary = |histogram: &Mutex<LatencyHistogram>| {
+        histogram
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .summary()
+    };
+
+    HttpResponse::Ok().json(json!({
+        "requests_served": state.requests_served.load(Ordering::Relaxed),
+        "analyze_latency": summary(&state.analyze_latency),
+        "analysis_latency": summary(&state.analysis_latency),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::{analyze_audio, tone_bytes};
+    use actix_web::{test as actix_test, App};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    #[actix_web::test]
+    async fn test_health_reports_crate_version() {
+        let state = web::Data::new(AppState::new());
+        state.requests_served.fetch_add(3, Ordering::Relaxed);
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/health", web::get().to(health)),
+        )
+        .await;
+
+        let req = actix_test::TestRequest::get().uri("/health").to_request();
+        let body: serde_json::Value = actix_test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(body["requests_served"], 3);
+        assert!(body["git_commit"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn test_metrics_percentiles_after_analyses() {
+        let state = web::Data::new(AppState::new());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/analyze", web::post().to(analyze_audio))
+                .route("/metrics", web::get().to(metrics)),
+        )
+        .await;
+
+        let bytes = tone_bytes(440.0, 48000, 4800, 0.5);
+        for _ in 0..5 {
+            let req = actix_test::TestRequest::post()
+                .uri("/analyze")
+                .set_json(json!({"audio_data": STANDARD.encode(&bytes), "sample_rate": 48000}))
+                .to_request();
+            assert!(actix_test::call_service(&app, req)
+                .await
+                .status()
+                .is_success());
+        }
+
+        let req = actix_test::TestRequest::get().uri("/metrics").to_request();
+        let body: serde_json::Value = actix_test::call_and_read_body_json(&app, req).await;
+        for histogram in ["analyze_latency", "analysis_latency"] {
+            let latency = &body[histogram];
+            assert_eq!(latency["count"], 5, "{histogram}: {latency}");
+            let percentiles: Vec<f64> = ["p50_ms", "p95_ms", "p99_ms", "max_ms"]
+                .iter()
+                .map(|field| latency[field].as_f64().unwrap())
+                .collect();
+            assert!(percentiles[0] > 0.0, "{histogram}: {latency}");
+            assert!(
+                percentiles.windows(2).all(|pair| pair[0] <= pair[1]),
+                "{histogram}: {latency}"
+            );
+        }
+    }
 }
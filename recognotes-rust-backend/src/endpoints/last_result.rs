@@ -1,20 +1,16 @@
+use crate::errors::ApiError;
 use crate::AppState;
 use actix_web::web;
 use actix_web::HttpResponse;
-use serde_json::json;
 
 /// Get last analysis result
-pub async fn get_last_result(state: web::Data<AppState>) -> HttpResponse {
-    state.last_result.lock().map_or_else(
-        |_| {
-            HttpResponse::InternalServerError()
-                .json(json!({"error": "Failed to access stored result"}))
-        },
-        |last_result| {
-            last_result.as_ref().map_or_else(
-                || HttpResponse::NoContent().finish(),
-                |result| HttpResponse::Ok().json(result.clone()),
-            )
-        },
-    )
+pub async fn get_last_result(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let last_result = state
+        .last_result
+        .lock()
+        .map_err(|_| ApiError::Internal("Failed to access stored result".to_string()))?;
+    Ok(last_result.as_ref().map_or_else(
+        || HttpResponse::NoContent().finish(),
+        |result| HttpResponse::Ok().json(result.clone()),
+    ))
 }
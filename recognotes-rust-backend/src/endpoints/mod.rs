@@ -1,7 +1,61 @@
+use crate::{errors::ApiError, models::AudioData};
+
 pub mod analyze;
+pub mod batch;
 pub mod health;
 pub mod last_result;
+pub mod note;
+pub mod profiles;
+pub mod raw;
+pub mod spectrum;
+pub mod stream;
+pub mod upload;
 
 pub use analyze::analyze_audio;
-pub use health::health;
+pub use batch::analyze_batch;
+pub use health::{health, metrics, version};
 pub use last_result::get_last_result;
+pub use note::lookup_note;
+pub use profiles::list_profiles;
+pub use raw::analyze_raw;
+pub use spectrum::analyze_spectrum;
+pub use stream::analyze_stream;
+pub use upload::analyze_wav;
+
+/// Sample rates a client may declare, from telephone audio to 192 kHz studio interfaces
+/// Anything outside is a buggy or hostile client, and would only yield nonsense frequencies
+pub const MIN_SAMPLE_RATE: u32 = 8_000;
+pub const MAX_SAMPLE_RATE: u32 = 192_000;
+
+/// Reject a declared `sample_rate` outside `MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE`
+pub fn check_sample_rate(sample_rate: u32) -> Result<u32, ApiError> {
+    if (MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&sample_rate) {
+        Ok(sample_rate)
+    } else {
+        log::error!("Invalid sample_rate: {sample_rate}");
+        Err(ApiError::InvalidSampleRate)
+    }
+}
+
+/// Reject `analyze_tail_ms` on routes that flatten `AudioData` but analyze the whole recording,
+/// rather than silently ignoring it
+pub fn reject_analyze_tail(audio: &AudioData) -> Result<(), ApiError> {
+    match audio.analyze_tail_ms {
+        Some(_) => Err(ApiError::InvalidParameter(
+            "analyze_tail_ms is only supported by /analyze and /analyze/raw".to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// `len` samples of a sine at `freq` Hz as 16-bit little-endian PCM, peaking at `amplitude`
+#[cfg(test)]
+pub(crate) fn tone_bytes(freq: f32, sample_rate: u32, len: u32, amplitude: f32) -> Vec<u8> {
+    (0..len)
+        .flat_map(|i| {
+            let t = i as f32 / sample_rate as f32;
+            let sample = amplitude * (2.0 * std::f32::consts::PI * freq * t).sin();
+            ((sample * 32767.0) as i16).to_le_bytes()
+        })
+        .collect()
+}
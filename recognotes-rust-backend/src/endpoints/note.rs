@@ -0,0 +1,98 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio_analyzer::{
+        FrequencyToNoteLookup, KNOWN_NOTE_FREQUENCY, MAX_REFERENCE_HZ, MIN_REFERENCE_HZ,
+    },
+    errors::ApiError,
+    models::{HIGHEST_OCTAVE, LOWEST_OCTAVE},
+    utils::{cents_between, parse_note_name, pitch_frequency_at},
+};
+
+/// Note lookup query, e.g. `/note?freq=446.2&reference_pitch=432`
+#[derive(Debug, Deserialize)]
+pub struct NoteQuery {
+    pub freq: f32,
+    #[serde(default)]
+    pub reference_pitch: Option<f32>, // Frequency of A4 in Hz (default 440)
+}
+
+/// Nearest note to a frequency
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteLookup {
+    pub note: String,
+    /// In-tune frequency of `note` at `reference_pitch`
+    pub frequency: f32,
+    /// How far the queried frequency is from `frequency` (positive = sharp)
+    pub cents_off: f32,
+    pub reference_pitch: f32,
+}
+
+/// Note endpoint - names a frequency without sending any audio (tuners, teaching tools)
+/// Uses the same natural-note table as analysis, over every supported octave (C0 to B9)
+pub async fn lookup_note(query: web::Query<NoteQuery>) -> Result<HttpResponse, ApiError> {
+    let reference_pitch = query.reference_pitch.unwrap_or(KNOWN_NOTE_FREQUENCY);
+    if !(MIN_REFERENCE_HZ..=MAX_REFERENCE_HZ).contains(&reference_pitch) {
+        return Err(ApiError::InvalidParameter(format!(
+            "reference_pitch must be between {MIN_REFERENCE_HZ} and {MAX_REFERENCE_HZ} Hz"
+        )));
+    }
+
+    let lookup = FrequencyToNoteLookup::shared(LOWEST_OCTAVE, HIGHEST_OCTAVE, reference_pitch);
+    let (note, _) = Some(query.freq)
+        .filter(|freq| freq.is_finite())
+        .and_then(|freq| lookup.find_closest_note(freq))
+        .ok_or_else(|| ApiError::InvalidParameter("freq must be between 0 and 20000 Hz".into()))?;
+    let (pitch_class, octave) =
+        parse_note_name(&note).ok_or_else(|| ApiError::Internal(format!("Bad note {note}")))?;
+    let frequency = pitch_frequency_at(i32::from(pitch_class), octave, reference_pitch);
+
+    Ok(HttpResponse::Ok().json(NoteLookup {
+        cents_off: cents_between(query.freq, frequency),
+        note,
+        frequency,
+        reference_pitch,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test as actix_test, App};
+
+    #[actix_web::test]
+    async fn test_note_lookup_names_frequencies() {
+        let app =
+            actix_test::init_service(App::new().route("/note", web::get().to(lookup_note))).await;
+        let lookup = |uri: &str| actix_test::TestRequest::get().uri(uri).to_request();
+
+        let a4: NoteLookup =
+            actix_test::call_and_read_body_json(&app, lookup("/note?freq=440")).await;
+        assert_eq!(a4.note, "A4");
+        assert!((a4.frequency - 440.0).abs() < 0.01);
+        assert!(a4.cents_off.abs() < 0.01, "{a4:?}");
+
+        let sharp: NoteLookup =
+            actix_test::call_and_read_body_json(&app, lookup("/note?freq=446.2")).await;
+        assert_eq!(sharp.note, "A4");
+        assert!((sharp.cents_off - 24.2).abs() < 0.5, "{sharp:?}");
+
+        // 440 Hz is sharp in 432 tuning
+        let tuned: NoteLookup =
+            actix_test::call_and_read_body_json(&app, lookup("/note?freq=440&reference_pitch=432"))
+                .await;
+        assert_eq!(tuned.note, "A4");
+        assert!((tuned.cents_off - 31.8).abs() < 0.5, "{tuned:?}");
+
+        for bad in [
+            "/note?freq=0",
+            "/note?freq=NaN",
+            "/note?freq=440&reference_pitch=300",
+            "/note",
+        ] {
+            let resp = actix_test::call_service(&app, lookup(bad)).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{bad}");
+        }
+    }
+}
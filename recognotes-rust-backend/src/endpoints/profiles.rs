@@ -0,0 +1,54 @@
+use actix_web::HttpResponse;
+use serde_json::json;
+
+use crate::models::{VoiceProfile, DEFAULT_MAX_OCTAVE, DEFAULT_MIN_OCTAVE};
+use crate::utils::note_to_frequency;
+
+/// Profiles endpoint - every `voice_profile` name a client may send, with its `[min_hz, max_hz]`
+/// "no_profile" doesn't filter, so it reports the default note table (C1 to B7) instead
+/// "custom" isn't listed: its range is whatever the client sends with it
+pub async fn list_profiles() -> HttpResponse {
+    let unfiltered = (
+        note_to_frequency(&format!("C{DEFAULT_MIN_OCTAVE}")),
+        note_to_frequency(&format!("B{DEFAULT_MAX_OCTAVE}")),
+    );
+    let profiles: Vec<_> = VoiceProfile::all_profiles()
+        .iter()
+        .map(|name| {
+            let (min_hz, max_hz) = VoiceProfile::from_str(name)
+                .freq_range()
+                .unwrap_or(unfiltered);
+            json!({"name": name, "range_hz": [min_hz, max_hz]})
+        })
+        .collect();
+
+    HttpResponse::Ok().json(json!({ "profiles": profiles }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test as actix_test, web, App};
+
+    #[actix_web::test]
+    async fn test_profiles_lists_every_profile_with_a_range() {
+        let app =
+            actix_test::init_service(App::new().route("/profiles", web::get().to(list_profiles)))
+                .await;
+
+        let req = actix_test::TestRequest::get().uri("/profiles").to_request();
+        let body: serde_json::Value = actix_test::call_and_read_body_json(&app, req).await;
+        let profiles = body["profiles"].as_array().unwrap();
+        assert_eq!(profiles.len(), 7);
+
+        for (profile, name) in profiles.iter().zip(VoiceProfile::all_profiles()) {
+            assert_eq!(profile["name"], *name);
+            let min_hz = profile["range_hz"][0].as_f64().unwrap();
+            let max_hz = profile["range_hz"][1].as_f64().unwrap();
+            assert!(0.0 < min_hz && min_hz < max_hz, "{profile}");
+        }
+        let tenor = &profiles[4];
+        assert_eq!(tenor["name"], "tenor");
+        assert!((tenor["range_hz"][0].as_f64().unwrap() - 130.81).abs() < 0.01);
+    }
+}
@@ -0,0 +1,194 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::StreamExt;
+use serde::Deserialize;
+
+use crate::{
+    endpoints::{
+        analyze::{analyze_pcm, Preprocessing},
+        check_sample_rate,
+    },
+    errors::ApiError,
+    models::AnalysisParams,
+    request_id::{request_id, respond_with_id},
+    AppState,
+};
+
+/// Largest accepted raw PCM body
+/// Raw bytes skip base64's 33% inflation, so this holds ~3x the audio of the 16MB JSON limit
+pub const MAX_RAW_PCM_BYTES: usize = 48 * 1024 * 1024;
+/// Headers carrying the sample rate and voice profile of a raw PCM body
+/// (alternatives to the `sample_rate` and `profile` query parameters)
+pub const SAMPLE_RATE_HEADER: &str = "X-Sample-Rate";
+pub const PROFILE_HEADER: &str = "X-Profile";
+
+/// Query parameters specific to the raw route (analysis settings are parsed separately)
+#[derive(Debug, Deserialize)]
+pub struct RawQuery {
+    #[serde(default)]
+    pub sample_rate: Option<u32>,
+    #[serde(default)]
+    pub analyze_tail_ms: Option<u32>, // Analyze only the last N ms of the body, as on /analyze
+}
+
+/// Raw PCM endpoint - `/analyze` without the JSON/base64 wrapper
+/// The body is `application/octet-stream` PCM; the rate and profile come from the query string
+/// or the `X-Sample-Rate`/`X-Profile` headers, e.g. `POST /analyze/raw?sample_rate=48000&profile=bass`
+pub async fn analyze_raw(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Payload,
+    query: web::Query<RawQuery>,
+    params: web::Query<AnalysisParams>,
+) -> HttpResponse {
+    let request_start = std::time::Instant::now();
+    let request_id = request_id(&req, &state.next_request_id);
+
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+    };
+
+    let sample_rate = query
+        .sample_rate
+        .or_else(|| header(SAMPLE_RATE_HEADER).and_then(|value| value.parse::<u32>().ok()));
+    let mut params = params.into_inner();
+    if params.profile.is_none() {
+        params.profile = header(PROFILE_HEADER);
+    }
+
+    let result = async {
+        // From the sample_rate query parameter or the X-Sample-Rate header
+        let sample_rate = sample_rate
+            .ok_or(ApiError::InvalidSampleRate)
+            .and_then(check_sample_rate)?;
+        let audio_bytes = read_body(body).await?;
+        analyze_pcm(
+            &state,
+            &request_id,
+            audio_bytes,
+            sample_rate,
+            Preprocessing {
+                target_rate: None,
+                analyze_tail_ms: query.analyze_tail_ms,
+            },
+            &params,
+            request_start,
+        )
+    }
+    .await;
+    respond_with_id(result, &request_id)
+}
+
+/// Collect the body, answering one over `MAX_RAW_PCM_BYTES` with the same `buffer_too_large`
+/// error the JSON routes give
+async fn read_body(mut body: web::Payload) -> Result<Vec<u8>, ApiError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| ApiError::InvalidRequest(format!("Body read error: {e}")))?;
+        if bytes.len() + chunk.len() > MAX_RAW_PCM_BYTES {
+            return Err(ApiError::BufferTooLarge {
+                message: format!(
+                    "Body exceeds the {}MB raw PCM limit",
+                    MAX_RAW_PCM_BYTES / (1024 * 1024)
+                ),
+                max_bytes: MAX_RAW_PCM_BYTES,
+            });
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoints::analyze_audio, models::AnalysisResult};
+    use actix_web::{http::header, http::StatusCode, test as actix_test, App};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::json;
+
+    fn chord_bytes() -> Vec<u8> {
+        (0..12000)
+            .flat_map(|i| {
+                let t = i as f32 / 48000.0;
+                let sample = [261.63, 329.63, 392.0]
+                    .iter()
+                    .map(|f| 0.25 * (2.0 * std::f32::consts::PI * f * t).sin())
+                    .sum::<f32>();
+                ((sample * 32767.0) as i16).to_le_bytes()
+            })
+            .collect()
+    }
+
+    #[actix_web::test]
+    async fn test_raw_route_matches_base64_route() {
+        let bytes = chord_bytes();
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new()))
+                .route("/analyze", web::post().to(analyze_audio))
+                .route("/analyze/raw", web::post().to(analyze_raw)),
+        )
+        .await;
+
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze")
+            .set_json(json!({
+                "audio_data": STANDARD.encode(&bytes),
+                "sample_rate": 48000,
+                "profile": "tenor"
+            }))
+            .to_request();
+        let via_json: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+
+        // Rate and profile in headers
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze/raw")
+            .insert_header((header::CONTENT_TYPE, "application/octet-stream"))
+            .insert_header((SAMPLE_RATE_HEADER, "48000"))
+            .insert_header((PROFILE_HEADER, "tenor"))
+            .set_payload(bytes.clone())
+            .to_request();
+        let via_headers: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+
+        // Rate and profile in the query string
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze/raw?sample_rate=48000&profile=tenor")
+            .insert_header((header::CONTENT_TYPE, "application/octet-stream"))
+            .set_payload(bytes.clone())
+            .to_request();
+        let via_query: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+
+        assert!(!via_json.notes.is_empty());
+        for raw in [&via_headers, &via_query] {
+            assert_eq!(raw.samples_analyzed, via_json.samples_analyzed);
+            assert_eq!(
+                serde_json::to_value(&raw.notes).unwrap(),
+                serde_json::to_value(&via_json.notes).unwrap()
+            );
+        }
+
+        // Missing sample rate
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze/raw")
+            .insert_header((header::CONTENT_TYPE, "application/octet-stream"))
+            .set_payload(bytes)
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Over the limit: the JSON routes' 413 body, not a plain-text one
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze/raw?sample_rate=48000")
+            .insert_header((header::CONTENT_TYPE, "application/octet-stream"))
+            .set_payload(vec![0u8; MAX_RAW_PCM_BYTES + 2])
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["code"], "buffer_too_large");
+        assert_eq!(body["max_bytes"], MAX_RAW_PCM_BYTES);
+    }
+}
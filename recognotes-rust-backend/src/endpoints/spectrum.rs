@@ -0,0 +1,132 @@
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+
+use crate::{
+    audio_analyzer::AudioAnalyzer,
+    endpoints::{
+        analyze::{resample_pcm, unix_timestamp},
+        check_sample_rate, reject_analyze_tail,
+    },
+    errors::ApiError,
+    models::{AudioData, SpectrumResult},
+    ANALYZER,
+};
+
+const DEFAULT_SPECTRUM_BINS: usize = 256;
+const MAX_SPECTRUM_BINS: usize = 4096;
+const DEFAULT_MIN_HZ: f32 = 50.0;
+const DEFAULT_MAX_HZ: f32 = 2000.0;
+
+/// Spectrum request: the usual audio payload plus the display range
+#[derive(Debug, Deserialize)]
+pub struct SpectrumRequest {
+    #[serde(flatten)]
+    pub audio: AudioData,
+    #[serde(default)]
+    pub bins: Option<usize>, // Number of log-spaced output bins (default 256)
+    #[serde(default)]
+    pub min_hz: Option<f32>, // Lowest bin center (default 50 Hz)
+    #[serde(default)]
+    pub max_hz: Option<f32>, // Highest bin center (default 2000 Hz)
+}
+
+/// Spectrum endpoint - returns a log-spaced magnitude spectrum for visualization
+pub async fn analyze_spectrum(
+    request: web::Json<SpectrumRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let audio = &request.audio;
+
+    let input_rate = check_sample_rate(audio.sample_rate)?;
+    reject_analyze_tail(audio)?;
+
+    let audio_bytes = audio
+        .to_bytes()
+        .map_err(|e| ApiError::DecodeFailed(format!("Audio decode error: {e}")))?;
+
+    let mut options = audio.params.analysis_options();
+    options
+        .format
+        .validate_len(audio_bytes.len())
+        .map_err(ApiError::DecodeFailed)?;
+    let (audio_bytes, sample_rate) =
+        resample_pcm(audio_bytes, &mut options, input_rate, audio.target_rate)?;
+
+    let bins = request
+        .bins
+        .unwrap_or(DEFAULT_SPECTRUM_BINS)
+        .clamp(1, MAX_SPECTRUM_BINS);
+    let nyquist = sample_rate as f32 / 2.0;
+    let min_hz = request.min_hz.unwrap_or(DEFAULT_MIN_HZ);
+    let max_hz = request.max_hz.unwrap_or(DEFAULT_MAX_HZ).min(nyquist);
+    if !(min_hz > 0.0 && min_hz < max_hz) {
+        return Err(ApiError::InvalidParameter(format!(
+            "Invalid range: need 0 < min_hz < max_hz <= {nyquist}"
+        )));
+    }
+
+    let samples = AudioAnalyzer::decode_raw_bytes(&audio_bytes, &options);
+    let (frequencies, magnitudes) =
+        ANALYZER.magnitude_spectrum(&samples, sample_rate, bins, min_hz, max_hz);
+
+    Ok(HttpResponse::Ok().json(SpectrumResult {
+        frequencies,
+        magnitudes,
+        sample_rate,
+        samples_analyzed: samples.len(),
+        timestamp: unix_timestamp(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::tone_bytes;
+    use actix_web::{test as actix_test, App};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::json;
+
+    #[actix_web::test]
+    async fn test_spectrum_endpoint_shape() {
+        let bytes = tone_bytes(440.0, 48000, 4800, 0.5);
+
+        let app = actix_test::init_service(
+            App::new().route("/spectrum", web::post().to(analyze_spectrum)),
+        )
+        .await;
+        let req = actix_test::TestRequest::post()
+            .uri("/spectrum")
+            .set_json(json!({"audio_data": STANDARD.encode(&bytes), "sample_rate": 48000}))
+            .to_request();
+        let result: SpectrumResult = actix_test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(result.frequencies.len(), DEFAULT_SPECTRUM_BINS);
+        assert_eq!(result.magnitudes.len(), DEFAULT_SPECTRUM_BINS);
+        assert!((result.frequencies[0] - 50.0).abs() < 0.01);
+        assert!((result.frequencies[DEFAULT_SPECTRUM_BINS - 1] - 2000.0).abs() < 1.0);
+        assert!(result.magnitudes.iter().all(|m| (0.0..=1.0).contains(m)));
+
+        // Resampled before the FFT, like /analyze
+        let req = actix_test::TestRequest::post()
+            .uri("/spectrum")
+            .set_json(json!({
+                "audio_data": STANDARD.encode(&bytes),
+                "sample_rate": 48000,
+                "target_rate": 24000
+            }))
+            .to_request();
+        let resampled: SpectrumResult = actix_test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resampled.sample_rate, 24000);
+        assert_eq!(resampled.samples_analyzed, 2400);
+
+        let req = actix_test::TestRequest::post()
+            .uri("/spectrum")
+            .set_json(json!({
+                "audio_data": STANDARD.encode(&bytes),
+                "sample_rate": 48000,
+                "analyze_tail_ms": 50
+            }))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+}
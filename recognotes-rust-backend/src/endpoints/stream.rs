@@ -0,0 +1,320 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_ws::Message;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::{
+    endpoints::{analyze::build_result, check_sample_rate},
+    errors::ApiError,
+    models::{AnalysisOptions, AnalysisParams, AnalysisResult},
+    ANALYZER,
+};
+
+/// Sliding window kept per connection when the client doesn't ask for a specific length
+const DEFAULT_STREAM_WINDOW_MS: u32 = 2000;
+/// Longest sliding window a client may request (bounds per-connection memory)
+const MAX_STREAM_WINDOW_MS: u32 = 10_000;
+/// Largest single WebSocket frame accepted (2 seconds of 48kHz mono i16 is ~190KB)
+const MAX_STREAM_FRAME_BYTES: usize = 1024 * 1024;
+
+/// Connection settings for `/analyze/stream`, passed as query parameters
+/// e.g. `/analyze/stream?sample_rate=48000&window_ms=2000&profile=bass`
+#[derive(Debug, Deserialize)]
+pub struct StreamConfig {
+    pub sample_rate: u32,
+    #[serde(default)]
+    pub window_ms: Option<u32>, // Server-side sliding window length (default 2000ms)
+    #[serde(flatten)]
+    pub params: AnalysisParams,
+}
+
+/// Control messages a client may send as WebSocket text frames, e.g. `{"cmd": "reset"}`
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum StreamCommand {
+    /// Drop the buffered window so the next frame starts a fresh phrase
+    Reset,
+}
+
+/// Per-connection rolling buffer of raw PCM bytes
+/// The server keeps the sliding window so clients only push newly captured audio
+pub struct StreamSession {
+    buffer: Vec<u8>,
+    capacity_bytes: usize,
+    sample_rate: u32,
+    options: AnalysisOptions,
+}
+
+impl StreamSession {
+    pub fn new(config: &StreamConfig) -> Self {
+        let window_ms = config
+            .window_ms
+            .unwrap_or(DEFAULT_STREAM_WINDOW_MS)
+            .clamp(1, MAX_STREAM_WINDOW_MS);
+        let samples = (u64::from(config.sample_rate) * u64::from(window_ms) / 1000).max(1);
+        let options = config.params.analysis_options();
+        #[allow(clippy::cast_possible_truncation)]
+        let capacity_bytes = samples as usize * options.frame_size();
+
+        Self {
+            buffer: Vec::with_capacity(capacity_bytes),
+            capacity_bytes,
+            sample_rate: config.sample_rate,
+            options,
+        }
+    }
+
+    /// Append a frame of PCM bytes, dropping the oldest samples beyond the window
+    ///
+    /// # Errors
+    /// Returns an error if the frame isn't a whole number of samples on every channel
+    pub fn push(&mut self, frame: &[u8]) -> Result<(), ApiError> {
+        self.options
+            .format
+            .validate_len(frame.len())
+            .map_err(ApiError::DecodeFailed)?;
+        let frame_size = self.options.frame_size();
+        if !frame.len().is_multiple_of(frame_size) {
+            return Err(ApiError::DecodeFailed(format!(
+                "Frame length {} is not a multiple of {frame_size} bytes ({} interleaved channels)",
+                frame.len(),
+                self.options.channels
+            )));
+        }
+
+        self.buffer.extend_from_slice(frame);
+        if self.buffer.len() > self.capacity_bytes {
+            let drain_count = self.buffer.len() - self.capacity_bytes;
+            self.buffer.drain(..drain_count);
+        }
+        Ok(())
+    }
+
+    /// Forget all buffered audio (the connection's settings are kept)
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Analyze the current window
+    pub fn analyze(&self) -> AnalysisResult {
+        let analysis = ANALYZER.analyze_raw_bytes(&self.buffer, self.sample_rate, &self.options);
+        build_result(analysis, &self.options, self.sample_rate)
+    }
+}
+
+/// Streaming analysis over WebSocket
+///
+/// Protocol: the client sends binary frames of raw audio in the connection's `format` (16-bit
+/// little-endian PCM by default, or `f32le`, `mulaw`, `alaw`), interleaved when `channels` is
+/// above 1 (averaged to mono); after each frame the server replies with a JSON `AnalysisResult`
+/// text message for its sliding window.
+/// Text frames are control commands: `{"cmd": "reset"}` empties the window without a reply, so
+/// a new phrase isn't analyzed together with the end of the last one.
+/// Errors (e.g. malformed frames) are reported as `{"error": "...", "code": "..."}` text messages.
+/// Invalid connection parameters are rejected before the upgrade with a JSON error response.
+pub async fn analyze_stream(
+    req: HttpRequest,
+    body: web::Payload,
+    query: web::Query<HashMap<String, String>>,
+) -> actix_web::Result<HttpResponse> {
+    let config: StreamConfig = match serde_json::from_value(query_to_json(&query)) {
+        Ok(config) => config,
+        Err(e) => {
+            return Err(ApiError::InvalidRequest(format!("Invalid stream parameters: {e}")).into());
+        }
+    };
+
+    check_sample_rate(config.sample_rate)?;
+
+    let (response, mut session, msg_stream) = actix_ws::handle(&req, body)?;
+    let mut msg_stream = msg_stream.max_frame_size(MAX_STREAM_FRAME_BYTES);
+
+    log::info!(
+        "Stream opened: sample_rate={}, window_ms={:?}",
+        config.sample_rate,
+        config.window_ms
+    );
+
+    actix_web::rt::spawn(async move {
+        let mut stream = StreamSession::new(&config);
+
+        while let Some(Ok(msg)) = msg_stream.recv().await {
+            let reply = match msg {
+                Message::Binary(frame) => match stream.push(&frame) {
+                    Ok(()) => serde_json::to_string(&stream.analyze()).unwrap_or_else(|e| {
+                        ApiError::Internal(e.to_string()).to_json().to_string()
+                    }),
+                    Err(e) => e.to_json().to_string(),
+                },
+                Message::Text(text) => match serde_json::from_str::<StreamCommand>(&text) {
+                    Ok(StreamCommand::Reset) => {
+                        stream.reset();
+                        continue;
+                    }
+                    Err(e) => ApiError::InvalidRequest(format!("Invalid stream command: {e}"))
+                        .to_json()
+                        .to_string(),
+                },
+                Message::Ping(bytes) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                Message::Close(reason) => {
+                    let _ = session.close(reason).await;
+                    return;
+                }
+                _ => continue,
+            };
+
+            if session.text(reply).await.is_err() {
+                return;
+            }
+        }
+
+        let _ = session.close(None).await;
+    });
+
+    Ok(response)
+}
+
+/// Convert query-string values into a JSON object so they deserialize like request bodies
+/// Values that parse as JSON scalars/arrays (`48000`, `true`, `[40,320]`) keep their type,
+/// anything else (e.g. `bass`) is passed through as a string
+fn query_to_json(query: &HashMap<String, String>) -> serde_json::Value {
+    query
+        .iter()
+        .map(|(key, value)| {
+            let value = serde_json::from_str(value)
+                .unwrap_or_else(|_| serde_json::Value::String(value.clone()));
+            (key.clone(), value)
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::tone_bytes;
+    use actix_web::{App, HttpServer};
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::json;
+    use tokio_tungstenite::tungstenite;
+
+    /// 100ms of a tone as 16-bit little-endian PCM
+    fn tone_frame(freq: f32, sample_rate: u32) -> Vec<u8> {
+        tone_bytes(freq, sample_rate, sample_rate / 10, 0.5)
+    }
+
+    #[actix_web::test]
+    async fn test_stream_returns_notes_per_frame() {
+        let server =
+            HttpServer::new(|| App::new().route("/analyze/stream", web::get().to(analyze_stream)))
+                .workers(1)
+                .bind(("127.0.0.1", 0))
+                .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let url = format!("ws://{addr}/analyze/stream?sample_rate=48000&window_ms=500");
+        let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        for frame_idx in 0..3 {
+            ws.send(tungstenite::Message::binary(tone_frame(440.0, 48000)))
+                .await
+                .unwrap();
+
+            let reply = loop {
+                match ws.next().await.unwrap().unwrap() {
+                    tungstenite::Message::Text(text) => break text,
+                    _ => continue,
+                }
+            };
+            let result: AnalysisResult = serde_json::from_str(reply.as_str()).unwrap();
+
+            // The server accumulates frames into its own window
+            assert_eq!(result.samples_analyzed, 4800 * (frame_idx + 1));
+            assert!(result.notes.iter().any(|n| n.note == "A4"));
+        }
+
+        ws.close(None).await.unwrap();
+        handle.stop(true).await;
+    }
+
+    #[actix_web::test]
+    async fn test_reset_command_starts_a_fresh_window() {
+        let server =
+            HttpServer::new(|| App::new().route("/analyze/stream", web::get().to(analyze_stream)))
+                .workers(1)
+                .bind(("127.0.0.1", 0))
+                .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let url = format!("ws://{addr}/analyze/stream?sample_rate=48000&window_ms=1000");
+        let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        let mut results = Vec::new();
+        for (freq, reset) in [(440.0, false), (440.0, false), (659.26, true)] {
+            if reset {
+                ws.send(tungstenite::Message::text(r#"{"cmd": "reset"}"#))
+                    .await
+                    .unwrap();
+            }
+            ws.send(tungstenite::Message::binary(tone_frame(freq, 48000)))
+                .await
+                .unwrap();
+            let reply = loop {
+                match ws.next().await.unwrap().unwrap() {
+                    tungstenite::Message::Text(text) => break text,
+                    _ => continue,
+                }
+            };
+            results.push(serde_json::from_str::<AnalysisResult>(reply.as_str()).unwrap());
+        }
+
+        assert_eq!(results[1].samples_analyzed, 9600);
+        // After the reset only the E5 frame is in the window: no A4 left over
+        assert_eq!(results[2].samples_analyzed, 4800);
+        let notes: Vec<_> = results[2].notes.iter().map(|n| n.note.as_str()).collect();
+        assert!(notes.contains(&"E5"), "{notes:?}");
+        assert!(!notes.contains(&"A4"), "{notes:?}");
+
+        // Anything else is an error reply, and the connection stays up
+        ws.send(tungstenite::Message::text(r#"{"cmd": "rewind"}"#))
+            .await
+            .unwrap();
+        let error: serde_json::Value = loop {
+            if let tungstenite::Message::Text(text) = ws.next().await.unwrap().unwrap() {
+                break serde_json::from_str(text.as_str()).unwrap();
+            }
+        };
+        assert_eq!(error["code"], "invalid_request");
+
+        ws.close(None).await.unwrap();
+        handle.stop(true).await;
+    }
+
+    #[test]
+    fn test_stream_session_keeps_window() {
+        let config: StreamConfig =
+            serde_json::from_value(json!({"sample_rate": 1000, "window_ms": 100})).unwrap();
+        let mut session = StreamSession::new(&config);
+
+        // 100ms @ 1kHz = 100 samples = 200 bytes
+        session.push(&[0u8; 150]).unwrap();
+        session.push(&[1u8; 150]).unwrap();
+        assert_eq!(session.buffer.len(), 200);
+        // Oldest 100 bytes were dropped: 50 zeros remain ahead of the newer frame
+        assert!(session.buffer[..50].iter().all(|&b| b == 0));
+        assert!(session.buffer[50..].iter().all(|&b| b == 1));
+
+        assert!(session.push(&[0u8; 3]).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use actix_multipart::Multipart;
+use actix_web::{web, HttpResponse};
+use futures_util::StreamExt;
+
+use crate::{
+    endpoints::{analyze::build_result, check_sample_rate},
+    errors::ApiError,
+    models::AnalysisParams,
+    wav::decode_wav,
+    ANALYZER,
+};
+
+/// Largest accepted upload; fixed, unlike the JSON limit (`RECOGNOTES_MAX_JSON_MB` doesn't apply)
+const MAX_UPLOAD_BYTES: usize = 16 * 1024 * 1024;
+
+/// WAV upload endpoint - analyzes a `.wav` file sent as multipart form data
+/// The first file field is used; analysis settings come from the query string
+/// e.g. `POST /analyze/wav?profile=bass&max_notes=3`
+pub async fn analyze_wav(
+    mut payload: Multipart,
+    params: web::Query<AnalysisParams>,
+) -> Result<HttpResponse, ApiError> {
+    let request_start = std::time::Instant::now();
+
+    let file_bytes = read_first_file(&mut payload)
+        .await?
+        .ok_or(ApiError::MissingFile)?;
+
+    let wav = decode_wav(&file_bytes).map_err(|e| {
+        log::error!("WAV upload rejected: {e}");
+        ApiError::DecodeFailed(e)
+    })?;
+
+    check_sample_rate(wav.sample_rate)?;
+
+    let options = params.analysis_options();
+    let analysis = ANALYZER.analyze_samples(&wav.samples, wav.sample_rate, &options);
+    let result = build_result(analysis, &options, wav.sample_rate);
+
+    log::info!(
+        "WAV: bytes={}, channels={}, bits={}, sample_rate={}, samples={}, TOTAL={}ms",
+        file_bytes.len(),
+        wav.channels,
+        wav.bits_per_sample,
+        wav.sample_rate,
+        wav.samples.len(),
+        request_start.elapsed().as_millis()
+    );
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Collect the bytes of the first file field (a field with a filename, or named "file")
+async fn read_first_file(payload: &mut Multipart) -> Result<Option<Vec<u8>>, ApiError> {
+    let multipart_error = |e| ApiError::InvalidRequest(format!("Multipart error: {e}"));
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(multipart_error)?;
+
+        let is_file = field
+            .content_disposition()
+            .is_some_and(|cd| cd.get_filename().is_some() || cd.get_name() == Some("file"));
+        if !is_file {
+            // Drain non-file fields so the parser can move on
+            while let Some(chunk) = field.next().await {
+                chunk.map_err(multipart_error)?;
+            }
+            continue;
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(multipart_error)?;
+            if bytes.len() + chunk.len() > MAX_UPLOAD_BYTES {
+                return Err(ApiError::BufferTooLarge {
+                    message: format!(
+                        "File exceeds the {}MB upload limit",
+                        MAX_UPLOAD_BYTES / (1024 * 1024)
+                    ),
+                    max_bytes: MAX_UPLOAD_BYTES,
+                });
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        return Ok(Some(bytes));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AnalysisResult;
+    use actix_web::{http::header, test as actix_test, App};
+    use hound::{SampleFormat, WavSpec, WavWriter};
+    use std::io::Cursor;
+
+    const BOUNDARY: &str = "recognotes-test-boundary";
+
+    fn multipart_body(file: &[u8]) -> Vec<u8> {
+        let mut body = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a4.wav\"\r\nContent-Type: audio/wav\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(file);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+        body
+    }
+
+    #[actix_web::test]
+    async fn test_wav_upload_detects_a4() {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let mut writer = WavWriter::new(&mut cursor, spec).unwrap();
+            for i in 0..22050 {
+                let t = i as f32 / 44100.0;
+                let sample = 0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin();
+                writer.write_sample((sample * 32767.0) as i16).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+
+        let app =
+            actix_test::init_service(App::new().route("/analyze/wav", web::post().to(analyze_wav)))
+                .await;
+
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze/wav")
+            .insert_header((
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            ))
+            .set_payload(multipart_body(cursor.get_ref()))
+            .to_request();
+        let result: AnalysisResult = actix_test::call_and_read_body_json(&app, req).await;
+        assert_eq!(result.sample_rate, 44100);
+        assert_eq!(result.samples_analyzed, 22050);
+        assert!(result.notes.iter().any(|n| n.note == "A4"));
+
+        // Not a WAV file
+        let req = actix_test::TestRequest::post()
+            .uri("/analyze/wav")
+            .insert_header((
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            ))
+            .set_payload(multipart_body(b"definitely not RIFF"))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,172 @@
+use actix_web::{
+    http::{header, StatusCode},
+    HttpResponse, ResponseError,
+};
+use serde_json::json;
+
+use crate::endpoints::{MAX_SAMPLE_RATE, MIN_SAMPLE_RATE};
+
+/// Errors returned by the API
+/// Each maps to an HTTP status and a stable machine-readable `code`; bodies look like
+/// `{"error": "sample_rate must be between 8000 and 192000 Hz", "code": "invalid_sample_rate"}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    /// `sample_rate` missing or outside `MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE`
+    InvalidSampleRate,
+    /// Audio that can't be decoded: bad base64, a PCM length that isn't whole frames, a broken WAV
+    DecodeFailed(String),
+    /// Request body over the size limit
+    BufferTooLarge { message: String, max_bytes: usize },
+    /// Malformed JSON body, query string or multipart form
+    InvalidRequest(String),
+    /// A well-formed parameter outside its accepted range
+    InvalidParameter(String),
+    /// Multipart upload without a file field
+    MissingFile,
+    /// An API key is configured and the request didn't present it
+    Unauthorized,
+    /// Server-side failure that isn't the client's fault
+    Internal(String),
+}
+
+impl ApiError {
+    /// Stable identifier clients can match on (messages may change, codes don't)
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidSampleRate => "invalid_sample_rate",
+            Self::DecodeFailed(_) => "decode_failed",
+            Self::BufferTooLarge { .. } => "buffer_too_large",
+            Self::InvalidRequest(_) => "invalid_request",
+            Self::InvalidParameter(_) => "invalid_parameter",
+            Self::MissingFile => "missing_file",
+            Self::Unauthorized => "unauthorized",
+            Self::Internal(_) => "internal_error",
+        }
+    }
+
+    /// JSON error body (also sent as a text message on the WebSocket stream)
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut body = json!({ "error": self.to_string(), "code": self.code() });
+        if let Self::BufferTooLarge { max_bytes, .. } = self {
+            body["max_bytes"] = json!(max_bytes);
+        }
+        body
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidSampleRate => write!(
+                f,
+                "sample_rate must be between {MIN_SAMPLE_RATE} and {MAX_SAMPLE_RATE} Hz"
+            ),
+            Self::MissingFile => write!(f, "No file field found in multipart body"),
+            Self::Unauthorized => write!(
+                f,
+                "Missing or invalid API key (send Authorization: Bearer <key>)"
+            ),
+            Self::DecodeFailed(message)
+            | Self::BufferTooLarge { message, .. }
+            | Self::InvalidRequest(message)
+            | Self::InvalidParameter(message)
+            | Self::Internal(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidSampleRate
+            | Self::DecodeFailed(_)
+            | Self::InvalidRequest(_)
+            | Self::InvalidParameter(_)
+            | Self::MissingFile => StatusCode::BAD_REQUEST,
+            Self::BufferTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if matches!(self, Self::Unauthorized) {
+            response.insert_header((header::WWW_AUTHENTICATE, "Bearer"));
+        }
+        response.json(self.to_json())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::MessageBody;
+
+    #[test]
+    fn test_variants_map_to_status_and_code() {
+        let cases = [
+            (
+                ApiError::InvalidSampleRate,
+                StatusCode::BAD_REQUEST,
+                "invalid_sample_rate",
+            ),
+            (
+                ApiError::DecodeFailed("bad base64".into()),
+                StatusCode::BAD_REQUEST,
+                "decode_failed",
+            ),
+            (
+                ApiError::BufferTooLarge {
+                    message: "too big".into(),
+                    max_bytes: 16,
+                },
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "buffer_too_large",
+            ),
+            (
+                ApiError::InvalidRequest("not JSON".into()),
+                StatusCode::BAD_REQUEST,
+                "invalid_request",
+            ),
+            (
+                ApiError::InvalidParameter("hop_ms".into()),
+                StatusCode::BAD_REQUEST,
+                "invalid_parameter",
+            ),
+            (
+                ApiError::MissingFile,
+                StatusCode::BAD_REQUEST,
+                "missing_file",
+            ),
+            (
+                ApiError::Unauthorized,
+                StatusCode::UNAUTHORIZED,
+                "unauthorized",
+            ),
+            (
+                ApiError::Internal("lock".into()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+            ),
+        ];
+
+        for (error, status, code) in cases {
+            let response = error.error_response();
+            assert_eq!(response.status(), status, "{error:?}");
+
+            let body = response.into_body().try_into_bytes().unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(body["code"], code);
+            assert_eq!(body["error"], error.to_string());
+        }
+
+        let too_large = ApiError::BufferTooLarge {
+            message: "too big".into(),
+            max_bytes: 16,
+        };
+        assert_eq!(too_large.to_json()["max_bytes"], 16);
+    }
+}
@@ -1,11 +1,32 @@
-mod audio_analyzer;
+mod auth;
+mod cli;
+mod config;
+mod cors;
 mod endpoints;
-mod models;
-mod utils;
+mod errors;
+mod metrics;
+mod request_id;
+mod resample;
+mod tls;
+mod wav;
 
-use actix_web::{error, web, App, HttpResponse, HttpServer};
+// The DSP lives in recognotes-core; the server is a thin HTTP layer over it
+use recognotes_core::{attack, audio_analyzer, chord, models, utils, vibrato};
+
+use actix_web::{
+    dev::Service,
+    error,
+    error::JsonPayloadError,
+    middleware::{from_fn, Compress},
+    web, App, HttpServer, ResponseError,
+};
 use audio_analyzer::AudioAnalyzer;
+use clap::Parser;
+use errors::ApiError;
+use metrics::LatencyHistogram;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
+use std::time::Instant;
 
 // Export for use in endpoints module
 pub use models::{AnalysisResult, AudioData, DetectedNote};
@@ -14,49 +35,152 @@ pub use models::{AnalysisResult, AudioData, DetectedNote};
 pub static ANALYZER: std::sync::LazyLock<AudioAnalyzer> =
     std::sync::LazyLock::new(AudioAnalyzer::new);
 
-// In-memory storage for analysis results
+// In-memory storage for analysis results and server stats
 pub struct AppState {
     pub last_result: Mutex<Option<AnalysisResult>>,
+    pub started_at: Instant,
+    pub requests_served: AtomicU64,
+    /// Source of generated `X-Request-Id`s
+    pub next_request_id: AtomicU64,
+    /// Whole `/analyze` handler time
+    pub analyze_latency: Mutex<LatencyHistogram>,
+    /// FFT and note extraction only (excludes decoding, resampling and serialization)
+    pub analysis_latency: Mutex<LatencyHistogram>,
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+impl AppState {
+    pub fn new() -> Self {
+        Self {
+            last_result: Mutex::new(None),
+            started_at: Instant::now(),
+            requests_served: AtomicU64::new(0),
+            next_request_id: AtomicU64::new(1),
+            analyze_latency: Mutex::new(LatencyHistogram::default()),
+            analysis_latency: Mutex::new(LatencyHistogram::default()),
+        }
+    }
+}
 
-    let app_state = web::Data::new(AppState {
-        last_result: Mutex::new(None),
-    });
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let cli = cli::Cli::parse();
+    match cli.command {
+        // Keep stdout pure JSON for scripts: only warnings and errors get logged
+        Some(cli::Command::Analyze(args)) => {
+            env_logger::init_from_env(env_logger::Env::new().default_filter_or("warn"));
+            std::process::exit(cli::run_analyze(&args));
+        }
+        None => {
+            env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+            // Plain HTTP unless a certificate and key were given
+            let tls = cli
+                .tls
+                .paths()
+                .map(|(cert, key)| tls::load_server_config(cert, key))
+                .transpose()?;
+            actix_web::rt::System::new().block_on(serve(tls))
+        }
+    }
+}
 
-    log::info!("Starting RecogNotes Rust Backend on http://127.0.0.1:5000");
+async fn serve(tls: Option<rustls::ServerConfig>) -> std::io::Result<()> {
+    let app_state = web::Data::new(AppState::new());
+    let allowed_origins = cors::AllowedOrigins::from_env();
+    let api_key = web::Data::new(auth::ApiKey::from_env());
+    let config = config::ServerConfig::from_env();
+
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    log::info!(
+        "Starting RecogNotes Rust Backend on {scheme}://{}",
+        config.socket_addr()
+    );
     log::info!("Audio analysis with FFT-based pitch detection enabled");
-    log::info!("Max payload size: 16MB, Workers: 8, No request timeout");
+    let max_json_bytes = config.max_json_bytes;
+    log::info!(
+        "Max payload size: {}MB, Workers: {}, No request timeout",
+        max_json_bytes / (1024 * 1024),
+        config.workers
+    );
+    log::info!("CORS allowed origins: {allowed_origins:?}");
+    if api_key.is_required() {
+        log::info!("API key required (health and version stay public)");
+    }
 
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
             .app_data(app_state.clone())
-            // Increase JSON payload limit to 16MB to handle larger audio chunks
+            .app_data(api_key.clone())
+            // JSON payload limit (16MB unless RECOGNOTES_MAX_JSON_MB says otherwise)
             .app_data(
                 web::JsonConfig::default()
-                    .limit(16 * 1024 * 1024) // 16MB limit
-                    .error_handler(|err, _req| {
-                        let err_msg = format!("{err}");
-                        log::error!("JSON parsing error: {err_msg}");
-                        error::InternalError::from_response(
-                        err,
-                        HttpResponse::BadRequest().json(
-                            serde_json::json!({"error": format!("JSON parse error: {}", err_msg)})
-                        )
-                    ).into()
+                    .limit(max_json_bytes)
+                    .error_handler(move |err, _req| {
+                        log::error!("JSON parsing error: {err}");
+                        // Oversized bodies get a 413 with the limit, so clients know how to split
+                        let api_error = match err {
+                            JsonPayloadError::OverflowKnownLength { .. }
+                            | JsonPayloadError::Overflow { .. } => ApiError::BufferTooLarge {
+                                message: format!(
+                                    "Payload too large: {err}; split the audio, or POST raw PCM to /analyze/raw"
+                                ),
+                                max_bytes: max_json_bytes,
+                            },
+                            _ => ApiError::InvalidRequest(format!("JSON parse error: {err}")),
+                        };
+                        error::InternalError::from_response(err, api_error.error_response()).into()
                     }),
             )
+            // Malformed query strings (e.g. /analyze/raw?sample_rate=fast) get the same error body
+            .app_data(web::QueryConfig::default().error_handler(|err, _req| {
+                let api_error = ApiError::InvalidRequest(format!("Query parse error: {err}"));
+                error::InternalError::from_response(err, api_error.error_response()).into()
+            }))
+            // Only callers with RECOGNOTES_API_KEY get past this (when it's set)
+            .wrap(from_fn(auth::require_api_key))
             // DISABLED: Logger middleware was causing 2-second delay!
             // .wrap(middleware::Logger::default())
+            // Count every request for /health (a relaxed atomic add, no locking)
+            .wrap_fn(|req, srv| {
+                if let Some(state) = req.app_data::<web::Data<AppState>>() {
+                    state.requests_served.fetch_add(1, Ordering::Relaxed);
+                }
+                srv.call(req)
+            })
+            // Outermost, so preflights are answered before anything else runs
+            .wrap(cors::cors(&allowed_origins))
             .route("/health", web::get().to(endpoints::health))
+            .route("/version", web::get().to(endpoints::version))
+            .route("/metrics", web::get().to(endpoints::metrics))
             .route("/analyze", web::post().to(endpoints::analyze_audio))
+            // Big timelines/spectra are worth gzipping; the per-chunk /analyze hot path isn't
+            .service(
+                web::resource("/analyze/batch")
+                    .wrap(Compress::default())
+                    .route(web::post().to(endpoints::analyze_batch)),
+            )
+            .route("/analyze/stream", web::get().to(endpoints::analyze_stream))
+            .route("/analyze/wav", web::post().to(endpoints::analyze_wav))
+            .route("/analyze/raw", web::post().to(endpoints::analyze_raw))
             .route("/last-result", web::get().to(endpoints::get_last_result))
+            .route("/note", web::get().to(endpoints::lookup_note))
+            .route("/profiles", web::get().to(endpoints::list_profiles))
+            .service(
+                web::resource("/spectrum")
+                    .wrap(Compress::default())
+                    .route(web::post().to(endpoints::analyze_spectrum)),
+            )
     })
-    .workers(8) // Increase worker threads for parallel processing
-    .bind("127.0.0.1:5000")?
+    .workers(config.workers);
+
+    match tls {
+        Some(tls) => server.bind_rustls_0_23(config.socket_addr(), tls)?,
+        None => server.bind(config.socket_addr())?,
+    }
     .run()
     .await
 }
@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+/// Latency samples kept for percentiles (older samples roll off)
+pub const LATENCY_WINDOW: usize = 1024;
+
+/// Rolling window of recent request latencies
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+    samples_ms: VecDeque<f64>,
+}
+
+/// Percentiles over the current window, in milliseconds
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyHistogram {
+    /// Record one latency, evicting the oldest once the window is full
+    pub fn record(&mut self, latency_ms: f64) {
+        if self.samples_ms.len() == LATENCY_WINDOW {
+            self.samples_ms.pop_front();
+        }
+        self.samples_ms.push_back(latency_ms);
+    }
+
+    /// Nearest-rank percentiles of the window (all zero when empty)
+    pub fn summary(&self) -> LatencySummary {
+        let mut sorted: Vec<f64> = self.samples_ms.iter().copied().collect();
+        if sorted.is_empty() {
+            return LatencySummary::default();
+        }
+        sorted.sort_by(f64::total_cmp);
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let percentile = |p: f64| {
+            let rank = (p * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        LatencySummary {
+            count: sorted.len(),
+            p50_ms: percentile(0.50),
+            p90_ms: percentile(0.90),
+            p95_ms: percentile(0.95),
+            p99_ms: percentile(0.99),
+            max_ms: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_over_rolling_window() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.summary(), LatencySummary::default());
+
+        for ms in 1..=100 {
+            histogram.record(f64::from(ms));
+        }
+        let summary = histogram.summary();
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.p50_ms, 50.0);
+        assert_eq!(summary.p90_ms, 90.0);
+        assert_eq!(summary.p95_ms, 95.0);
+        assert_eq!(summary.p99_ms, 99.0);
+        assert_eq!(summary.max_ms, 100.0);
+
+        // Old samples roll off once the window is full
+        for _ in 0..LATENCY_WINDOW {
+            histogram.record(5.0);
+        }
+        let summary = histogram.summary();
+        assert_eq!(summary.count, LATENCY_WINDOW);
+        assert_eq!(summary.max_ms, 5.0);
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use actix_web::{
+    http::header::{HeaderName, HeaderValue},
+    HttpRequest, HttpResponse, ResponseError,
+};
+
+use crate::errors::ApiError;
+
+/// Header carrying the ID that ties a response to its log lines
+/// A client-supplied ID is kept, so one ID can follow a request across services
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Longer incoming IDs are replaced rather than echoed into every log line
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// ID for this request: the client's `X-Request-Id` if it sent a usable one,
+/// otherwise the next value of `counter` in hex (e.g. "00002a")
+pub fn request_id(req: &HttpRequest, counter: &AtomicU64) -> String {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.chars().all(|c| c.is_ascii_graphic())
+        })
+        .map_or_else(
+            || format!("{:06x}", counter.fetch_add(1, Ordering::Relaxed)),
+            str::to_string,
+        )
+}
+
+/// Turn a handler result into the response, tagged with `X-Request-Id`
+/// Errors are logged with the ID too, so failed requests are as traceable as successful ones
+pub fn respond_with_id(result: Result<HttpResponse, ApiError>, request_id: &str) -> HttpResponse {
+    let mut response = result.unwrap_or_else(|e| {
+        log::warn!("[{request_id}] {e}");
+        e.error_response()
+    });
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static("x-request-id"), value);
+    }
+    response
+}
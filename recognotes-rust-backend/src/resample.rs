@@ -0,0 +1,90 @@
+/// Lowest and highest internal analysis rates a client may request
+pub const MIN_TARGET_RATE: u32 = 8_000;
+pub const MAX_TARGET_RATE: u32 = 192_000;
+/// Cap on resampler output so upsampling can't blow up memory (~3 minutes @ 48kHz)
+pub const MAX_RESAMPLED_SAMPLES: usize = 8 * 1024 * 1024;
+
+/// Resample 16-bit PCM from `from_rate` to `to_rate` with linear interpolation
+///
+/// Linear interpolation is cheap and keeps pitch exact; it doesn't low-pass before
+/// downsampling, which is acceptable for the moderate ratios used here (e.g. 48k -> 44.1k)
+///
+/// # Errors
+/// Returns an error for a zero rate or when the output would exceed `MAX_RESAMPLED_SAMPLES`
+pub fn resample_linear(samples: &[i16], from_rate: u32, to_rate: u32) -> Result<Vec<i16>, String> {
+    if from_rate == 0 || to_rate == 0 {
+        return Err("sample rates must be greater than 0".to_string());
+    }
+    if from_rate == to_rate || samples.is_empty() {
+        return Ok(samples.to_vec());
+    }
+
+    let output_len = resampled_len(samples.len(), from_rate, to_rate);
+    if output_len > MAX_RESAMPLED_SAMPLES {
+        return Err(format!(
+            "Resampling {} samples from {from_rate}Hz to {to_rate}Hz would produce {output_len} samples (max {MAX_RESAMPLED_SAMPLES})",
+            samples.len()
+        ));
+    }
+
+    let step = f64::from(from_rate) / f64::from(to_rate);
+    let last = samples.len() - 1;
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let resampled = (0..output_len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let idx = (position as usize).min(last);
+            let frac = position - idx as f64;
+            let a = f64::from(samples[idx]);
+            let b = f64::from(samples[(idx + 1).min(last)]);
+            frac.mul_add(b - a, a).round() as i16
+        })
+        .collect();
+
+    Ok(resampled)
+}
+
+/// Number of output samples for a buffer of `len` samples converted between rates
+#[allow(clippy::cast_possible_truncation)]
+const fn resampled_len(len: usize, from_rate: u32, to_rate: u32) -> usize {
+    ((len as u64 * to_rate as u64) / from_rate as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{audio_analyzer::AudioAnalyzer, models::AnalysisOptions};
+
+    #[test]
+    fn test_resample_48k_to_44k_keeps_pitch() {
+        let samples: Vec<i16> = (0..24000)
+            .map(|i| {
+                let t = i as f32 / 48000.0;
+                ((2.0 * std::f32::consts::PI * 440.0 * t).sin() * 16000.0) as i16
+            })
+            .collect();
+
+        let resampled = resample_linear(&samples, 48000, 44100).unwrap();
+        assert_eq!(resampled.len(), 22050);
+
+        let bytes: Vec<u8> = resampled.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let notes = AudioAnalyzer::new()
+            .analyze_raw_bytes(&bytes, 44100, &AnalysisOptions::default())
+            .notes;
+        assert!(notes.iter().any(|(note, ..)| note == "A4"), "{notes:?}");
+    }
+
+    #[test]
+    fn test_resample_guards() {
+        assert!(resample_linear(&[1, 2, 3], 0, 44100).is_err());
+        assert_eq!(
+            resample_linear(&[1, 2, 3], 44100, 44100).unwrap(),
+            vec![1, 2, 3]
+        );
+
+        // Upsampling 24 seconds of 8kHz audio by 24000x would be far beyond the cap
+        let long = vec![0i16; 192_000];
+        assert!(resample_linear(&long, 8_000, 192_000 * 1000).is_err());
+    }
+}
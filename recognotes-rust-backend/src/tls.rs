@@ -0,0 +1,113 @@
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+
+/// Env var with the PEM certificate chain to serve HTTPS with (`--tls-cert` overrides it)
+pub const TLS_CERT_ENV: &str = "RECOGNOTES_TLS_CERT";
+/// Env var with the PEM private key matching `RECOGNOTES_TLS_CERT` (`--tls-key` overrides it)
+pub const TLS_KEY_ENV: &str = "RECOGNOTES_TLS_KEY";
+
+/// Build the rustls server config from a PEM certificate chain (leaf first) and its private key
+/// (PKCS#8, PKCS#1 or SEC1). Errors name the offending file, so a typo fails at startup
+pub fn load_server_config(cert_path: &Path, key_path: &Path) -> io::Result<rustls::ServerConfig> {
+    let invalid = |path: &Path, message: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: {message}", path.display()),
+        )
+    };
+
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+        .map_err(|e| invalid(cert_path, e.to_string()))?;
+    if certs.is_empty() {
+        return Err(invalid(cert_path, "no certificate found".into()));
+    }
+    let key =
+        PrivateKeyDer::from_pem_file(key_path).map_err(|e| invalid(key_path, e.to_string()))?;
+
+    // Pick the provider explicitly rather than relying on a process-wide default
+    rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| invalid(key_path, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpServer};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Write a self-signed `localhost` certificate and its key to per-test temp files
+    fn self_signed(name: &str) -> (rcgen::CertifiedKey, std::path::PathBuf, std::path::PathBuf) {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let dir = std::env::temp_dir();
+        let prefix = format!("recognotes-tls-{}-{name}", std::process::id());
+        let cert_path = dir.join(format!("{prefix}-cert.pem"));
+        let key_path = dir.join(format!("{prefix}-key.pem"));
+        std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+        std::fs::write(&key_path, certified.key_pair.serialize_pem()).unwrap();
+        (certified, cert_path, key_path)
+    }
+
+    #[actix_web::test]
+    async fn test_https_handshake_with_self_signed_cert() {
+        let (certified, cert_path, key_path) = self_signed("handshake");
+        let config = load_server_config(&cert_path, &key_path);
+        std::fs::remove_file(&cert_path).unwrap();
+        std::fs::remove_file(&key_path).unwrap();
+
+        let server = HttpServer::new(|| App::new().route("/", web::get().to(|| async { "hello" })))
+            .workers(1)
+            .bind_rustls_0_23(("127.0.0.1", 0), config.unwrap())
+            .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        // A client that trusts only our certificate
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(certified.cert.der().clone()).unwrap();
+        let client = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client));
+
+        let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut tls = connector.connect(server_name, tcp).await.unwrap();
+        tls.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        tls.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("hello"), "{response}");
+
+        handle.stop(true).await;
+    }
+
+    #[test]
+    fn test_bad_cert_or_key_is_reported() {
+        let (_, cert_path, key_path) = self_signed("bad");
+        let missing = std::env::temp_dir().join("recognotes-tls-does-not-exist.pem");
+
+        let err = load_server_config(&missing, &key_path).unwrap_err();
+        assert!(err.to_string().contains("does-not-exist"), "{err}");
+        // The key file holds no certificate, and the certificate no key
+        assert!(load_server_config(&key_path, &key_path).is_err());
+        assert!(load_server_config(&cert_path, &cert_path).is_err());
+        assert!(load_server_config(&cert_path, &key_path).is_ok());
+
+        std::fs::remove_file(&cert_path).unwrap();
+        std::fs::remove_file(&key_path).unwrap();
+    }
+}
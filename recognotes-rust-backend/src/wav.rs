@@ -0,0 +1,96 @@
+use hound::WavReader;
+use recognotes_core::wav::wav_samples;
+use std::io::Cursor;
+
+use crate::utils::{downmix_to_mono, sanitize_samples};
+
+/// WAV file decoded to the analyzer's internal representation
+#[derive(Debug, Clone)]
+pub struct DecodedWav {
+    /// Mono samples in -1.0..1.0 (multi-channel files are averaged)
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    /// Channel count and bit depth of the original file (for logging)
+    pub channels: u16,
+    pub bits_per_sample: u16,
+}
+
+/// Decode a RIFF/WAV file and downmix it to mono f32
+/// Supports 16-bit and 24-bit integer PCM, plus 32-bit float
+///
+/// # Errors
+/// Returns an error for malformed files and unsupported encodings (compressed, 8-bit, ...)
+pub fn decode_wav(bytes: &[u8]) -> Result<DecodedWav, String> {
+    let reader = WavReader::new(Cursor::new(bytes)).map_err(|e| format!("Invalid WAV: {e}"))?;
+    let spec = reader.spec();
+
+    if spec.channels == 0 {
+        return Err("Invalid WAV: zero channels".to_string());
+    }
+
+    let interleaved: Vec<f32> = wav_samples(reader)?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("WAV decode error: {e}"))?;
+
+    let mut samples = downmix_to_mono(interleaved, usize::from(spec.channels));
+    // Float files can carry NaN/infinity; analyze those samples as silence
+    sanitize_samples(&mut samples);
+
+    Ok(DecodedWav {
+        samples,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        bits_per_sample: spec.bits_per_sample,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hound::{SampleFormat, WavSpec, WavWriter};
+
+    #[test]
+    fn test_decode_24bit_stereo_downmix() {
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 44100,
+            bits_per_sample: 24,
+            sample_format: SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let mut writer = WavWriter::new(&mut cursor, spec).unwrap();
+            // Left at half scale, right silent -> mono at quarter scale
+            for _ in 0..100 {
+                writer.write_sample(1 << 22).unwrap();
+                writer.write_sample(0).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+
+        let decoded = decode_wav(cursor.get_ref()).unwrap();
+        assert_eq!(decoded.sample_rate, 44100);
+        assert_eq!(decoded.samples.len(), 100);
+        assert!(decoded.samples.iter().all(|&s| (s - 0.25).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_rejects_unsupported_depth() {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 8,
+            sample_format: SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let mut writer = WavWriter::new(&mut cursor, spec).unwrap();
+            writer.write_sample(0i8).unwrap();
+            writer.finalize().unwrap();
+        }
+
+        let err = decode_wav(cursor.get_ref()).unwrap_err();
+        assert!(err.contains("Unsupported"), "{err}");
+        assert!(decode_wav(b"not a wav").is_err());
+    }
+}
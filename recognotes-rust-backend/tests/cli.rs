@@ -0,0 +1,69 @@
+//! End-to-end tests of the `analyze` subcommand, run against the built binary
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+const BIN: &str = env!("CARGO_BIN_EXE_recognotes-rust-backend");
+
+/// Write a quarter second of a 16-bit mono sine to a per-test temp file
+fn write_sine_wav(name: &str, freq: f32) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("recognotes-cli-{}-{name}.wav", std::process::id()));
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: 44100,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let mut writer = WavWriter::create(&path, spec).unwrap();
+    for i in 0..11025 {
+        let t = i as f32 / 44100.0;
+        let sample = 0.5 * (2.0 * std::f32::consts::PI * freq * t).sin();
+        writer.write_sample((sample * 32767.0) as i16).unwrap();
+    }
+    writer.finalize().unwrap();
+    path
+}
+
+#[test]
+fn test_analyze_prints_notes_as_json() {
+    let path = write_sine_wav("a4", 440.0);
+    let output = Command::new(BIN)
+        .arg("analyze")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success(), "{output:?}");
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["notes"][0]["note"], "A4");
+    assert_eq!(result["sample_rate"], 44100);
+}
+
+#[test]
+fn test_analyze_fails_on_bad_input() {
+    let missing = std::env::temp_dir().join("recognotes-cli-does-not-exist.wav");
+    let output = Command::new(BIN)
+        .arg("analyze")
+        .arg(&missing)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+
+    // Not a WAV at all
+    let garbage =
+        std::env::temp_dir().join(format!("recognotes-cli-{}-garbage.wav", std::process::id()));
+    std::fs::write(&garbage, b"definitely not RIFF").unwrap();
+    let output = Command::new(BIN)
+        .arg("analyze")
+        .arg(&garbage)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&garbage).unwrap();
+    assert!(!output.status.success());
+}